@@ -22,6 +22,9 @@ fn create_item(cwd: &str, cmd: &str, exit_status: i64) -> reedline::HistoryItem
         duration: Some(Duration::from_millis(1000)),
         exit_status: Some(exit_status),
         more_info: None,
+        tags: vec![],
+        note: None,
+        bookmarked: false,
     }
 }
 
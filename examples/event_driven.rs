@@ -0,0 +1,58 @@
+// Drive reedline from a host event loop instead of blocking in `read_line`.
+// The loop multiplexes terminal input with a timer that prints notifications.
+// to run:
+// cargo run --example event_driven
+
+use {
+    crossterm::event,
+    reedline::{DefaultPrompt, Reedline, ReedlineRawEvent, Signal},
+    std::io,
+    std::time::{Duration, Instant},
+};
+
+fn main() -> io::Result<()> {
+    let mut line_editor = Reedline::create();
+    let prompt = DefaultPrompt::default();
+
+    let tick = Duration::from_secs(3);
+    let mut next_tick = Instant::now() + tick;
+    let mut ticks = 0;
+
+    loop {
+        line_editor.begin_read_line(&prompt)?;
+        let signal = loop {
+            let timeout = next_tick.saturating_duration_since(Instant::now());
+            if !event::poll(timeout)? {
+                ticks += 1;
+                line_editor
+                    .print_external_message(&prompt, &format!("Timer fired {ticks} times"))?;
+                next_tick = Instant::now() + tick;
+                continue;
+            }
+
+            // Collect everything that is already queued so pastes are handled in one go
+            let mut events = vec![];
+            loop {
+                if let Ok(event) = ReedlineRawEvent::try_from(event::read()?) {
+                    events.push(event);
+                }
+                if !event::poll(Duration::ZERO)? {
+                    break;
+                }
+            }
+            if let Some(signal) = line_editor.handle_raw_events(&prompt, events)? {
+                break signal;
+            }
+        };
+
+        match signal {
+            Signal::Success(buffer) => {
+                println!("We processed: {buffer}");
+            }
+            Signal::CtrlD | Signal::CtrlC => {
+                println!("\nAborted!");
+                break Ok(());
+            }
+        }
+    }
+}
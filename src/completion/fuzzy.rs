@@ -0,0 +1,311 @@
+use crate::{menu_functions::floor_char_boundary, Suggestion};
+use unicode_segmentation::UnicodeSegmentation;
+
+// Scores loosely follow the ones used by fzf
+const SCORE_MATCH: i32 = 16;
+const PENALTY_GAP_START: i32 = 3;
+const PENALTY_GAP_EXTENSION: i32 = 1;
+const BONUS_BOUNDARY: i32 = 8;
+const BONUS_CAMEL_CASE: i32 = 7;
+const BONUS_CONSECUTIVE: i32 = 4;
+// The bonus of the position of the first pattern character is multiplied by this
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+
+/// Result of a successful fuzzy match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// Quality of the match, higher is better
+    pub score: i32,
+    /// Indices of the graphemes of the candidate that matched the pattern
+    pub indices: Vec<usize>,
+}
+
+/// Fuzzy matcher that finds the characters of a pattern in order inside a candidate
+///
+/// Matches are scored higher if they start at word boundaries (after a separator or
+/// at a camel case hump) and if matched characters are consecutive. Gaps between
+/// matched characters lower the score.
+///
+/// With smart case (the default) the matching is case-insensitive unless the pattern
+/// contains an uppercase character.
+///
+/// # Example
+/// ```rust
+/// use reedline::FuzzyMatcher;
+///
+/// let matcher = FuzzyMatcher::default();
+/// let found = matcher.fuzzy_match("git checkout", "gco").unwrap();
+/// assert_eq!(found.indices, vec![0, 4, 9]);
+/// assert!(matcher.fuzzy_match("git checkout", "gcx").is_none());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FuzzyMatcher {
+    smart_case: bool,
+}
+
+impl Default for FuzzyMatcher {
+    fn default() -> Self {
+        Self { smart_case: true }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Separator,
+    Lower,
+    Upper,
+    Other,
+}
+
+fn char_class(grapheme: &str) -> CharClass {
+    match grapheme.chars().next() {
+        Some(c) if c.is_lowercase() => CharClass::Lower,
+        Some(c) if c.is_uppercase() => CharClass::Upper,
+        Some(c) if c.is_alphanumeric() => CharClass::Other,
+        _ => CharClass::Separator,
+    }
+}
+
+fn position_bonus(previous: CharClass, current: CharClass) -> i32 {
+    match (previous, current) {
+        (_, CharClass::Separator) => 0,
+        (CharClass::Separator, _) => BONUS_BOUNDARY,
+        (CharClass::Lower, CharClass::Upper) => BONUS_CAMEL_CASE,
+        _ => 0,
+    }
+}
+
+impl FuzzyMatcher {
+    /// Create a matcher with smart case enabled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A builder that enables or disables smart case
+    ///
+    /// Without smart case the matching is always case-insensitive.
+    #[must_use]
+    pub fn with_smart_case(mut self, smart_case: bool) -> Self {
+        self.smart_case = smart_case;
+        self
+    }
+
+    fn is_case_sensitive(&self, pattern: &str) -> bool {
+        self.smart_case && pattern.chars().any(char::is_uppercase)
+    }
+
+    /// Check if all characters of `pattern` appear in order in `candidate`
+    ///
+    /// Cheaper than [`FuzzyMatcher::fuzzy_match`] as no score is calculated.
+    pub fn is_match(&self, candidate: &str, pattern: &str) -> bool {
+        let case_sensitive = self.is_case_sensitive(pattern);
+        let mut candidate = candidate.graphemes(true);
+        pattern.graphemes(true).all(|expected| {
+            candidate.any(|grapheme| graphemes_equal(grapheme, expected, case_sensitive))
+        })
+    }
+
+    /// Find the best match of `pattern` in `candidate`
+    ///
+    /// Returns `None` if not all characters of the pattern appear in order.
+    /// An empty pattern matches everything with a score of zero.
+    pub fn fuzzy_match(&self, candidate: &str, pattern: &str) -> Option<FuzzyMatch> {
+        let pattern: Vec<&str> = pattern.graphemes(true).collect();
+        if pattern.is_empty() {
+            return Some(FuzzyMatch {
+                score: 0,
+                indices: vec![],
+            });
+        }
+        let case_sensitive = self.is_case_sensitive(&pattern.concat());
+        let candidate: Vec<&str> = candidate.graphemes(true).collect();
+
+        // Skip the scoring if the pattern can't be found at all
+        let mut remaining = candidate.iter();
+        if !pattern.iter().all(|expected| {
+            remaining.any(|grapheme| graphemes_equal(grapheme, expected, case_sensitive))
+        }) {
+            return None;
+        }
+
+        let mut previous_class = CharClass::Separator;
+        let bonuses: Vec<i32> = candidate
+            .iter()
+            .map(|grapheme| {
+                let class = char_class(grapheme);
+                let bonus = position_bonus(previous_class, class);
+                previous_class = class;
+                bonus
+            })
+            .collect();
+
+        // scores[i][j]: best score with the pattern up to `i` matched and `i` placed at `j`
+        // from[i][j]: position of pattern character `i - 1` for that score
+        let width = candidate.len();
+        let mut scores = vec![vec![None; width]; pattern.len()];
+        let mut from = vec![vec![0; width]; pattern.len()];
+
+        for (i, expected) in pattern.iter().enumerate() {
+            // Best score of the previous row that leaves a gap before `j`, with its position
+            let mut gap: Option<(i32, usize)> = None;
+            for j in i..width {
+                if i > 0 && j >= 2 {
+                    if let Some(previous) = scores[i - 1][j - 2] {
+                        let candidate_gap = previous - PENALTY_GAP_START;
+                        gap = match gap {
+                            Some((score, _)) if score - PENALTY_GAP_EXTENSION >= candidate_gap => {
+                                gap.map(|(score, at)| (score - PENALTY_GAP_EXTENSION, at))
+                            }
+                            _ => Some((candidate_gap, j - 2)),
+                        };
+                    } else {
+                        gap = gap.map(|(score, at)| (score - PENALTY_GAP_EXTENSION, at));
+                    }
+                }
+
+                if !graphemes_equal(candidate[j], expected, case_sensitive) {
+                    continue;
+                }
+                if i == 0 {
+                    scores[i][j] = Some(SCORE_MATCH + bonuses[j] * BONUS_FIRST_CHAR_MULTIPLIER);
+                    continue;
+                }
+
+                let consecutive =
+                    scores[i - 1][j - 1].map(|score| (score + BONUS_CONSECUTIVE, j - 1));
+                let best = match (consecutive, gap) {
+                    (Some(consecutive), Some(gap)) if gap.0 > consecutive.0 => Some(gap),
+                    (Some(consecutive), _) => Some(consecutive),
+                    (None, gap) => gap,
+                };
+                if let Some((score, at)) = best {
+                    scores[i][j] = Some(score + SCORE_MATCH + bonuses[j]);
+                    from[i][j] = at;
+                }
+            }
+        }
+
+        let last = pattern.len() - 1;
+        let (mut position, score) = scores[last]
+            .iter()
+            .enumerate()
+            .filter_map(|(j, score)| score.map(|score| (j, score)))
+            .max_by_key(|(j, score)| (*score, std::cmp::Reverse(*j)))?;
+
+        let mut indices = vec![0; pattern.len()];
+        for i in (0..pattern.len()).rev() {
+            indices[i] = position;
+            position = from[i][position];
+        }
+
+        Some(FuzzyMatch { score, indices })
+    }
+
+    /// Filter and rank suggestions by how well they match the text they replace
+    ///
+    /// The pattern of each suggestion is the part of `line` covered by its span. Suggestions
+    /// that don't match are removed, the others are sorted by descending score (keeping the
+    /// order of the completer for equal scores) and get their `match_indices` filled in.
+    pub fn rank_suggestions(&self, line: &str, suggestions: Vec<Suggestion>) -> Vec<Suggestion> {
+        let mut ranked: Vec<(i32, Suggestion)> = suggestions
+            .into_iter()
+            .filter_map(|mut suggestion| {
+                let end = floor_char_boundary(line, suggestion.span.end);
+                let start = floor_char_boundary(line, suggestion.span.start).min(end);
+                let found = self.fuzzy_match(suggestion.display_value(), &line[start..end])?;
+                suggestion.match_indices = Some(found.indices);
+                Some((found.score, suggestion))
+            })
+            .collect();
+        ranked.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        ranked
+            .into_iter()
+            .map(|(_, suggestion)| suggestion)
+            .collect()
+    }
+}
+
+fn graphemes_equal(grapheme: &str, expected: &str, case_sensitive: bool) -> bool {
+    if case_sensitive {
+        grapheme == expected
+    } else {
+        grapheme == expected || grapheme.to_lowercase() == expected.to_lowercase()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Span;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("git checkout", "gco", Some(vec![0, 4, 9]))]
+    #[case("git checkout", "GCO", None)]
+    #[case("Cargo.toml", "ct", Some(vec![0, 6]))]
+    #[case("Cargo.toml", "Ct", Some(vec![0, 6]))]
+    #[case("cargo.toml", "Ct", None)]
+    #[case("abc", "abcd", None)]
+    #[case("abc", "", Some(vec![]))]
+    #[case("y̆es", "y̆s", Some(vec![0, 2]))]
+    fn matches_subsequences_with_smart_case(
+        #[case] candidate: &str,
+        #[case] pattern: &str,
+        #[case] expected: Option<Vec<usize>>,
+    ) {
+        let found = FuzzyMatcher::default().fuzzy_match(candidate, pattern);
+        assert_eq!(found.map(|found| found.indices), expected);
+    }
+
+    #[test]
+    fn without_smart_case_matching_ignores_case() {
+        let matcher = FuzzyMatcher::new().with_smart_case(false);
+        assert!(matcher.is_match("cargo.toml", "CT"));
+        assert!(!FuzzyMatcher::new().is_match("cargo.toml", "CT"));
+    }
+
+    #[test]
+    fn prefers_word_boundaries_and_consecutive_characters() {
+        let matcher = FuzzyMatcher::default();
+        // `fb` can match the boundaries in `foo_bar` instead of `fb` inside `foobar`
+        let found = matcher.fuzzy_match("xfxb foo_bar", "fb").unwrap();
+        assert_eq!(found.indices, vec![5, 9]);
+
+        let consecutive = matcher.fuzzy_match("xxabc", "abc").unwrap();
+        let scattered = matcher.fuzzy_match("xaxbxc", "abc").unwrap();
+        assert!(consecutive.score > scattered.score);
+
+        let camel_case = matcher.fuzzy_match("someFileName", "fn").unwrap();
+        assert_eq!(camel_case.indices, vec![4, 8]);
+    }
+
+    #[test]
+    fn ranks_suggestions_by_score() {
+        let suggestion = |value: &str| Suggestion {
+            value: value.to_string(),
+            span: Span::new(4, 7),
+            ..Default::default()
+        };
+        let ranked = FuzzyMatcher::default().rank_suggestions(
+            "cat rdm",
+            vec![
+                suggestion("crude_mode.txt"),
+                suggestion("README.md"),
+                suggestion("notes.txt"),
+            ],
+        );
+
+        let values: Vec<_> = ranked
+            .iter()
+            .map(|suggestion| (suggestion.value.as_str(), suggestion.match_indices.clone()))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("README.md", Some(vec![0, 3, 4])),
+                ("crude_mode.txt", Some(vec![1, 3, 6])),
+            ]
+        );
+    }
+}
@@ -1,55 +1,139 @@
-use std::{collections::HashSet, ops::Deref};
+use std::ops::Deref;
 
 use crate::{
-    history::SearchQuery, menu_functions::parse_selection_char, Completer, History, HistoryItem,
-    Result, Span, Suggestion,
+    history::{CommandLineSearch, SearchDirection, SearchFilter, SearchQuery},
+    menu_functions::parse_selection_char,
+    Completer, History, HistoryItem, HistoryItemId, MatchAlgorithm, Result, Span, Suggestion,
 };
 
 const SELECTION_CHAR: char = '!';
 
 // The HistoryCompleter is created just before updating the menu
 // It pulls data from the object that contains access to the History
-pub(crate) struct HistoryCompleter<'menu>(&'menu dyn History);
+pub(crate) struct HistoryCompleter<'menu> {
+    history: &'menu dyn History,
+    match_algorithm: MatchAlgorithm,
+}
 
 // Safe to implement Send since the HistoryCompleter should only be used when
 // updating the menu and that must happen in the same thread
 unsafe impl Send for HistoryCompleter<'_> {}
 
-fn search_unique(
-    completer: &HistoryCompleter,
-    line: &str,
-) -> Result<impl Iterator<Item = HistoryItem>> {
+fn unique_query(completer: &HistoryCompleter, line: &str) -> SearchQuery {
     let parsed = parse_selection_char(line, SELECTION_CHAR);
-    let values = completer.0.search(SearchQuery::all_that_contain_rev(
-        parsed.remainder.to_string(),
-    ))?;
-
-    let mut seen_matching_command_lines = HashSet::new();
-    Ok(values
-        .into_iter()
-        .filter(move |value| seen_matching_command_lines.insert(value.command_line.clone())))
+    let mut query = match completer.match_algorithm {
+        MatchAlgorithm::Completer => {
+            SearchQuery::all_that_contain_rev(parsed.remainder.to_string())
+        }
+        MatchAlgorithm::Fuzzy => SearchQuery {
+            filter: SearchFilter::from_text_search(
+                CommandLineSearch::Fuzzy(parsed.remainder.to_string()),
+                None,
+            ),
+            ..SearchQuery::everything(SearchDirection::Backward, None)
+        },
+    };
+    query.filter.distinct = true;
+    query
+}
+
+fn search_unique<'h>(
+    completer: &HistoryCompleter<'h>,
+    line: &str,
+) -> impl Iterator<Item = HistoryItem> + 'h {
+    UniqueMatches::new(completer.history, unique_query(completer, line))
+}
+
+/// Matches of a completion query that survive a failed read of the history
+///
+/// A read that fails is retried once after the last match returned so far.
+/// If the retry fails too the error is reported on stderr, since a completer
+/// has no way to hand it to the menu.
+struct UniqueMatches<'h> {
+    history: &'h dyn History,
+    query: SearchQuery,
+    results: Box<dyn Iterator<Item = Result<HistoryItem>> + 'h>,
+    last_id: Option<HistoryItemId>,
+    retried: bool,
+}
+
+impl<'h> UniqueMatches<'h> {
+    fn new(history: &'h dyn History, query: SearchQuery) -> Self {
+        Self {
+            history,
+            results: history.search_iter(query.clone()),
+            query,
+            last_id: None,
+            retried: false,
+        }
+    }
+}
+
+impl Iterator for UniqueMatches<'_> {
+    type Item = HistoryItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.results.next()? {
+                Ok(item) => {
+                    self.last_id = item.id.or(self.last_id);
+                    return Some(item);
+                }
+                Err(err) if self.retried => {
+                    eprintln!("History completion stopped after a failed read: {err}");
+                    return None;
+                }
+                Err(_) => {
+                    self.retried = true;
+                    let mut query = self.query.clone();
+                    query.start_id = self.last_id;
+                    self.results = self.history.search_iter(query);
+                }
+            }
+        }
+    }
 }
 
 impl Completer for HistoryCompleter<'_> {
     fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
-        match search_unique(self, line) {
-            Err(_) => vec![],
-            Ok(search_results) => search_results
-                .map(|value| self.create_suggestion(line, pos, value.command_line.deref()))
-                .collect(),
-        }
+        search_unique(self, line)
+            .map(|value| self.create_suggestion(line, pos, value.command_line.deref()))
+            .collect()
     }
 
-    // TODO: Implement `fn partial_complete()`
+    fn partial_complete(
+        &mut self,
+        line: &str,
+        pos: usize,
+        start: usize,
+        offset: usize,
+    ) -> Vec<Suggestion> {
+        search_unique(self, line)
+            .skip(start)
+            .take(offset)
+            .map(|value| self.create_suggestion(line, pos, value.command_line.deref()))
+            .collect()
+    }
 
     fn total_completions(&mut self, line: &str, _pos: usize) -> usize {
-        search_unique(self, line).map(|i| i.count()).unwrap_or(0)
+        self.history
+            .count(unique_query(self, line))
+            .map_or(0, |count| count as usize)
     }
 }
 
 impl<'menu> HistoryCompleter<'menu> {
     pub fn new(history: &'menu dyn History) -> Self {
-        Self(history)
+        Self {
+            history,
+            match_algorithm: MatchAlgorithm::default(),
+        }
+    }
+
+    /// Search the history with the matching of the menu
+    pub fn with_match_algorithm(mut self, match_algorithm: MatchAlgorithm) -> Self {
+        self.match_algorithm = match_algorithm;
+        self
     }
 
     fn create_suggestion(&self, line: &str, pos: usize, value: &str) -> Suggestion {
@@ -88,6 +172,9 @@ mod tests {
             duration: None,
             exit_status: None,
             more_info: None,
+            tags: vec![],
+            note: None,
+            bookmarked: false,
         }
     }
 
@@ -156,4 +243,110 @@ mod tests {
         assert_eq!(actual, expected);
         Ok(())
     }
+
+    #[test]
+    fn partial_complete_pages_through_unique_matches() -> Result<()> {
+        let command_lines = ["a", "b", "c", "a", "d", "b", "e"];
+        let mut history = FileBackedHistory::new(command_lines.len())?;
+        for command_line in command_lines {
+            history.save(new_history_item(command_line))?;
+        }
+        let mut sut = HistoryCompleter::new(&history);
+
+        let page = |sut: &mut HistoryCompleter, start| -> Vec<String> {
+            sut.partial_complete("", 0, start, 2)
+                .into_iter()
+                .map(|suggestion| suggestion.value)
+                .collect()
+        };
+        assert_eq!(page(&mut sut, 0), vec!["e", "b"]);
+        assert_eq!(page(&mut sut, 2), vec!["d", "a"]);
+        assert_eq!(page(&mut sut, 4), vec!["c"]);
+        assert_eq!(sut.total_completions("", 0), 5);
+        Ok(())
+    }
+
+    /// Fails the first search and forwards everything else
+    struct FlakyHistory {
+        history: FileBackedHistory,
+        failed: std::sync::atomic::AtomicBool,
+    }
+
+    impl History for FlakyHistory {
+        fn save(&mut self, h: HistoryItem) -> Result<HistoryItem> {
+            self.history.save(h)
+        }
+        fn load(&self, id: HistoryItemId) -> Result<HistoryItem> {
+            self.history.load(id)
+        }
+        fn count(&self, query: SearchQuery) -> Result<i64> {
+            self.history.count(query)
+        }
+        fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>> {
+            if self.failed.swap(true, std::sync::atomic::Ordering::Relaxed) {
+                self.history.search(query)
+            } else {
+                Err(ReedlineError(ReedlineErrorVariants::OtherHistoryError(
+                    "flaky read",
+                )))
+            }
+        }
+        fn update(
+            &mut self,
+            id: HistoryItemId,
+            updater: &dyn Fn(HistoryItem) -> HistoryItem,
+        ) -> Result<()> {
+            self.history.update(id, updater)
+        }
+        fn clear(&mut self) -> Result<()> {
+            self.history.clear()
+        }
+        fn delete(&mut self, h: HistoryItemId) -> Result<()> {
+            self.history.delete(h)
+        }
+        fn sync(&mut self) -> std::io::Result<()> {
+            self.history.sync()
+        }
+        fn session(&self) -> Option<HistorySessionId> {
+            self.history.session()
+        }
+    }
+
+    #[test]
+    fn failed_read_does_not_end_the_completions() -> Result<()> {
+        let mut history = FlakyHistory {
+            history: FileBackedHistory::new(3)?,
+            failed: Default::default(),
+        };
+        for command_line in ["a", "b", "c"] {
+            history.history.save(new_history_item(command_line))?;
+        }
+        let mut sut = HistoryCompleter::new(&history);
+
+        let actual: Vec<String> = sut
+            .complete("", 0)
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect();
+        assert_eq!(actual, vec!["c", "b", "a"]);
+        Ok(())
+    }
+
+    #[test]
+    fn fuzzy_matching_finds_scattered_characters() -> Result<()> {
+        let mut history = FileBackedHistory::new(3)?;
+        for command_line in ["git commit --amend", "ls", "git checkout main"] {
+            history.save(new_history_item(command_line))?;
+        }
+        let input = "gcm";
+        let mut sut = HistoryCompleter::new(&history).with_match_algorithm(MatchAlgorithm::Fuzzy);
+
+        let actual: Vec<String> = sut
+            .complete(input, input.len())
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect();
+        assert_eq!(actual, vec!["git checkout main", "git commit --amend"]);
+        Ok(())
+    }
 }
@@ -1,6 +1,8 @@
 mod base;
 mod default;
+mod fuzzy;
 pub(crate) mod history;
 
 pub use base::{Completer, Span, Suggestion};
 pub use default::DefaultCompleter;
+pub use fuzzy::{FuzzyMatch, FuzzyMatcher};
@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 /// Defines an interface to interact with a Clipboard for cut and paste.
 ///
 /// Mutable reference requirements are stricter than always necessary, but the currently used system clipboard API demands them for exclusive access.
@@ -18,7 +20,7 @@ pub trait Clipboard: Send {
 }
 
 /// Determines how the content in the clipboard should be inserted
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub enum ClipboardMode {
     /// As direct content at the current cursor position
     #[default]
@@ -53,10 +55,144 @@ impl Clipboard for LocalClipboard {
 }
 
 /// Creates a local clipboard
+#[cfg(test)]
 pub fn get_local_clipboard() -> Box<dyn Clipboard> {
     Box::new(LocalClipboard::new())
 }
 
+/// Default number of entries kept by a [`KillRing`]
+pub const DEFAULT_KILL_RING_SIZE: usize = 60;
+
+/// Side of the most recent kill ring entry that a consecutive kill is merged into
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum KillDirection {
+    /// The killed text followed the previous kill (e.g. repeated `kill-word`)
+    Append,
+    /// The killed text preceded the previous kill (e.g. repeated `backward-kill-word`)
+    Prepend,
+}
+
+/// Ring of previously killed text as known from GNU readline and Emacs
+///
+/// Every cut pushes a new entry, unless it directly follows another kill, in which case it
+/// is merged into the most recent entry. Yanking inserts the entry at the yank position, and
+/// yank-pop rotates that position towards older entries.
+/// Once `capacity` entries are stored, the oldest one is dropped.
+///
+/// The entries can be read and restored by the host to persist them across sessions.
+#[derive(Debug, Clone)]
+pub struct KillRing {
+    entries: VecDeque<(String, ClipboardMode)>,
+    capacity: usize,
+    yank_index: usize,
+    merge_direction: Option<KillDirection>,
+}
+
+impl Default for KillRing {
+    fn default() -> Self {
+        Self::new(DEFAULT_KILL_RING_SIZE)
+    }
+}
+
+impl KillRing {
+    /// Create an empty kill ring holding at most `capacity` entries (at least one)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+            yank_index: 0,
+            merge_direction: None,
+        }
+    }
+
+    /// Maximum number of entries kept
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the maximum number of entries kept (at least one), dropping the oldest if necessary
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        self.entries.truncate(self.capacity);
+        if self.yank_index >= self.entries.len() {
+            self.yank_index = 0;
+        }
+    }
+
+    /// Number of stored entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if nothing has been killed yet
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over the stored entries, starting with the most recent kill
+    pub fn entries(&self) -> impl Iterator<Item = &str> + '_ {
+        self.entries.iter().map(|(content, _)| content.as_str())
+    }
+
+    /// Add `content` as the most recent entry
+    ///
+    /// To restore a ring saved from [`KillRing::entries`] push the entries in reverse order.
+    pub fn push(&mut self, content: impl Into<String>) {
+        self.kill(content.into(), ClipboardMode::Normal);
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.yank_index = 0;
+    }
+
+    /// Merge the next kill into the most recent entry instead of creating a new one
+    pub(crate) fn set_merge_direction(&mut self, direction: Option<KillDirection>) {
+        self.merge_direction = direction;
+    }
+
+    /// Move the yank position to the next older entry, wrapping around to the most recent one
+    pub(crate) fn rotate(&mut self) {
+        if !self.entries.is_empty() {
+            self.yank_index = (self.yank_index + 1) % self.entries.len();
+        }
+    }
+
+    fn kill(&mut self, content: String, mode: ClipboardMode) {
+        self.yank_index = 0;
+        match (self.merge_direction.take(), self.entries.front_mut()) {
+            (Some(direction), Some((previous, previous_mode))) if *previous_mode == mode => {
+                match direction {
+                    KillDirection::Append => previous.push_str(&content),
+                    KillDirection::Prepend => previous.insert_str(0, &content),
+                }
+            }
+            _ => {
+                self.entries.push_front((content, mode));
+                self.entries.truncate(self.capacity);
+            }
+        }
+    }
+}
+
+impl Clipboard for KillRing {
+    fn set(&mut self, content: &str, mode: ClipboardMode) {
+        self.kill(content.to_owned(), mode);
+    }
+
+    fn get(&mut self) -> (String, ClipboardMode) {
+        self.entries
+            .get(self.yank_index)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn clear(&mut self) {
+        KillRing::clear(self);
+    }
+}
+
 #[cfg(feature = "system_clipboard")]
 pub use system_clipboard::SystemClipboard;
 
@@ -117,7 +253,7 @@ mod system_clipboard {
 mod tests {
     #[cfg(feature = "system_clipboard")]
     use super::get_system_clipboard;
-    use super::{get_local_clipboard, ClipboardMode};
+    use super::{get_local_clipboard, Clipboard, ClipboardMode, KillDirection, KillRing};
     #[test]
     fn reads_back_local() {
         let mut cb = get_local_clipboard();
@@ -154,4 +290,57 @@ mod tests {
 
         cb.set(&previous_state, ClipboardMode::Normal);
     }
+
+    #[test]
+    fn kill_ring_keeps_most_recent_entries() {
+        let mut ring = KillRing::new(2);
+        ring.set("one", ClipboardMode::Normal);
+        ring.set("two", ClipboardMode::Normal);
+        ring.set("three", ClipboardMode::Normal);
+
+        assert_eq!(ring.entries().collect::<Vec<_>>(), vec!["three", "two"]);
+        assert_eq!(ring.get().0, "three");
+
+        ring.rotate();
+        assert_eq!(ring.get().0, "two");
+        ring.rotate();
+        assert_eq!(ring.get().0, "three");
+
+        ring.set_capacity(1);
+        assert_eq!(ring.entries().collect::<Vec<_>>(), vec!["three"]);
+    }
+
+    #[test]
+    fn kill_ring_merges_consecutive_kills() {
+        let mut ring = KillRing::default();
+        ring.set("foo", ClipboardMode::Normal);
+        ring.set_merge_direction(Some(KillDirection::Append));
+        ring.set(" bar", ClipboardMode::Normal);
+        ring.set_merge_direction(Some(KillDirection::Prepend));
+        ring.set("baz ", ClipboardMode::Normal);
+
+        assert_eq!(ring.entries().collect::<Vec<_>>(), vec!["baz foo bar"]);
+
+        // Without a merge direction a new entry is started
+        ring.set("qux", ClipboardMode::Normal);
+        assert_eq!(
+            ring.entries().collect::<Vec<_>>(),
+            vec!["qux", "baz foo bar"]
+        );
+    }
+
+    #[test]
+    fn kill_ring_restores_pushed_entries() {
+        let saved = vec!["newest", "older", "oldest"];
+        let mut ring = KillRing::default();
+        for entry in saved.iter().rev() {
+            ring.push(*entry);
+        }
+
+        assert_eq!(ring.entries().collect::<Vec<_>>(), saved);
+        assert_eq!(ring.len(), 3);
+        ring.clear();
+        assert!(ring.is_empty());
+        assert_eq!(ring.get().0, "");
+    }
 }
@@ -1,11 +1,12 @@
-use super::{edit_stack::EditStack, Clipboard, ClipboardMode, LineBuffer};
-#[cfg(feature = "system_clipboard")]
-use crate::core_editor::get_system_clipboard;
+use super::{
+    edit_stack::EditStack, Clipboard, ClipboardMode, KillDirection, KillRing, LineBuffer,
+    RegisterOperation, Registers, StructuredEditing,
+};
 use crate::enums::{EditType, TextObject, TextObjectScope, TextObjectType, UndoBehavior};
 use crate::prompt::{PromptEditMode, PromptViMode};
-use crate::{core_editor::get_local_clipboard, EditCommand};
+use crate::EditCommand;
 use std::cmp::{max, min};
-use std::ops::{DerefMut, Range};
+use std::ops::Range;
 
 /// Stateful editor executing changes to the underlying [`LineBuffer`]
 ///
@@ -13,28 +14,32 @@ use std::ops::{DerefMut, Range};
 /// the undo/redo history and has facilities for cut/copy/yank/paste
 pub struct Editor {
     line_buffer: LineBuffer,
-    cut_buffer: Box<dyn Clipboard>,
-    #[cfg(feature = "system_clipboard")]
-    system_clipboard: Box<dyn Clipboard>,
+    cut_buffer: Registers,
+    pending_register: Option<char>,
+    last_command_was_kill: bool,
+    last_yank: Option<Range<usize>>,
     edit_stack: EditStack<LineBuffer>,
     last_undo_behavior: UndoBehavior,
     selection_anchor: Option<usize>,
     selection_mode: Option<PromptEditMode>,
     edit_mode: PromptEditMode,
+    structured_editing: Option<StructuredEditing>,
 }
 
 impl Default for Editor {
     fn default() -> Self {
         Editor {
             line_buffer: LineBuffer::new(),
-            cut_buffer: get_local_clipboard(),
-            #[cfg(feature = "system_clipboard")]
-            system_clipboard: get_system_clipboard(),
+            cut_buffer: Registers::default(),
+            pending_register: None,
+            last_command_was_kill: false,
+            last_yank: None,
             edit_stack: EditStack::new(),
             last_undo_behavior: UndoBehavior::CreateUndoPoint,
             selection_anchor: None,
             selection_mode: None,
             edit_mode: PromptEditMode::Default,
+            structured_editing: None,
         }
     }
 }
@@ -49,10 +54,46 @@ impl Editor {
     /// [`UndoBehavior`] specifies how this change should be reflected on the undo stack.
     pub(crate) fn set_line_buffer(&mut self, line_buffer: LineBuffer, undo_behavior: UndoBehavior) {
         self.line_buffer = line_buffer;
+        self.reset_kill_state();
         self.update_undo_state(undo_behavior);
     }
 
+    /// Set the [`StructuredEditing`] applied to typed characters, newlines and backspace
+    pub(crate) fn set_structured_editing(&mut self, structured_editing: Option<StructuredEditing>) {
+        self.structured_editing = structured_editing;
+    }
+
+    /// Get the [`KillRing`] used for cutting and pasting
+    pub(crate) fn kill_ring(&self) -> &KillRing {
+        self.cut_buffer.kill_ring()
+    }
+
+    /// Get mutable access to the [`KillRing`], e.g. to restore persisted entries
+    pub(crate) fn kill_ring_mut(&mut self) -> &mut KillRing {
+        self.cut_buffer.kill_ring_mut()
+    }
+
+    /// Forget the preceding kill and yank so the next command cannot continue them
+    fn reset_kill_state(&mut self) {
+        self.last_command_was_kill = false;
+        self.last_yank = None;
+    }
+
     pub(crate) fn run_edit_command(&mut self, command: &EditCommand) {
+        let kill_direction = self.kill_direction(command);
+        if self.last_command_was_kill {
+            self.cut_buffer
+                .kill_ring_mut()
+                .set_merge_direction(kill_direction);
+        }
+        let last_yank = self.last_yank.take();
+        let operation = match command.edit_type() {
+            EditType::NoOp => RegisterOperation::Yank,
+            _ => RegisterOperation::Delete,
+        };
+        self.cut_buffer
+            .select(self.pending_register.take(), operation);
+
         match command {
             EditCommand::MoveToStart { select } => self.move_to_start(*select),
             EditCommand::MoveToLineStart { select } => self.move_to_line_start(*select),
@@ -109,6 +150,8 @@ impl Editor {
             EditCommand::CutBigWordRightToNext => self.cut_big_word_right_to_next(),
             EditCommand::PasteCutBufferBefore => self.insert_cut_buffer_before(),
             EditCommand::PasteCutBufferAfter => self.insert_cut_buffer_after(),
+            EditCommand::YankPop => self.yank_pop(last_yank),
+            EditCommand::SelectRegister(register) => self.pending_register = Some(*register),
             EditCommand::UppercaseWord => self.line_buffer.uppercase_word(),
             EditCommand::LowercaseWord => self.line_buffer.lowercase_word(),
             EditCommand::SwitchcaseChar => self.line_buffer.switchcase_char(),
@@ -197,7 +240,12 @@ impl Editor {
             EditCommand::CutTextObject { text_object } => self.cut_text_object(*text_object),
             EditCommand::CopyTextObject { text_object } => self.copy_text_object(*text_object),
         }
-        if !matches!(command.edit_type(), EditType::MoveCursor { select: true }) {
+        self.cut_buffer.kill_ring_mut().set_merge_direction(None);
+        self.cut_buffer.select(None, RegisterOperation::Delete);
+        self.last_command_was_kill = kill_direction.is_some();
+        if !matches!(command.edit_type(), EditType::MoveCursor { select: true })
+            && !matches!(command, EditCommand::SelectRegister(_))
+        {
             self.clear_selection();
         }
         if let EditType::MoveCursor { select: true } = command.edit_type() {}
@@ -220,6 +268,27 @@ impl Editor {
         self.update_undo_state(new_undo_behavior);
     }
 
+    /// Side of the previous kill that `command` extends when run right after it
+    ///
+    /// Only the Emacs-style kill commands are merged, vi deletes always start a new entry.
+    fn kill_direction(&self, command: &EditCommand) -> Option<KillDirection> {
+        if matches!(self.edit_mode, PromptEditMode::Vi(_)) {
+            return None;
+        }
+        match command {
+            EditCommand::CutToEnd
+            | EditCommand::CutToLineEnd
+            | EditCommand::KillLine
+            | EditCommand::CutWordRight
+            | EditCommand::CutBigWordRight => Some(KillDirection::Append),
+            EditCommand::CutFromStart
+            | EditCommand::CutFromLineStart
+            | EditCommand::CutWordLeft
+            | EditCommand::CutBigWordLeft => Some(KillDirection::Prepend),
+            _ => None,
+        }
+    }
+
     fn swap_cursor_and_anchor(&mut self) {
         if let Some(anchor) = self.selection_anchor {
             self.selection_anchor = Some(self.insertion_point());
@@ -280,6 +349,7 @@ impl Editor {
     /// Insertion point update to the end of the buffer.
     pub(crate) fn set_buffer(&mut self, buffer: String, undo_behavior: UndoBehavior) {
         self.line_buffer.set_buffer(buffer);
+        self.reset_kill_state();
         self.update_undo_state(undo_behavior);
     }
 
@@ -512,11 +582,38 @@ impl Editor {
 
     fn insert_cut_buffer_before(&mut self) {
         self.delete_selection();
-        insert_clipboard_content_before(&mut self.line_buffer, self.cut_buffer.deref_mut())
+        self.track_yank(|editor| {
+            insert_clipboard_content_before(&mut editor.line_buffer, &mut editor.cut_buffer)
+        });
     }
 
     fn insert_cut_buffer_after(&mut self) {
         self.delete_selection();
+        self.track_yank(Self::insert_cut_buffer_content_after);
+    }
+
+    /// Run `yank` and remember the inserted range for a following [`EditCommand::YankPop`]
+    fn track_yank(&mut self, yank: impl FnOnce(&mut Self)) {
+        let previous_len = self.line_buffer.len();
+        yank(self);
+        let end = self.line_buffer.insertion_point();
+        let inserted_len = self.line_buffer.len().saturating_sub(previous_len);
+        self.last_yank = Some(end.saturating_sub(inserted_len)..end);
+    }
+
+    fn yank_pop(&mut self, last_yank: Option<Range<usize>>) {
+        let Some(range) = last_yank else {
+            return;
+        };
+        self.cut_buffer.kill_ring_mut().rotate();
+        let (content, _) = self.cut_buffer.get();
+        self.line_buffer.clear_range_safe(range.clone());
+        self.line_buffer.set_insertion_point(range.start);
+        self.line_buffer.insert_str(&content);
+        self.last_yank = Some(range.start..range.start + content.len());
+    }
+
+    fn insert_cut_buffer_content_after(&mut self) {
         match self.cut_buffer.get() {
             (content, ClipboardMode::Normal) => {
                 self.line_buffer.move_right();
@@ -638,7 +735,9 @@ impl Editor {
     fn cut_selection_to_system(&mut self) {
         if let Some((start, end)) = self.get_selection() {
             let cut_slice = &self.line_buffer.get_buffer()[start..end];
-            self.system_clipboard.set(cut_slice, ClipboardMode::Normal);
+            self.cut_buffer
+                .system_clipboard_mut()
+                .set(cut_slice, ClipboardMode::Normal);
             self.cut_range(start..end);
             self.clear_selection();
         }
@@ -655,7 +754,9 @@ impl Editor {
     fn copy_selection_to_system(&mut self) {
         if let Some((start, end)) = self.get_selection() {
             let cut_slice = &self.line_buffer.get_buffer()[start..end];
-            self.system_clipboard.set(cut_slice, ClipboardMode::Normal);
+            self.cut_buffer
+                .system_clipboard_mut()
+                .set(cut_slice, ClipboardMode::Normal);
         }
     }
 
@@ -714,7 +815,11 @@ impl Editor {
     fn backspace(&mut self) {
         if self.selection_anchor.is_some() {
             self.delete_selection();
-        } else {
+        } else if !self
+            .structured_editing
+            .as_ref()
+            .map_or(false, |editing| editing.backspace(&mut self.line_buffer))
+        {
             self.line_buffer.delete_left_grapheme();
         }
     }
@@ -757,7 +862,11 @@ impl Editor {
 
     fn insert_char(&mut self, c: char) {
         self.delete_selection();
-        self.line_buffer.insert_char(c);
+        if !self.structured_editing.as_ref().map_or(false, |editing| {
+            editing.insert_char(&mut self.line_buffer, c)
+        }) {
+            self.line_buffer.insert_char(c);
+        }
     }
 
     fn insert_str(&mut self, str: &str) {
@@ -767,18 +876,26 @@ impl Editor {
 
     fn insert_newline(&mut self) {
         self.delete_selection();
-        self.line_buffer.insert_newline();
+        match &self.structured_editing {
+            Some(editing) => editing.insert_newline(&mut self.line_buffer),
+            None => self.line_buffer.insert_newline(),
+        }
     }
 
     #[cfg(feature = "system_clipboard")]
     fn paste_from_system(&mut self) {
         self.delete_selection();
-        insert_clipboard_content_before(&mut self.line_buffer, self.system_clipboard.deref_mut());
+        insert_clipboard_content_before(
+            &mut self.line_buffer,
+            self.cut_buffer.system_clipboard_mut(),
+        );
     }
 
     fn paste_cut_buffer(&mut self) {
         self.delete_selection();
-        insert_clipboard_content_before(&mut self.line_buffer, self.cut_buffer.deref_mut());
+        self.track_yank(|editor| {
+            insert_clipboard_content_before(&mut editor.line_buffer, &mut editor.cut_buffer)
+        });
     }
 
     fn cut_range(&mut self, range: Range<usize>) {
@@ -1557,6 +1674,100 @@ mod test {
         assert_eq!(editor.cut_buffer.get().0, "bar()qux");
     }
 
+    #[test]
+    fn test_consecutive_kills_are_merged() {
+        let mut editor = editor_with("foo bar baz");
+        editor.move_to_position(4, false);
+        editor.run_edit_command(&EditCommand::CutWordRight);
+        editor.run_edit_command(&EditCommand::CutWordRight);
+        editor.run_edit_command(&EditCommand::CutWordLeft);
+        assert_eq!(editor.get_buffer(), "");
+        assert_eq!(
+            editor.kill_ring().entries().collect::<Vec<_>>(),
+            vec!["foo bar baz"]
+        );
+
+        // Any other command in between starts a new entry
+        editor.set_buffer("qux quux".to_string(), UndoBehavior::CreateUndoPoint);
+        editor.run_edit_command(&EditCommand::MoveToStart { select: false });
+        editor.run_edit_command(&EditCommand::CutWordRight);
+        editor.run_edit_command(&EditCommand::MoveRight { select: false });
+        editor.run_edit_command(&EditCommand::CutWordRight);
+        assert_eq!(
+            editor.kill_ring().entries().collect::<Vec<_>>(),
+            vec!["quux", "qux", "foo bar baz"]
+        );
+    }
+
+    #[test]
+    fn test_vi_deletes_are_not_merged() {
+        let mut editor = editor_with("foo bar");
+        editor.set_edit_mode(PromptEditMode::Vi(PromptViMode::Normal));
+        editor.run_edit_command(&EditCommand::MoveToStart { select: false });
+        editor.run_edit_command(&EditCommand::CutWordRight);
+        editor.run_edit_command(&EditCommand::CutWordRight);
+        assert_eq!(
+            editor.kill_ring().entries().collect::<Vec<_>>(),
+            vec![" bar", "foo"]
+        );
+    }
+
+    #[test]
+    fn test_cut_and_paste_through_registers() {
+        let mut editor = editor_with("foo bar");
+        editor.set_edit_mode(PromptEditMode::Vi(PromptViMode::Normal));
+        editor.run_edit_command(&EditCommand::MoveToStart { select: false });
+        editor.run_edit_command(&EditCommand::SelectRegister('a'));
+        editor.run_edit_command(&EditCommand::CutWordRightToNext);
+        editor.run_edit_command(&EditCommand::SelectRegister('_'));
+        editor.run_edit_command(&EditCommand::CutWordRightToNext);
+        assert_eq!(editor.get_buffer(), "");
+
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "foo ");
+        editor.run_edit_command(&EditCommand::SelectRegister('a'));
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "foo foo ");
+    }
+
+    #[test]
+    fn test_select_register_keeps_selection() {
+        let mut editor = editor_with("foo bar");
+        editor.run_edit_command(&EditCommand::MoveToStart { select: false });
+        editor.run_edit_command(&EditCommand::MoveWordRight { select: true });
+        editor.run_edit_command(&EditCommand::SelectRegister('b'));
+        editor.run_edit_command(&EditCommand::CopySelection);
+
+        editor.run_edit_command(&EditCommand::MoveToEnd { select: false });
+        editor.run_edit_command(&EditCommand::SelectRegister('b'));
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "foo barfoo");
+    }
+
+    #[test]
+    fn test_yank_pop_cycles_through_kill_ring() {
+        let mut editor = editor_with("ab");
+        editor.kill_ring_mut().push("one");
+        editor.kill_ring_mut().push("two");
+        editor.kill_ring_mut().push("three");
+        editor.move_to_position(1, false);
+
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "athreeb");
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "atwob");
+        assert_eq!(editor.insertion_point(), 4);
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "aoneb");
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "athreeb");
+
+        // Yank-pop only works directly after a yank
+        editor.run_edit_command(&EditCommand::MoveToEnd { select: false });
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "athreeb");
+    }
+
     #[test]
     fn test_yank_inside_brackets() {
         let mut editor = editor_with("foo(bar)baz");
@@ -2145,4 +2356,28 @@ mod test {
         assert_eq!(bracket_result, expected_bracket);
         assert_eq!(quote_result, expected_quote);
     }
+
+    #[test]
+    fn structured_editing_applies_to_edit_commands() {
+        let mut editor = Editor::default();
+        editor.set_structured_editing(Some(StructuredEditing::default()));
+
+        for command in [
+            EditCommand::InsertChar('f'),
+            EditCommand::InsertChar('('),
+            EditCommand::InsertChar('{'),
+            EditCommand::InsertNewline,
+            EditCommand::InsertChar('x'),
+        ] {
+            editor.run_edit_command(&command);
+        }
+        assert_eq!(editor.get_buffer(), "f({\n    x\n})");
+
+        editor.run_edit_command(&EditCommand::Undo);
+        editor.run_edit_command(&EditCommand::Undo);
+        editor.run_edit_command(&EditCommand::Backspace);
+        assert_eq!(editor.get_buffer(), "f()");
+        editor.run_edit_command(&EditCommand::Backspace);
+        assert_eq!(editor.get_buffer(), "f");
+    }
 }
@@ -2,9 +2,14 @@ mod clip_buffer;
 mod edit_stack;
 mod editor;
 mod line_buffer;
+mod registers;
+mod structured_editing;
 
 #[cfg(feature = "system_clipboard")]
 pub(crate) use clip_buffer::get_system_clipboard;
-pub(crate) use clip_buffer::{get_local_clipboard, Clipboard, ClipboardMode};
+pub(crate) use clip_buffer::{Clipboard, ClipboardMode, KillDirection};
+pub use clip_buffer::{KillRing, DEFAULT_KILL_RING_SIZE};
 pub use editor::Editor;
 pub use line_buffer::LineBuffer;
+pub(crate) use registers::{RegisterOperation, Registers};
+pub use structured_editing::StructuredEditing;
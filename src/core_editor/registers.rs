@@ -0,0 +1,209 @@
+use super::{Clipboard, ClipboardMode, KillRing};
+use std::collections::{HashMap, VecDeque};
+
+/// Number of registers (`"1` to `"9`) remembering previous deletes
+const NUMBERED_DELETE_REGISTERS: usize = 9;
+
+/// Whether a register is written by a copy or by a cut
+///
+/// Like vim only unnamed copies go to `"0` and unnamed cuts are shifted into `"1`-`"9`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum RegisterOperation {
+    Yank,
+    Delete,
+}
+
+/// The editor's cut buffer: the [`KillRing`] serving as the unnamed register and the vim-style
+/// registers picked with [`EditCommand::SelectRegister`](crate::EditCommand::SelectRegister)
+///
+/// Implements [`Clipboard`] for the currently selected register, so every cut, copy and paste
+/// of the editor is routed through it.
+pub(crate) struct Registers {
+    kill_ring: KillRing,
+    named: HashMap<char, (String, ClipboardMode)>,
+    last_yank: Option<(String, ClipboardMode)>,
+    deletes: VecDeque<(String, ClipboardMode)>,
+    #[cfg(feature = "system_clipboard")]
+    system_clipboard: Box<dyn Clipboard>,
+    selected: Option<char>,
+    operation: RegisterOperation,
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Self {
+            kill_ring: KillRing::default(),
+            named: HashMap::new(),
+            last_yank: None,
+            deletes: VecDeque::new(),
+            #[cfg(feature = "system_clipboard")]
+            system_clipboard: super::get_system_clipboard(),
+            selected: None,
+            operation: RegisterOperation::Delete,
+        }
+    }
+}
+
+impl Registers {
+    pub(crate) fn kill_ring(&self) -> &KillRing {
+        &self.kill_ring
+    }
+
+    pub(crate) fn kill_ring_mut(&mut self) -> &mut KillRing {
+        &mut self.kill_ring
+    }
+
+    #[cfg(feature = "system_clipboard")]
+    pub(crate) fn system_clipboard_mut(&mut self) -> &mut dyn Clipboard {
+        self.system_clipboard.as_mut()
+    }
+
+    /// Route the following reads and writes to `register`, or the unnamed one for `None`
+    pub(crate) fn select(&mut self, register: Option<char>, operation: RegisterOperation) {
+        self.selected = register;
+        self.operation = operation;
+    }
+
+    fn set_unnamed(&mut self, content: &str, mode: ClipboardMode) {
+        self.kill_ring.set(content, mode);
+        match self.operation {
+            RegisterOperation::Yank => self.last_yank = Some((content.to_owned(), mode)),
+            RegisterOperation::Delete => {
+                self.deletes.push_front((content.to_owned(), mode));
+                self.deletes.truncate(NUMBERED_DELETE_REGISTERS);
+            }
+        }
+    }
+
+    fn set_numbered(&mut self, index: usize, content: &str, mode: ClipboardMode) {
+        if index == 0 {
+            self.last_yank = Some((content.to_owned(), mode));
+        } else {
+            if self.deletes.len() < index {
+                self.deletes.resize(index, Default::default());
+            }
+            self.deletes[index - 1] = (content.to_owned(), mode);
+        }
+    }
+
+    fn append_named(&mut self, register: char, content: &str, mode: ClipboardMode) {
+        let (previous, previous_mode) = self.named.entry(register).or_default();
+        if mode == ClipboardMode::Lines && !previous.is_empty() && !previous.ends_with('\n') {
+            previous.push('\n');
+        }
+        previous.push_str(content);
+        if mode == ClipboardMode::Lines {
+            *previous_mode = ClipboardMode::Lines;
+        }
+        let (content, mode) = (previous.clone(), *previous_mode);
+        self.kill_ring.set(&content, mode);
+    }
+}
+
+impl Clipboard for Registers {
+    fn set(&mut self, content: &str, mode: ClipboardMode) {
+        match self.selected {
+            None | Some('"') => self.set_unnamed(content, mode),
+            Some('_') => {}
+            Some(register @ 'a'..='z') => {
+                self.named.insert(register, (content.to_owned(), mode));
+                self.kill_ring.set(content, mode);
+            }
+            Some(register @ 'A'..='Z') => {
+                self.append_named(register.to_ascii_lowercase(), content, mode)
+            }
+            Some(register @ '0'..='9') => {
+                let index = register.to_digit(10).unwrap_or_default() as usize;
+                self.set_numbered(index, content, mode);
+            }
+            #[cfg(feature = "system_clipboard")]
+            Some('+' | '*') => self.system_clipboard.set(content, mode),
+            Some(_) => self.set_unnamed(content, mode),
+        }
+    }
+
+    fn get(&mut self) -> (String, ClipboardMode) {
+        match self.selected {
+            None | Some('"') => self.kill_ring.get(),
+            Some('_') => Default::default(),
+            Some(register) if register.is_ascii_alphabetic() => self
+                .named
+                .get(&register.to_ascii_lowercase())
+                .cloned()
+                .unwrap_or_default(),
+            Some('0') => self.last_yank.clone().unwrap_or_default(),
+            Some(register @ '1'..='9') => {
+                let index = register.to_digit(10).unwrap_or_default() as usize;
+                self.deletes.get(index - 1).cloned().unwrap_or_default()
+            }
+            #[cfg(feature = "system_clipboard")]
+            Some('+' | '*') => self.system_clipboard.get(),
+            Some(_) => self.kill_ring.get(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn write(registers: &mut Registers, register: Option<char>, op: RegisterOperation, s: &str) {
+        registers.select(register, op);
+        registers.set(s, ClipboardMode::Normal);
+        registers.select(None, RegisterOperation::Delete);
+    }
+
+    fn read(registers: &mut Registers, register: char) -> String {
+        registers.select(Some(register), RegisterOperation::Yank);
+        let content = registers.get().0;
+        registers.select(None, RegisterOperation::Delete);
+        content
+    }
+
+    #[test]
+    fn unnamed_writes_fill_yank_and_delete_registers() {
+        let mut registers = Registers::default();
+        write(&mut registers, None, RegisterOperation::Yank, "yanked");
+        write(&mut registers, None, RegisterOperation::Delete, "first");
+        write(&mut registers, None, RegisterOperation::Delete, "second");
+
+        assert_eq!(registers.get().0, "second");
+        assert_eq!(read(&mut registers, '"'), "second");
+        assert_eq!(read(&mut registers, '0'), "yanked");
+        assert_eq!(read(&mut registers, '1'), "second");
+        assert_eq!(read(&mut registers, '2'), "first");
+        assert_eq!(read(&mut registers, '3'), "");
+    }
+
+    #[test]
+    fn named_registers_and_append() {
+        let mut registers = Registers::default();
+        write(&mut registers, Some('a'), RegisterOperation::Yank, "foo");
+        write(&mut registers, Some('A'), RegisterOperation::Delete, "bar");
+        write(&mut registers, Some('b'), RegisterOperation::Delete, "baz");
+
+        assert_eq!(read(&mut registers, 'a'), "foobar");
+        assert_eq!(read(&mut registers, 'A'), "foobar");
+        assert_eq!(read(&mut registers, 'b'), "baz");
+        // Named writes leave the numbered registers alone
+        assert_eq!(read(&mut registers, '0'), "");
+        assert_eq!(read(&mut registers, '1'), "");
+    }
+
+    #[test]
+    fn black_hole_register_discards() {
+        let mut registers = Registers::default();
+        write(&mut registers, None, RegisterOperation::Delete, "kept");
+        write(
+            &mut registers,
+            Some('_'),
+            RegisterOperation::Delete,
+            "dropped",
+        );
+
+        assert_eq!(registers.get().0, "kept");
+        assert_eq!(read(&mut registers, '_'), "");
+        assert_eq!(registers.kill_ring().len(), 1);
+    }
+}
@@ -0,0 +1,312 @@
+use super::LineBuffer;
+
+/// Opt-in editing helper for structured multi-line input
+///
+/// When enabled on the line editor with [`crate::Reedline::with_structured_editing`] it
+/// changes how characters, newlines and backspace are handled:
+///
+/// - A new line starts with the indentation of the previous line and is indented one level
+///   further if the previous line leaves a bracket open. A newline between an empty pair of
+///   brackets moves the closing bracket to its own line.
+/// - Typing a closing bracket at the start of an indented line removes one level of indentation.
+/// - Typing an opening character inserts the closing one as well. Typing a closing character
+///   right in front of the same character moves over it instead of inserting it.
+/// - Backspace between an empty pair deletes both characters.
+///
+/// Pairs with the same opening and closing character (like quotes) are not used for
+/// indentation and are only inserted if the previous character isn't alphanumeric.
+///
+/// # Example
+/// ```rust
+/// use reedline::{Reedline, StructuredEditing};
+///
+/// let line_editor = Reedline::create().with_structured_editing(
+///     StructuredEditing::default()
+///         .with_pairs([('(', ')'), ('[', ']'), ('"', '"')])
+///         .with_indent_unit("  "),
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructuredEditing {
+    pairs: Vec<(char, char)>,
+    indent_unit: String,
+    auto_indent: bool,
+    auto_pairs: bool,
+}
+
+impl Default for StructuredEditing {
+    fn default() -> Self {
+        Self {
+            pairs: vec![('(', ')'), ('[', ']'), ('{', '}'), ('\'', '\''), ('"', '"')],
+            indent_unit: "    ".to_string(),
+            auto_indent: true,
+            auto_pairs: true,
+        }
+    }
+}
+
+impl StructuredEditing {
+    /// A builder that sets the pairs of opening and closing characters
+    #[must_use]
+    pub fn with_pairs(mut self, pairs: impl IntoIterator<Item = (char, char)>) -> Self {
+        self.pairs = pairs.into_iter().collect();
+        self
+    }
+
+    /// A builder that sets the text inserted for one level of indentation
+    #[must_use]
+    pub fn with_indent_unit(mut self, indent_unit: &str) -> Self {
+        self.indent_unit = indent_unit.to_string();
+        self
+    }
+
+    /// A builder that enables or disables the indentation of new lines
+    #[must_use]
+    pub fn with_auto_indent(mut self, auto_indent: bool) -> Self {
+        self.auto_indent = auto_indent;
+        self
+    }
+
+    /// A builder that enables or disables the insertion and deletion of pairs
+    #[must_use]
+    pub fn with_auto_pairs(mut self, auto_pairs: bool) -> Self {
+        self.auto_pairs = auto_pairs;
+        self
+    }
+
+    fn closing_for(&self, opening: char) -> Option<char> {
+        self.pairs
+            .iter()
+            .find(|(open, _)| *open == opening)
+            .map(|(_, close)| *close)
+    }
+
+    fn is_closing(&self, c: char) -> bool {
+        self.pairs.iter().any(|(_, close)| *close == c)
+    }
+
+    fn is_bracket_closing(&self, c: char) -> bool {
+        self.pairs
+            .iter()
+            .any(|(open, close)| *close == c && open != close)
+    }
+
+    /// Text of the current line up to the cursor
+    fn line_before_cursor(line_buffer: &LineBuffer) -> &str {
+        let line_start = line_buffer.current_line_range().start;
+        &line_buffer.get_buffer()[line_start..line_buffer.insertion_point()]
+    }
+
+    fn char_before_cursor(line_buffer: &LineBuffer) -> Option<char> {
+        line_buffer.get_buffer()[..line_buffer.insertion_point()]
+            .chars()
+            .next_back()
+    }
+
+    fn char_after_cursor(line_buffer: &LineBuffer) -> Option<char> {
+        line_buffer.get_buffer()[line_buffer.insertion_point()..]
+            .chars()
+            .next()
+    }
+
+    /// Insert a typed character, returns `false` if it should be inserted as is
+    pub(crate) fn insert_char(&self, line_buffer: &mut LineBuffer, c: char) -> bool {
+        let next = Self::char_after_cursor(line_buffer);
+
+        if self.auto_pairs && self.is_closing(c) && next == Some(c) {
+            line_buffer.move_right();
+            return true;
+        }
+
+        if self.auto_indent && self.is_bracket_closing(c) {
+            let line = Self::line_before_cursor(line_buffer);
+            if !line.is_empty() && line.trim_start().is_empty() {
+                if let Some(dedented) = line.strip_suffix(self.indent_unit.as_str()) {
+                    let dedented_len = dedented.len();
+                    let start = line_buffer.current_line_range().start + dedented_len;
+                    line_buffer.clear_range_safe(start..line_buffer.insertion_point());
+                }
+            }
+        }
+
+        if self.auto_pairs {
+            if let Some(close) = self.closing_for(c) {
+                let next_allows_pair = next.map_or(true, |next| {
+                    next.is_whitespace() || self.is_bracket_closing(next)
+                });
+                let previous_allows_pair = c != close
+                    || !Self::char_before_cursor(line_buffer).map_or(false, |previous| {
+                        previous.is_alphanumeric() || previous == c
+                    });
+                if next_allows_pair && previous_allows_pair {
+                    line_buffer.insert_char(c);
+                    line_buffer.insert_char(close);
+                    line_buffer.move_left();
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Insert a new line with the indentation for its position
+    pub(crate) fn insert_newline(&self, line_buffer: &mut LineBuffer) {
+        if !self.auto_indent {
+            line_buffer.insert_newline();
+            return;
+        }
+
+        let line = Self::line_before_cursor(line_buffer);
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let open_brackets: i32 = line
+            .chars()
+            .map(|c| {
+                if self.is_bracket_closing(c) {
+                    -1
+                } else if self.closing_for(c).map_or(false, |close| close != c) {
+                    1
+                } else {
+                    0
+                }
+            })
+            .sum();
+        let indent = indent.to_string();
+        let inner_indent = if open_brackets > 0 {
+            indent.clone() + &self.indent_unit
+        } else {
+            indent.clone()
+        };
+
+        let between_pair = Self::char_before_cursor(line_buffer)
+            .and_then(|previous| {
+                self.closing_for(previous)
+                    .filter(|close| *close != previous)
+            })
+            .map_or(false, |close| {
+                Self::char_after_cursor(line_buffer) == Some(close)
+            });
+
+        line_buffer.insert_newline();
+        line_buffer.insert_str(&inner_indent);
+        if between_pair {
+            let cursor = line_buffer.insertion_point();
+            line_buffer.insert_newline();
+            line_buffer.insert_str(&indent);
+            line_buffer.set_insertion_point(cursor);
+        }
+    }
+
+    /// Delete an empty pair around the cursor, returns `false` if nothing was deleted
+    pub(crate) fn backspace(&self, line_buffer: &mut LineBuffer) -> bool {
+        if !self.auto_pairs {
+            return false;
+        }
+        let Some(previous) = Self::char_before_cursor(line_buffer) else {
+            return false;
+        };
+        match (
+            self.closing_for(previous),
+            Self::char_after_cursor(line_buffer),
+        ) {
+            (Some(close), Some(next)) if close == next => {
+                let cursor = line_buffer.insertion_point();
+                line_buffer
+                    .clear_range_safe(cursor - previous.len_utf8()..cursor + next.len_utf8());
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn buffer_with_cursor(text: &str) -> LineBuffer {
+        let cursor = text.find('|').expect("cursor marker");
+        let mut line_buffer = LineBuffer::from(text.replacen('|', "", 1).as_str());
+        line_buffer.set_insertion_point(cursor);
+        line_buffer
+    }
+
+    fn show_cursor(line_buffer: &LineBuffer) -> String {
+        let mut text = line_buffer.get_buffer().to_string();
+        text.insert(line_buffer.insertion_point(), '|');
+        text
+    }
+
+    fn type_chars(text: &str, typed: &str) -> String {
+        let editing = StructuredEditing::default();
+        let mut line_buffer = buffer_with_cursor(text);
+        for c in typed.chars() {
+            if !editing.insert_char(&mut line_buffer, c) {
+                line_buffer.insert_char(c);
+            }
+        }
+        show_cursor(&line_buffer)
+    }
+
+    #[rstest]
+    #[case("|", "(", "(|)")]
+    #[case("|", "(a)", "(a)|")]
+    #[case("|", "\"hi\"", "\"hi\"|")]
+    #[case("don|", "'", "don'|")]
+    #[case("|word", "(", "(|word")]
+    #[case("(|)", "[", "([|])")]
+    #[case("    |", "}", "}|")]
+    #[case("        |", "]", "    ]|")]
+    #[case("  x|", ")", "  x)|")]
+    fn pairs_and_overtypes_characters(
+        #[case] text: &str,
+        #[case] typed: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(type_chars(text, typed), expected);
+    }
+
+    #[rstest]
+    #[case("let x = 1|", "let x = 1\n|")]
+    #[case("    foo|", "    foo\n    |")]
+    #[case("if (a) {|", "if (a) {\n    |")]
+    #[case("call(a,|", "call(a,\n    |")]
+    #[case("f(x)|", "f(x)\n|")]
+    #[case("  {|}", "  {\n      |\n  }")]
+    fn indents_new_lines(#[case] text: &str, #[case] expected: &str) {
+        let mut line_buffer = buffer_with_cursor(text);
+        StructuredEditing::default().insert_newline(&mut line_buffer);
+        assert_eq!(show_cursor(&line_buffer), expected);
+    }
+
+    #[rstest]
+    #[case("(|)", true, "|")]
+    #[case("\"|\"", true, "|")]
+    #[case("(|x)", false, "(|x)")]
+    #[case("|", false, "|")]
+    fn backspace_deletes_empty_pairs(
+        #[case] text: &str,
+        #[case] handled: bool,
+        #[case] expected: &str,
+    ) {
+        let mut line_buffer = buffer_with_cursor(text);
+        assert_eq!(
+            StructuredEditing::default().backspace(&mut line_buffer),
+            handled
+        );
+        assert_eq!(show_cursor(&line_buffer), expected);
+    }
+
+    #[test]
+    fn features_can_be_disabled() {
+        let editing = StructuredEditing::default()
+            .with_auto_pairs(false)
+            .with_auto_indent(false);
+        let mut line_buffer = buffer_with_cursor("  {|");
+        assert!(!editing.insert_char(&mut line_buffer, '('));
+        editing.insert_newline(&mut line_buffer);
+        assert_eq!(show_cursor(&line_buffer), "  {\n|");
+    }
+}
@@ -1,7 +1,9 @@
 use crate::{
+    edit_mode::keybindings::KeyCombination,
     enums::{EventStatus, ReedlineEvent, ReedlineRawEvent},
     PromptEditMode,
 };
+use std::time::Duration;
 
 /// Define the style of parsing for the edit events
 /// Available default options:
@@ -18,4 +20,26 @@ pub trait EditMode: Send {
     fn handle_mode_specific_event(&mut self, _event: ReedlineEvent) -> EventStatus {
         EventStatus::Inapplicable
     }
+
+    /// Keys of a key sequence binding typed so far, empty if no sequence is in progress
+    fn pending_key_sequence(&self) -> &[KeyCombination] {
+        &[]
+    }
+
+    /// Time left to type the next key of the pending key sequence
+    ///
+    /// Once it elapses without input [`EditMode::flush_pending_key_sequence`] is called.
+    fn pending_key_sequence_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Abandon the pending key sequence, returning the event bound to the keys typed so far
+    fn flush_pending_key_sequence(&mut self) -> ReedlineEvent {
+        ReedlineEvent::None
+    }
+
+    /// Numeric argument typed so far for the next command, like readline's `(arg: 4)`
+    fn pending_numeric_argument(&self) -> Option<i64> {
+        None
+    }
 }
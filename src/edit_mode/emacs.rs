@@ -2,7 +2,8 @@ use crate::{
     edit_mode::{
         keybindings::{
             add_common_control_bindings, add_common_edit_bindings, add_common_navigation_bindings,
-            add_common_selection_bindings, edit_bind, Keybindings,
+            add_common_selection_bindings, edit_bind, KeyCombination, KeySequenceMatch,
+            Keybindings,
         },
         EditMode,
     },
@@ -10,6 +11,12 @@ use crate::{
     PromptEditMode,
 };
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+use std::time::{Duration, Instant};
+
+/// Default time to wait for the next key of a key sequence
+const DEFAULT_KEY_SEQUENCE_TIMEOUT: Duration = Duration::from_millis(1000);
+/// Largest numeric argument, larger values are clamped
+const MAX_NUMERIC_ARGUMENT: u32 = 9999;
 
 /// Returns the current default emacs keybindings
 pub fn default_emacs_keybindings() -> Keybindings {
@@ -55,6 +62,7 @@ pub fn default_emacs_keybindings() -> Keybindings {
     kb.add_binding(KM::CONTROL, KC::Char('k'), edit_bind(EC::KillLine));
     kb.add_binding(KM::CONTROL, KC::Char('u'), edit_bind(EC::CutFromStart));
     kb.add_binding(KM::ALT, KC::Char('d'), edit_bind(EC::CutWordRight));
+    kb.add_binding(KM::ALT, KC::Char('y'), edit_bind(EC::YankPop));
     // Edits
     kb.add_binding(KM::CONTROL, KC::Char('t'), edit_bind(EC::SwapGraphemes));
 
@@ -109,19 +117,83 @@ pub fn default_emacs_keybindings() -> Keybindings {
     kb.add_binding(KM::ALT, KC::Char('l'), edit_bind(EC::LowercaseWord));
     kb.add_binding(KM::ALT, KC::Char('c'), edit_bind(EC::CapitalizeChar));
 
+    // Numeric arguments
+    for c in ('0'..='9').chain(['-']) {
+        kb.add_binding(KM::ALT, KC::Char(c), ReedlineEvent::DigitArgument);
+    }
+
+    // *** CTRL-X sequences ***
+    let ctrl_x = KeyCombination::new(KM::CONTROL, KC::Char('x'));
+    kb.add_sequence_binding(
+        vec![
+            ctrl_x.clone(),
+            KeyCombination::new(KM::CONTROL, KC::Char('e')),
+        ],
+        ReedlineEvent::OpenEditor,
+    );
+    kb.add_sequence_binding(
+        vec![ctrl_x.clone(), KeyCombination::new(KM::NONE, KC::Char('u'))],
+        edit_bind(EC::Undo),
+    );
+    kb.add_sequence_binding(
+        vec![ctrl_x, KeyCombination::new(KM::CONTROL, KC::Char('u'))],
+        edit_bind(EC::Undo),
+    );
+
     kb
 }
 
 /// This parses the incoming Events like a emacs style-editor
+///
+/// Key sequences added with [`Keybindings::add_sequence_binding`] are supported. While a
+/// sequence is incomplete the typed keys are shown in front of the prompt indicator. If the
+/// keys typed so far have a binding of their own, it applies when the next key doesn't
+/// continue a sequence or when no key follows within the timeout. Otherwise an unknown
+/// continuation discards the sequence.
+///
+/// Like in readline, keys bound to [`ReedlineEvent::DigitArgument`] (`M-0` to `M-9` and `M--`
+/// by default) and [`ReedlineEvent::UniversalArgument`] start a numeric argument. Further
+/// digits extend it and the next command is repeated that many times. A negative argument
+/// reverses the direction of movements and deletions.
 pub struct Emacs {
     keybindings: Keybindings,
+    key_sequence_timeout: Option<Duration>,
+    pending_sequence: Vec<KeyCombination>,
+    pending_event: ReedlineEvent,
+    pending_since: Option<Instant>,
+    numeric_argument: Option<NumericArgument>,
+}
+
+/// Numeric argument typed before a command
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct NumericArgument {
+    digits: Option<u32>,
+    negative: bool,
+    universal: u32,
+}
+
+impl NumericArgument {
+    fn push_digit(&mut self, digit: u32) {
+        let digits = self.digits.unwrap_or(0).saturating_mul(10) + digit;
+        self.digits = Some(digits.min(MAX_NUMERIC_ARGUMENT));
+    }
+
+    fn value(self) -> i64 {
+        let magnitude = self.digits.unwrap_or_else(|| {
+            4u32.saturating_pow(self.universal)
+                .min(MAX_NUMERIC_ARGUMENT)
+        });
+        if self.negative {
+            -i64::from(magnitude)
+        } else {
+            i64::from(magnitude)
+        }
+    }
 }
 
 impl Default for Emacs {
     fn default() -> Self {
-        Emacs {
-            keybindings: default_emacs_keybindings(),
-        }
+        Emacs::new(default_emacs_keybindings())
     }
 }
 
@@ -130,49 +202,21 @@ impl EditMode for Emacs {
         match event.into() {
             Event::Key(KeyEvent {
                 code, modifiers, ..
-            }) => match (modifiers, code) {
-                (modifier, KeyCode::Char(c)) => {
-                    // Note. The modifier can also be a combination of modifiers, for
-                    // example:
-                    //     KeyModifiers::CONTROL | KeyModifiers::ALT
-                    //     KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT
-                    //
-                    // Mixed modifiers are used by non american keyboards that have extra
-                    // keys like 'alt gr'. Keep this in mind if in the future there are
-                    // cases where an event is not being captured
-                    let c = match modifier {
-                        KeyModifiers::NONE => c,
-                        _ => c.to_ascii_lowercase(),
-                    };
-
-                    self.keybindings
-                        .find_binding(modifier, KeyCode::Char(c))
-                        .unwrap_or_else(|| {
-                            if modifier == KeyModifiers::NONE
-                                || modifier == KeyModifiers::SHIFT
-                                || modifier == KeyModifiers::CONTROL | KeyModifiers::ALT
-                                || modifier
-                                    == KeyModifiers::CONTROL
-                                        | KeyModifiers::ALT
-                                        | KeyModifiers::SHIFT
-                            {
-                                ReedlineEvent::Edit(vec![EditCommand::InsertChar(
-                                    if modifier == KeyModifiers::SHIFT {
-                                        c.to_ascii_uppercase()
-                                    } else {
-                                        c
-                                    },
-                                )])
-                            } else {
-                                ReedlineEvent::None
-                            }
-                        })
+            }) => {
+                let timed_out = self
+                    .pending_key_sequence_timeout()
+                    .map_or(false, |timeout| timeout.is_zero());
+                let expired = if timed_out {
+                    self.flush_pending_key_sequence()
+                } else {
+                    ReedlineEvent::None
+                };
+                if self.extend_numeric_argument(modifiers, code) {
+                    return ReedlineEvent::None;
                 }
-                _ => self
-                    .keybindings
-                    .find_binding(modifiers, code)
-                    .unwrap_or(ReedlineEvent::None),
-            },
+                let event = self.parse_key_sequence(modifiers, code);
+                self.apply_numeric_argument(combine_events(expired, event), code)
+            }
 
             Event::Mouse(MouseEvent {
                 kind: MouseEventKind::Down(button),
@@ -197,12 +241,298 @@ impl EditMode for Emacs {
     fn edit_mode(&self) -> PromptEditMode {
         PromptEditMode::Emacs
     }
+
+    fn pending_key_sequence(&self) -> &[KeyCombination] {
+        &self.pending_sequence
+    }
+
+    fn pending_key_sequence_timeout(&self) -> Option<Duration> {
+        let since = self.pending_since?;
+        Some(self.key_sequence_timeout?.saturating_sub(since.elapsed()))
+    }
+
+    fn flush_pending_key_sequence(&mut self) -> ReedlineEvent {
+        self.pending_sequence.clear();
+        self.pending_since = None;
+        std::mem::replace(&mut self.pending_event, ReedlineEvent::None)
+    }
+
+    fn pending_numeric_argument(&self) -> Option<i64> {
+        self.numeric_argument.map(NumericArgument::value)
+    }
 }
 
 impl Emacs {
     /// Emacs style input parsing constructor if you want to use custom keybindings
     pub const fn new(keybindings: Keybindings) -> Self {
-        Emacs { keybindings }
+        Emacs {
+            keybindings,
+            key_sequence_timeout: Some(DEFAULT_KEY_SEQUENCE_TIMEOUT),
+            pending_sequence: Vec::new(),
+            pending_event: ReedlineEvent::None,
+            pending_since: None,
+            numeric_argument: None,
+        }
+    }
+
+    /// A builder that sets how long to wait for the next key of a key sequence
+    ///
+    /// With `None` an incomplete sequence waits for the next key indefinitely.
+    #[must_use]
+    pub const fn with_key_sequence_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.key_sequence_timeout = timeout;
+        self
+    }
+
+    /// Digits and a leading `-` typed after a numeric argument was started extend it
+    fn extend_numeric_argument(&mut self, modifier: KeyModifiers, code: KeyCode) -> bool {
+        let Some(argument) = self.numeric_argument.as_mut() else {
+            return false;
+        };
+        if modifier != KeyModifiers::NONE || !self.pending_sequence.is_empty() {
+            return false;
+        }
+        match code {
+            KeyCode::Char(c) if c.is_ascii_digit() => {
+                argument.push_digit(c.to_digit(10).unwrap_or_default());
+                true
+            }
+            KeyCode::Char('-') if argument.digits.is_none() && !argument.negative => {
+                argument.negative = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Update the numeric argument or apply it to the command the key is bound to
+    fn apply_numeric_argument(&mut self, event: ReedlineEvent, code: KeyCode) -> ReedlineEvent {
+        match event {
+            ReedlineEvent::DigitArgument => {
+                let argument = self.numeric_argument.get_or_insert_with(Default::default);
+                match code {
+                    KeyCode::Char(c) if c.is_ascii_digit() => {
+                        argument.push_digit(c.to_digit(10).unwrap_or_default());
+                    }
+                    KeyCode::Char('-') if argument.digits.is_none() => {
+                        argument.negative = !argument.negative;
+                    }
+                    _ => {}
+                }
+                ReedlineEvent::None
+            }
+            ReedlineEvent::UniversalArgument => {
+                let argument = self.numeric_argument.get_or_insert_with(Default::default);
+                if argument.digits.is_none() {
+                    argument.universal += 1;
+                }
+                ReedlineEvent::None
+            }
+            ReedlineEvent::None => ReedlineEvent::None,
+            event => match self.numeric_argument.take() {
+                Some(argument) => repeat_event(event, argument.value()),
+                None => event,
+            },
+        }
+    }
+
+    fn parse_key_sequence(&mut self, modifier: KeyModifiers, code: KeyCode) -> ReedlineEvent {
+        let key_code = match (modifier, code) {
+            (KeyModifiers::NONE, code) => code,
+            (_, KeyCode::Char(c)) => KeyCode::Char(c.to_ascii_lowercase()),
+            (_, code) => code,
+        };
+        self.pending_sequence
+            .push(KeyCombination::new(modifier, key_code));
+
+        match self
+            .keybindings
+            .find_sequence_binding(&self.pending_sequence)
+        {
+            KeySequenceMatch::Prefix(event) => {
+                self.pending_event = if self.pending_sequence.len() == 1 {
+                    self.parse_key(modifier, code)
+                } else {
+                    event.unwrap_or(ReedlineEvent::None)
+                };
+                self.pending_since = Some(Instant::now());
+                ReedlineEvent::None
+            }
+            KeySequenceMatch::Complete(event) if self.pending_sequence.len() > 1 => {
+                self.flush_pending_key_sequence();
+                event
+            }
+            KeySequenceMatch::NoMatch if self.pending_sequence.len() > 1 => {
+                self.pending_sequence.pop();
+                let pending_event = self.flush_pending_key_sequence();
+                if pending_event == ReedlineEvent::None {
+                    // Like readline, an unknown continuation discards the whole sequence
+                    ReedlineEvent::None
+                } else {
+                    combine_events(pending_event, self.parse_key_sequence(modifier, code))
+                }
+            }
+            _ => {
+                self.flush_pending_key_sequence();
+                self.parse_key(modifier, code)
+            }
+        }
+    }
+
+    fn parse_key(&self, modifier: KeyModifiers, code: KeyCode) -> ReedlineEvent {
+        match (modifier, code) {
+            (modifier, KeyCode::Char(c)) => {
+                // Note. The modifier can also be a combination of modifiers, for
+                // example:
+                //     KeyModifiers::CONTROL | KeyModifiers::ALT
+                //     KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT
+                //
+                // Mixed modifiers are used by non american keyboards that have extra
+                // keys like 'alt gr'. Keep this in mind if in the future there are
+                // cases where an event is not being captured
+                let c = match modifier {
+                    KeyModifiers::NONE => c,
+                    _ => c.to_ascii_lowercase(),
+                };
+
+                self.keybindings
+                    .find_binding(modifier, KeyCode::Char(c))
+                    .unwrap_or_else(|| {
+                        if modifier == KeyModifiers::NONE
+                            || modifier == KeyModifiers::SHIFT
+                            || modifier == KeyModifiers::CONTROL | KeyModifiers::ALT
+                            || modifier
+                                == KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT
+                        {
+                            ReedlineEvent::Edit(vec![EditCommand::InsertChar(
+                                if modifier == KeyModifiers::SHIFT {
+                                    c.to_ascii_uppercase()
+                                } else {
+                                    c
+                                },
+                            )])
+                        } else {
+                            ReedlineEvent::None
+                        }
+                    })
+            }
+            _ => self
+                .keybindings
+                .find_binding(modifier, code)
+                .unwrap_or(ReedlineEvent::None),
+        }
+    }
+}
+
+/// Repeat an event `count` times, reversing its direction for negative counts
+///
+/// Events that don't move or edit ignore the count.
+fn repeat_event(event: ReedlineEvent, count: i64) -> ReedlineEvent {
+    let times = usize::try_from(count.unsigned_abs()).unwrap_or_default();
+    match event {
+        ReedlineEvent::Edit(commands) => {
+            let commands: Vec<EditCommand> = commands
+                .into_iter()
+                .filter_map(|command| {
+                    if count < 0 {
+                        reverse_edit_command(command)
+                    } else {
+                        Some(command)
+                    }
+                })
+                .collect();
+            let length = commands.len() * times;
+            ReedlineEvent::Edit(commands.into_iter().cycle().take(length).collect())
+        }
+        ReedlineEvent::UntilFound(events) => ReedlineEvent::UntilFound(
+            events
+                .into_iter()
+                .map(|event| repeat_event(event, count))
+                .collect(),
+        ),
+        ReedlineEvent::Multiple(events) => ReedlineEvent::Multiple(
+            events
+                .into_iter()
+                .map(|event| repeat_event(event, count))
+                .collect(),
+        ),
+        ReedlineEvent::Left
+        | ReedlineEvent::Right
+        | ReedlineEvent::Up
+        | ReedlineEvent::Down
+        | ReedlineEvent::PreviousHistory
+        | ReedlineEvent::NextHistory
+        | ReedlineEvent::MenuLeft
+        | ReedlineEvent::MenuRight
+        | ReedlineEvent::MenuUp
+        | ReedlineEvent::MenuDown
+        | ReedlineEvent::MenuNext
+        | ReedlineEvent::MenuPrevious => {
+            let event = if count < 0 {
+                reverse_event(event)
+            } else {
+                event
+            };
+            ReedlineEvent::Multiple(vec![event; times])
+        }
+        event => event,
+    }
+}
+
+fn reverse_event(event: ReedlineEvent) -> ReedlineEvent {
+    match event {
+        ReedlineEvent::Left => ReedlineEvent::Right,
+        ReedlineEvent::Right => ReedlineEvent::Left,
+        ReedlineEvent::Up => ReedlineEvent::Down,
+        ReedlineEvent::Down => ReedlineEvent::Up,
+        ReedlineEvent::PreviousHistory => ReedlineEvent::NextHistory,
+        ReedlineEvent::NextHistory => ReedlineEvent::PreviousHistory,
+        ReedlineEvent::MenuLeft => ReedlineEvent::MenuRight,
+        ReedlineEvent::MenuRight => ReedlineEvent::MenuLeft,
+        ReedlineEvent::MenuUp => ReedlineEvent::MenuDown,
+        ReedlineEvent::MenuDown => ReedlineEvent::MenuUp,
+        ReedlineEvent::MenuNext => ReedlineEvent::MenuPrevious,
+        ReedlineEvent::MenuPrevious => ReedlineEvent::MenuNext,
+        event => event,
+    }
+}
+
+/// The command going in the opposite direction, `None` for insertions which a negative
+/// argument cancels like in readline
+fn reverse_edit_command(command: EditCommand) -> Option<EditCommand> {
+    use EditCommand as EC;
+
+    Some(match command {
+        EC::MoveLeft { select } => EC::MoveRight { select },
+        EC::MoveRight { select } => EC::MoveLeft { select },
+        EC::MoveWordLeft { select } => EC::MoveWordRight { select },
+        EC::MoveWordRight { select } => EC::MoveWordLeft { select },
+        EC::Backspace => EC::Delete,
+        EC::Delete => EC::Backspace,
+        EC::BackspaceWord => EC::DeleteWord,
+        EC::DeleteWord => EC::BackspaceWord,
+        EC::CutWordLeft => EC::CutWordRight,
+        EC::CutWordRight => EC::CutWordLeft,
+        EC::CutBigWordLeft => EC::CutBigWordRight,
+        EC::CutBigWordRight => EC::CutBigWordLeft,
+        EC::KillLine | EC::CutToLineEnd => EC::CutFromLineStart,
+        EC::CutFromLineStart => EC::CutToLineEnd,
+        EC::InsertChar(_)
+        | EC::InsertString(_)
+        | EC::InsertNewline
+        | EC::PasteCutBufferBefore
+        | EC::PasteCutBufferAfter
+        | EC::Paste
+        | EC::YankPop => return None,
+        command => command,
+    })
+}
+
+/// Run `second` after `first`, skipping [`ReedlineEvent::None`]
+fn combine_events(first: ReedlineEvent, second: ReedlineEvent) -> ReedlineEvent {
+    match (first, second) {
+        (ReedlineEvent::None, event) | (event, ReedlineEvent::None) => event,
+        (first, second) => ReedlineEvent::Multiple(vec![first, second]),
     }
 }
 
@@ -322,4 +652,201 @@ mod test {
 
         assert_eq!(result, ReedlineEvent::Edit(vec![EditCommand::KillLine]));
     }
+
+    fn key(modifiers: KeyModifiers, c: char) -> ReedlineRawEvent {
+        ReedlineRawEvent::try_from(Event::Key(KeyEvent::new(KeyCode::Char(c), modifiers))).unwrap()
+    }
+
+    fn emacs_with_sequences() -> Emacs {
+        let ctrl = |c| KeyCombination::new(KeyModifiers::CONTROL, KeyCode::Char(c));
+        let plain = |c| KeyCombination::new(KeyModifiers::NONE, KeyCode::Char(c));
+        let mut keybindings = default_emacs_keybindings();
+        keybindings.add_sequence_binding(vec![ctrl('x'), ctrl('e')], ReedlineEvent::OpenEditor);
+        keybindings.add_sequence_binding(vec![ctrl('x'), plain('u')], edit_bind(EditCommand::Undo));
+        keybindings.add_sequence_binding(vec![plain('j'), plain('k')], ReedlineEvent::Esc);
+        Emacs::new(keybindings)
+    }
+
+    #[test]
+    fn key_sequence_waits_for_the_next_key() {
+        let mut emacs = emacs_with_sequences();
+
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::CONTROL, 'x')),
+            ReedlineEvent::None
+        );
+        assert_eq!(
+            emacs.pending_key_sequence(),
+            [KeyCombination::new(
+                KeyModifiers::CONTROL,
+                KeyCode::Char('x')
+            )]
+        );
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::CONTROL, 'e')),
+            ReedlineEvent::OpenEditor
+        );
+        assert!(emacs.pending_key_sequence().is_empty());
+
+        emacs.parse_event(key(KeyModifiers::CONTROL, 'x'));
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::NONE, 'u')),
+            ReedlineEvent::Edit(vec![EditCommand::Undo])
+        );
+    }
+
+    #[test]
+    fn unknown_continuation_discards_the_sequence() {
+        let mut emacs = emacs_with_sequences();
+
+        emacs.parse_event(key(KeyModifiers::CONTROL, 'x'));
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::NONE, 'q')),
+            ReedlineEvent::None
+        );
+        assert!(emacs.pending_key_sequence().is_empty());
+    }
+
+    #[test]
+    fn prefix_with_own_binding_applies_before_the_next_key() {
+        let mut emacs = emacs_with_sequences();
+
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::NONE, 'j')),
+            ReedlineEvent::None
+        );
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::NONE, 'x')),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar('j')]),
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar('x')]),
+            ])
+        );
+
+        emacs.parse_event(key(KeyModifiers::NONE, 'j'));
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::NONE, 'j')),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('j')])
+        );
+        assert_eq!(
+            emacs.flush_pending_key_sequence(),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('j')])
+        );
+        assert!(emacs.pending_key_sequence().is_empty());
+    }
+
+    #[test]
+    fn expired_key_sequence_is_abandoned() {
+        let mut emacs = emacs_with_sequences().with_key_sequence_timeout(Some(Duration::ZERO));
+
+        emacs.parse_event(key(KeyModifiers::NONE, 'j'));
+        assert_eq!(emacs.pending_key_sequence_timeout(), Some(Duration::ZERO));
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::NONE, 'k')),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar('j')]),
+                ReedlineEvent::Edit(vec![EditCommand::InsertChar('k')]),
+            ])
+        );
+
+        let mut emacs = emacs_with_sequences().with_key_sequence_timeout(None);
+        emacs.parse_event(key(KeyModifiers::NONE, 'j'));
+        assert_eq!(emacs.pending_key_sequence_timeout(), None);
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::NONE, 'k')),
+            ReedlineEvent::Esc
+        );
+    }
+
+    fn special_key(modifiers: KeyModifiers, code: KeyCode) -> ReedlineRawEvent {
+        ReedlineRawEvent::try_from(Event::Key(KeyEvent::new(code, modifiers))).unwrap()
+    }
+
+    #[test]
+    fn numeric_argument_repeats_the_next_command() {
+        let mut emacs = Emacs::default();
+
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::ALT, '1')),
+            ReedlineEvent::None
+        );
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::NONE, '2')),
+            ReedlineEvent::None
+        );
+        assert_eq!(emacs.pending_numeric_argument(), Some(12));
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::NONE, 'x')),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('x'); 12])
+        );
+        assert_eq!(emacs.pending_numeric_argument(), None);
+
+        emacs.parse_event(key(KeyModifiers::ALT, '3'));
+        assert_eq!(
+            emacs.parse_event(special_key(KeyModifiers::NONE, KeyCode::Backspace)),
+            ReedlineEvent::Edit(vec![EditCommand::Backspace; 3])
+        );
+        emacs.parse_event(key(KeyModifiers::ALT, '2'));
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::CONTROL, 'b')),
+            ReedlineEvent::UntilFound(vec![
+                ReedlineEvent::Multiple(vec![ReedlineEvent::MenuLeft; 2]),
+                ReedlineEvent::Multiple(vec![ReedlineEvent::Left; 2]),
+            ])
+        );
+    }
+
+    #[test]
+    fn negative_numeric_argument_reverses_the_direction() {
+        let mut emacs = Emacs::default();
+
+        emacs.parse_event(key(KeyModifiers::ALT, '-'));
+        emacs.parse_event(key(KeyModifiers::NONE, '2'));
+        assert_eq!(emacs.pending_numeric_argument(), Some(-2));
+        assert_eq!(
+            emacs.parse_event(special_key(KeyModifiers::NONE, KeyCode::Backspace)),
+            ReedlineEvent::Edit(vec![EditCommand::Delete; 2])
+        );
+
+        emacs.parse_event(key(KeyModifiers::ALT, '-'));
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::CONTROL, 'k')),
+            ReedlineEvent::Edit(vec![EditCommand::CutFromLineStart])
+        );
+
+        emacs.parse_event(key(KeyModifiers::ALT, '-'));
+        assert_eq!(
+            emacs.parse_event(key(KeyModifiers::NONE, 'x')),
+            ReedlineEvent::Edit(vec![])
+        );
+    }
+
+    #[test]
+    fn universal_argument_multiplies_by_four() {
+        let mut keybindings = default_emacs_keybindings();
+        keybindings.add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('u'),
+            ReedlineEvent::UniversalArgument,
+        );
+        let mut emacs = Emacs::new(keybindings);
+
+        emacs.parse_event(key(KeyModifiers::CONTROL, 'u'));
+        assert_eq!(emacs.pending_numeric_argument(), Some(4));
+        emacs.parse_event(key(KeyModifiers::CONTROL, 'u'));
+        assert_eq!(emacs.pending_numeric_argument(), Some(16));
+        assert_eq!(
+            emacs.parse_event(special_key(KeyModifiers::NONE, KeyCode::Right)),
+            ReedlineEvent::UntilFound(vec![
+                ReedlineEvent::HistoryHintComplete,
+                ReedlineEvent::Multiple(vec![ReedlineEvent::MenuRight; 16]),
+                ReedlineEvent::Multiple(vec![ReedlineEvent::Right; 16]),
+            ])
+        );
+
+        emacs.parse_event(key(KeyModifiers::CONTROL, 'u'));
+        emacs.parse_event(key(KeyModifiers::NONE, '1'));
+        emacs.parse_event(key(KeyModifiers::NONE, '0'));
+        assert_eq!(emacs.pending_numeric_argument(), Some(10));
+    }
 }
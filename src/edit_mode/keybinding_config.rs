@@ -0,0 +1,670 @@
+use {
+    super::{
+        default_emacs_keybindings, default_vi_insert_keybindings, default_vi_normal_keybindings,
+        keybindings::{key_sequence_to_string, KeyCombination},
+        Keybindings,
+    },
+    crate::{
+        enums::ReedlineEvent,
+        result::{ReedlineError, ReedlineErrorVariants},
+        Result,
+    },
+    crossterm::event::{KeyCode, KeyModifiers},
+    serde::{de, Deserialize, Deserializer, Serialize, Serializer},
+};
+
+const MODIFIER_NAMES: [(&str, KeyModifiers); 6] = [
+    ("control", KeyModifiers::CONTROL),
+    ("alt", KeyModifiers::ALT),
+    ("shift", KeyModifiers::SHIFT),
+    ("super", KeyModifiers::SUPER),
+    ("hyper", KeyModifiers::HYPER),
+    ("meta", KeyModifiers::META),
+];
+
+const KEYCODE_NAMES: [(&str, KeyCode); 23] = [
+    ("backspace", KeyCode::Backspace),
+    ("enter", KeyCode::Enter),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("tab", KeyCode::Tab),
+    ("backtab", KeyCode::BackTab),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("space", KeyCode::Char(' ')),
+    ("null", KeyCode::Null),
+    ("esc", KeyCode::Esc),
+    ("capslock", KeyCode::CapsLock),
+    ("scrolllock", KeyCode::ScrollLock),
+    ("numlock", KeyCode::NumLock),
+    ("printscreen", KeyCode::PrintScreen),
+    ("pause", KeyCode::Pause),
+    ("menu", KeyCode::Menu),
+];
+
+/// The keybindings of an edit mode a configured binding belongs to
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum KeybindingMode {
+    /// Keybindings of the [`crate::Emacs`] edit mode
+    Emacs,
+    /// Insert mode keybindings of the [`crate::Vi`] edit mode
+    ViInsert,
+    /// Normal mode keybindings of the [`crate::Vi`] edit mode
+    ViNormal,
+}
+
+/// Modifier names joined by `_`, like `control_alt`, or `none`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ModifierName(KeyModifiers);
+
+impl<'de> Deserialize<'de> for ModifierName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        parse_modifier(&name)
+            .map(ModifierName)
+            .map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for ModifierName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&modifier_name(self.0))
+    }
+}
+
+/// Key names like `enter`, `f5`, `space` or `char_a`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct KeyCodeName(KeyCode);
+
+impl<'de> Deserialize<'de> for KeyCodeName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        parse_keycode(&name)
+            .map(KeyCodeName)
+            .map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for KeyCodeName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let name = keycode_name(self.0).ok_or_else(|| {
+            serde::ser::Error::custom(format!("keycode {:?} can't be configured", self.0))
+        })?;
+        serializer.serialize_str(&name)
+    }
+}
+
+fn parse_modifier(name: &str) -> std::result::Result<KeyModifiers, String> {
+    if name.eq_ignore_ascii_case("none") {
+        return Ok(KeyModifiers::NONE);
+    }
+    name.split('_')
+        .try_fold(KeyModifiers::NONE, |modifiers, part| {
+            MODIFIER_NAMES
+                .iter()
+                .find(|(known, _)| known.eq_ignore_ascii_case(part))
+                .map(|(_, modifier)| modifiers | *modifier)
+                .ok_or_else(|| {
+                    format!(
+                    "unknown modifier `{part}` in `{name}`, expected `none` or a combination of {}",
+                    MODIFIER_NAMES.map(|(known, _)| format!("`{known}`")).join(", ")
+                )
+                })
+        })
+}
+
+fn modifier_name(modifiers: KeyModifiers) -> String {
+    let names: Vec<&str> = MODIFIER_NAMES
+        .iter()
+        .filter(|(_, modifier)| modifiers.contains(*modifier))
+        .map(|(name, _)| *name)
+        .collect();
+    if names.is_empty() {
+        "none".to_string()
+    } else {
+        names.join("_")
+    }
+}
+
+fn parse_keycode(name: &str) -> std::result::Result<KeyCode, String> {
+    if let Some((_, keycode)) = KEYCODE_NAMES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(name))
+    {
+        return Ok(*keycode);
+    }
+    if let Some(character) = name
+        .strip_prefix("char_")
+        .or_else(|| name.strip_prefix("Char_"))
+    {
+        let mut chars = character.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(KeyCode::Char(c)),
+            _ => Err(format!(
+                "`{name}` must name exactly one character after `char_`"
+            )),
+        };
+    }
+    if let Some(number) = name.strip_prefix(['f', 'F']) {
+        if let Ok(number @ 1..=24) = number.parse::<u8>() {
+            return Ok(KeyCode::F(number));
+        }
+    }
+    Err(format!(
+        "unknown keycode `{name}`, expected `char_<character>`, `f<1-24>` or one of {}",
+        KEYCODE_NAMES
+            .map(|(known, _)| format!("`{known}`"))
+            .join(", ")
+    ))
+}
+
+fn keycode_name(keycode: KeyCode) -> Option<String> {
+    if let Some((name, _)) = KEYCODE_NAMES.iter().find(|(_, known)| *known == keycode) {
+        return Some(name.to_string());
+    }
+    match keycode {
+        KeyCode::Char(c) => Some(format!("char_{c}")),
+        KeyCode::F(number) => Some(format!("f{number}")),
+        _ => None,
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct KeybindingEntry {
+    mode: KeybindingMode,
+    modifier: ModifierName,
+    keycode: KeyCodeName,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    followed_by: Vec<KeyEntry>,
+    event: ReedlineEvent,
+}
+
+/// Further key of a key sequence binding
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct KeyEntry {
+    modifier: ModifierName,
+    keycode: KeyCodeName,
+}
+
+impl KeybindingEntry {
+    fn sequence(&self) -> Vec<KeyCombination> {
+        std::iter::once((self.modifier, self.keycode))
+            .chain(
+                self.followed_by
+                    .iter()
+                    .map(|key| (key.modifier, key.keycode)),
+            )
+            .map(|(modifier, keycode)| KeyCombination::new(modifier.0, keycode.0))
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct KeybindingFile {
+    #[serde(default)]
+    keybindings: Vec<KeybindingEntry>,
+}
+
+fn config_error(message: impl std::fmt::Display) -> ReedlineError {
+    ReedlineError(ReedlineErrorVariants::KeybindingConfigError(
+        message.to_string(),
+    ))
+}
+
+fn inline_toml<T: Serialize>(value: &T) -> Result<String> {
+    toml::Value::try_from(value)
+        .map(|value| value.to_string())
+        .map_err(config_error)
+}
+
+/// Keybindings of all edit modes that can be loaded from and saved to TOML or JSON
+///
+/// Each binding lists the edit mode, the modifiers, the key and the [`ReedlineEvent`] in
+/// the same form used by the serde implementation of [`ReedlineEvent`]:
+///
+/// ```toml
+/// [[keybindings]]
+/// mode = "emacs"
+/// modifier = "control_alt"
+/// keycode = "char_u"
+/// event = { Edit = [{ MoveToStart = { select = false } }] }
+///
+/// [[keybindings]]
+/// mode = "vi_insert"
+/// modifier = "none"
+/// keycode = "f5"
+/// event = "ClearScreen"
+///
+/// [[keybindings]]
+/// mode = "emacs"
+/// modifier = "control"
+/// keycode = "char_x"
+/// followed_by = [{ modifier = "control", keycode = "char_e" }]
+/// event = "OpenEditor"
+/// ```
+///
+/// Key sequences list the keys after the first one in `followed_by`. Modifiers are `none` or names out of `control`, `alt`, `shift`, `super`, `hyper` and
+/// `meta` joined by `_`. Keys are `char_<character>`, `f<1-24>` or named keys like `enter`,
+/// `space` or `pagedown`. Loading reports the position of unknown fields, modifiers, keys
+/// and malformed events.
+///
+/// # Example
+/// ```rust
+/// use reedline::{Emacs, KeybindingsConfig, Reedline};
+///
+/// let mut config = KeybindingsConfig::default_keybindings();
+/// config
+///     .load_toml_str(
+///         r#"
+///         [[keybindings]]
+///         mode = "emacs"
+///         modifier = "control"
+///         keycode = "char_g"
+///         event = "ClearScreen"
+///         "#,
+///     )
+///     .unwrap();
+///
+/// let line_editor = Reedline::create().with_edit_mode(Box::new(Emacs::new(config.emacs)));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct KeybindingsConfig {
+    /// Keybindings of the [`crate::Emacs`] edit mode
+    pub emacs: Keybindings,
+    /// Insert mode keybindings of the [`crate::Vi`] edit mode
+    pub vi_insert: Keybindings,
+    /// Normal mode keybindings of the [`crate::Vi`] edit mode
+    pub vi_normal: Keybindings,
+}
+
+impl KeybindingsConfig {
+    /// Configuration with the default keybindings of every edit mode
+    pub fn default_keybindings() -> Self {
+        Self {
+            emacs: default_emacs_keybindings(),
+            vi_insert: default_vi_insert_keybindings(),
+            vi_normal: default_vi_normal_keybindings(),
+        }
+    }
+
+    /// Parse a TOML configuration into otherwise empty keybindings
+    pub fn from_toml_str(toml: &str) -> Result<Self> {
+        let mut config = Self::default();
+        config.load_toml_str(toml)?;
+        Ok(config)
+    }
+
+    /// Parse a JSON configuration into otherwise empty keybindings
+    pub fn from_json_str(json: &str) -> Result<Self> {
+        let mut config = Self::default();
+        config.load_json_str(json)?;
+        Ok(config)
+    }
+
+    /// Add the bindings of a TOML configuration, replacing existing bindings of the same keys
+    ///
+    /// Nothing is added if the configuration contains an error.
+    pub fn load_toml_str(&mut self, toml: &str) -> Result<()> {
+        let file: KeybindingFile = toml::from_str(toml).map_err(config_error)?;
+        self.load(file)
+    }
+
+    /// Add the bindings of a JSON configuration, replacing existing bindings of the same keys
+    ///
+    /// Nothing is added if the configuration contains an error.
+    pub fn load_json_str(&mut self, json: &str) -> Result<()> {
+        let file: KeybindingFile = serde_json::from_str(json).map_err(config_error)?;
+        self.load(file)
+    }
+
+    /// Serialize all bindings to TOML, sorted by mode, modifiers and key
+    ///
+    /// Events are written as inline tables to keep every binding in one block.
+    pub fn to_toml_string(&self) -> Result<String> {
+        let blocks = self
+            .to_file()
+            .keybindings
+            .iter()
+            .map(|entry| {
+                let followed_by = if entry.followed_by.is_empty() {
+                    String::new()
+                } else {
+                    format!("followed_by = {}\n", inline_toml(&entry.followed_by)?)
+                };
+                Ok(format!(
+                    "[[keybindings]]\nmode = {}\nmodifier = {}\nkeycode = {}\n{followed_by}event = {}\n",
+                    inline_toml(&entry.mode)?,
+                    inline_toml(&entry.modifier)?,
+                    inline_toml(&entry.keycode)?,
+                    inline_toml(&entry.event)?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(blocks.join("\n"))
+    }
+
+    /// Serialize all bindings to pretty printed JSON, sorted by mode, modifiers and key
+    pub fn to_json_string(&self) -> Result<String> {
+        serde_json::to_string_pretty(&self.to_file()).map_err(config_error)
+    }
+
+    /// The keybindings of an edit mode
+    pub fn keybindings(&self, mode: KeybindingMode) -> &Keybindings {
+        match mode {
+            KeybindingMode::Emacs => &self.emacs,
+            KeybindingMode::ViInsert => &self.vi_insert,
+            KeybindingMode::ViNormal => &self.vi_normal,
+        }
+    }
+
+    /// The mutable keybindings of an edit mode
+    pub fn keybindings_mut(&mut self, mode: KeybindingMode) -> &mut Keybindings {
+        match mode {
+            KeybindingMode::Emacs => &mut self.emacs,
+            KeybindingMode::ViInsert => &mut self.vi_insert,
+            KeybindingMode::ViNormal => &mut self.vi_normal,
+        }
+    }
+
+    fn load(&mut self, file: KeybindingFile) -> Result<()> {
+        for (index, entry) in file.keybindings.iter().enumerate() {
+            if matches!(&entry.event, ReedlineEvent::UntilFound(events) if events.is_empty()) {
+                return Err(config_error(format!(
+                    "keybinding {} ({:?} {}): UntilFound must contain at least one event",
+                    index + 1,
+                    entry.mode,
+                    key_sequence_to_string(&entry.sequence()),
+                )));
+            }
+        }
+        for entry in file.keybindings {
+            let sequence = entry.sequence();
+            self.keybindings_mut(entry.mode)
+                .add_sequence_binding(sequence, entry.event);
+        }
+        Ok(())
+    }
+
+    fn to_file(&self) -> KeybindingFile {
+        let mut keybindings: Vec<KeybindingEntry> = [
+            KeybindingMode::Emacs,
+            KeybindingMode::ViInsert,
+            KeybindingMode::ViNormal,
+        ]
+        .into_iter()
+        .flat_map(|mode| {
+            let keybindings = self.keybindings(mode);
+            keybindings
+                .get_keybindings()
+                .iter()
+                .map(|(combination, event)| (vec![combination.clone()], event.clone()))
+                .chain(keybindings.get_sequence_bindings())
+                .map(move |(sequence, event)| {
+                    let mut keys =
+                        sequence
+                            .into_iter()
+                            .map(|KeyCombination { modifier, key_code }| KeyEntry {
+                                modifier: ModifierName(modifier),
+                                keycode: KeyCodeName(key_code),
+                            });
+                    let first = keys.next().expect("bindings have at least one key");
+                    KeybindingEntry {
+                        mode,
+                        modifier: first.modifier,
+                        keycode: first.keycode,
+                        followed_by: keys.collect(),
+                        event,
+                    }
+                })
+        })
+        .collect();
+        keybindings.sort_by_cached_key(|entry| {
+            let keys: Vec<_> = entry
+                .sequence()
+                .into_iter()
+                .map(|combination| {
+                    (
+                        combination.modifier.bits(),
+                        keycode_name(combination.key_code),
+                    )
+                })
+                .collect();
+            (entry.mode, keys)
+        });
+        KeybindingFile { keybindings }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EditCommand;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("none", KeyModifiers::NONE)]
+    #[case("control", KeyModifiers::CONTROL)]
+    #[case("Control_Alt", KeyModifiers::CONTROL | KeyModifiers::ALT)]
+    #[case("shift_super", KeyModifiers::SHIFT | KeyModifiers::SUPER)]
+    fn parses_modifiers(#[case] name: &str, #[case] expected: KeyModifiers) {
+        assert_eq!(parse_modifier(name), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("enter", KeyCode::Enter)]
+    #[case("PageDown", KeyCode::PageDown)]
+    #[case("space", KeyCode::Char(' '))]
+    #[case("char_a", KeyCode::Char('a'))]
+    #[case("char_ß", KeyCode::Char('ß'))]
+    #[case("f12", KeyCode::F(12))]
+    fn parses_keycodes(#[case] name: &str, #[case] expected: KeyCode) {
+        assert_eq!(parse_keycode(name), Ok(expected));
+        assert_eq!(
+            parse_keycode(&keycode_name(expected).unwrap()),
+            Ok(expected)
+        );
+    }
+
+    #[rstest]
+    #[case("char_ab")]
+    #[case("f25")]
+    #[case("enterr")]
+    fn rejects_unknown_keycodes(#[case] name: &str) {
+        assert!(parse_keycode(name).is_err());
+    }
+
+    #[test]
+    fn loads_toml_into_the_edit_modes() {
+        let config = KeybindingsConfig::from_toml_str(
+            r#"
+            [[keybindings]]
+            mode = "emacs"
+            modifier = "control_alt"
+            keycode = "char_u"
+            event = { Edit = [{ MoveToStart = { select = false } }] }
+
+            [[keybindings]]
+            mode = "vi_normal"
+            modifier = "none"
+            keycode = "f5"
+            event = { UntilFound = ["MenuNext", "Down"] }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.emacs.find_binding(
+                KeyModifiers::CONTROL | KeyModifiers::ALT,
+                KeyCode::Char('u')
+            ),
+            Some(ReedlineEvent::Edit(vec![EditCommand::MoveToStart {
+                select: false
+            }]))
+        );
+        assert_eq!(
+            config
+                .vi_normal
+                .find_binding(KeyModifiers::NONE, KeyCode::F(5)),
+            Some(ReedlineEvent::UntilFound(vec![
+                ReedlineEvent::MenuNext,
+                ReedlineEvent::Down
+            ]))
+        );
+        assert!(config.vi_insert.get_keybindings().is_empty());
+    }
+
+    #[test]
+    fn loads_json() {
+        let config = KeybindingsConfig::from_json_str(
+            r#"{"keybindings": [
+                {"mode": "vi_insert", "modifier": "control", "keycode": "char_r", "event": "SearchHistory"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config
+                .vi_insert
+                .find_binding(KeyModifiers::CONTROL, KeyCode::Char('r')),
+            Some(ReedlineEvent::SearchHistory)
+        );
+    }
+
+    #[rstest]
+    #[case(
+        "[[keybindings]]\nmode = \"emacs\"\nmodifier = \"ctrl\"\nkeycode = \"char_a\"\nevent = \"Esc\"\n",
+        &["line 3", "unknown modifier `ctrl`"]
+    )]
+    #[case(
+        "[[keybindings]]\nmode = \"emacs\"\nmodifier = \"none\"\nkeycode = \"char_a\"\nevent = \"Escape\"\n",
+        &["line 5", "unknown variant `Escape`"]
+    )]
+    #[case(
+        "[[keybindings]]\nmode = \"emacs\"\nmodifier = \"none\"\nkeycode = \"char_a\"\nevent = { Edit = [{ MoveToStart = {} }] }\n",
+        &["line 5", "missing field `select`"]
+    )]
+    #[case(
+        "[[keybindings]]\nmode = \"vim\"\nmodifier = \"none\"\nkeycode = \"char_a\"\nevent = \"Esc\"\n",
+        &["line 2", "unknown variant `vim`"]
+    )]
+    #[case(
+        "[[keybindings]]\nmode = \"emacs\"\nmodifiers = \"none\"\nkeycode = \"char_a\"\nevent = \"Esc\"\n",
+        &["line 3", "unknown field `modifiers`"]
+    )]
+    #[case(
+        "[[keybindings]]\nmode = \"emacs\"\nmodifier = \"none\"\nkeycode = \"char_a\"\nevent = { UntilFound = [] }\n",
+        &["keybinding 1 (Emacs a)", "UntilFound"]
+    )]
+    fn reports_precise_errors(#[case] toml: &str, #[case] expected: &[&str]) {
+        let mut config = KeybindingsConfig::default();
+        let message = config.load_toml_str(toml).unwrap_err().to_string();
+        for part in expected {
+            assert!(message.contains(part), "{part:?} not found in {message:?}");
+        }
+        assert!(config.emacs.get_keybindings().is_empty());
+    }
+
+    #[test]
+    fn json_errors_report_the_position() {
+        let message = KeybindingsConfig::from_json_str(
+            "{\"keybindings\": [\n{\"mode\": \"emacs\", \"modifier\": \"none\", \"keycode\": \"nope\", \"event\": \"Esc\"}]}",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(message.contains("unknown keycode `nope`"));
+        assert!(message.contains("line 2"));
+    }
+
+    #[test]
+    fn default_keybindings_round_trip() {
+        let config = KeybindingsConfig::default_keybindings();
+
+        let from_toml =
+            KeybindingsConfig::from_toml_str(&config.to_toml_string().unwrap()).unwrap();
+        let from_json =
+            KeybindingsConfig::from_json_str(&config.to_json_string().unwrap()).unwrap();
+
+        for mode in [
+            KeybindingMode::Emacs,
+            KeybindingMode::ViInsert,
+            KeybindingMode::ViNormal,
+        ] {
+            assert_eq!(
+                from_toml.keybindings(mode).get_keybindings(),
+                config.keybindings(mode).get_keybindings()
+            );
+            assert_eq!(
+                from_json.keybindings(mode).get_keybindings(),
+                config.keybindings(mode).get_keybindings()
+            );
+        }
+    }
+
+    #[test]
+    fn serialization_is_sorted() {
+        let mut config = KeybindingsConfig::default();
+        config
+            .vi_normal
+            .add_binding(KeyModifiers::NONE, KeyCode::Enter, ReedlineEvent::Enter);
+        config.emacs.add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('a'),
+            ReedlineEvent::Edit(vec![EditCommand::MoveToLineStart { select: false }]),
+        );
+        config
+            .emacs
+            .add_binding(KeyModifiers::NONE, KeyCode::F(1), ReedlineEvent::Esc);
+        config.emacs.add_sequence_binding(
+            vec![
+                KeyCombination::new(KeyModifiers::CONTROL, KeyCode::Char('x')),
+                KeyCombination::new(KeyModifiers::NONE, KeyCode::Char('u')),
+            ],
+            ReedlineEvent::Edit(vec![EditCommand::Undo]),
+        );
+
+        let toml = config.to_toml_string().unwrap();
+        let loaded = KeybindingsConfig::from_toml_str(&toml).unwrap();
+        assert_eq!(
+            loaded.emacs.get_sequence_bindings(),
+            config.emacs.get_sequence_bindings()
+        );
+        assert_eq!(
+            toml,
+            r#"[[keybindings]]
+mode = "emacs"
+modifier = "none"
+keycode = "f1"
+event = "Esc"
+
+[[keybindings]]
+mode = "emacs"
+modifier = "control"
+keycode = "char_a"
+event = { Edit = [{ MoveToLineStart = { select = false } }] }
+
+[[keybindings]]
+mode = "emacs"
+modifier = "control"
+keycode = "char_x"
+followed_by = [{ keycode = "char_u", modifier = "none" }]
+event = { Edit = ["Undo"] }
+
+[[keybindings]]
+mode = "vi_normal"
+modifier = "none"
+keycode = "enter"
+event = "Enter"
+"#
+        );
+    }
+}
@@ -1,21 +1,130 @@
 use {
-    crate::{enums::ReedlineEvent, EditCommand},
+    crate::{
+        enums::{HistorySearchFilter, ReedlineEvent},
+        EditCommand,
+    },
     crossterm::event::{KeyCode, KeyModifiers},
     serde::{Deserialize, Serialize},
-    std::collections::HashMap,
+    std::{
+        collections::HashMap,
+        fmt::{Display, Formatter},
+    },
 };
 
+/// A key together with the modifiers held while pressing it
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct KeyCombination {
+    /// Modifiers held down
     pub modifier: KeyModifiers,
+    /// The pressed key
     pub key_code: KeyCode,
 }
 
+impl KeyCombination {
+    /// Create a key combination
+    pub const fn new(modifier: KeyModifiers, key_code: KeyCode) -> Self {
+        Self { modifier, key_code }
+    }
+}
+
+impl Display for KeyCombination {
+    /// Emacs style description like `C-x`, `M-f`, `RET` or `<f5>`
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (modifier, prefix) in [
+            (KeyModifiers::CONTROL, "C-"),
+            (KeyModifiers::ALT, "M-"),
+            (KeyModifiers::SHIFT, "S-"),
+            (KeyModifiers::SUPER, "s-"),
+        ] {
+            if self.modifier.contains(modifier) {
+                write!(f, "{prefix}")?;
+            }
+        }
+        match self.key_code {
+            KeyCode::Char(' ') => write!(f, "SPC"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Enter => write!(f, "RET"),
+            KeyCode::Tab => write!(f, "TAB"),
+            KeyCode::Esc => write!(f, "ESC"),
+            KeyCode::Backspace => write!(f, "DEL"),
+            KeyCode::F(number) => write!(f, "<f{number}>"),
+            key_code => write!(f, "<{}>", format!("{key_code:?}").to_lowercase()),
+        }
+    }
+}
+
+/// Format a key sequence as space separated key combinations like `C-x C-e`
+pub fn key_sequence_to_string(sequence: &[KeyCombination]) -> String {
+    sequence
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Result of looking up a key sequence with [`Keybindings::find_sequence_binding`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeySequenceMatch {
+    /// No binding starts with the sequence
+    NoMatch,
+    /// Longer sequences start with the sequence, contains the event bound to the sequence itself
+    Prefix(Option<ReedlineEvent>),
+    /// The sequence is bound to the event and no longer sequence starts with it
+    Complete(ReedlineEvent),
+}
+
+/// Node of the trie of key sequence bindings
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+struct KeySequenceNode {
+    event: Option<ReedlineEvent>,
+    next: HashMap<KeyCombination, KeySequenceNode>,
+}
+
+impl KeySequenceNode {
+    fn find(&self, sequence: &[KeyCombination]) -> Option<&KeySequenceNode> {
+        sequence
+            .iter()
+            .try_fold(self, |node, combination| node.next.get(combination))
+    }
+
+    fn remove(&mut self, sequence: &[KeyCombination]) -> Option<ReedlineEvent> {
+        let (first, rest) = sequence.split_first()?;
+        let child = self.next.get_mut(first)?;
+        let removed = if rest.is_empty() {
+            child.event.take()
+        } else {
+            child.remove(rest)
+        };
+        if child.event.is_none() && child.next.is_empty() {
+            self.next.remove(first);
+        }
+        removed
+    }
+
+    fn collect(
+        &self,
+        prefix: &mut Vec<KeyCombination>,
+        sequences: &mut Vec<(Vec<KeyCombination>, ReedlineEvent)>,
+    ) {
+        if let Some(event) = &self.event {
+            sequences.push((prefix.clone(), event.clone()));
+        }
+        for (combination, node) in &self.next {
+            prefix.push(combination.clone());
+            node.collect(prefix, sequences);
+            prefix.pop();
+        }
+    }
+}
+
 /// Main definition of editor keybindings
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Keybindings {
     /// Defines a keybinding for a reedline event
     pub bindings: HashMap<KeyCombination, ReedlineEvent>,
+    /// Bindings of sequences of multiple keys
+    #[serde(default)]
+    sequences: KeySequenceNode,
 }
 
 impl Default for Keybindings {
@@ -29,6 +138,7 @@ impl Keybindings {
     pub fn new() -> Self {
         Self {
             bindings: HashMap::new(),
+            sequences: KeySequenceNode::default(),
         }
     }
 
@@ -81,6 +191,77 @@ impl Keybindings {
     pub fn get_keybindings(&self) -> &HashMap<KeyCombination, ReedlineEvent> {
         &self.bindings
     }
+
+    /// Adds a binding for a sequence of keys, like `C-x C-e`
+    ///
+    /// A sequence of a single key is added like [`Keybindings::add_binding`]. Longer sequences
+    /// may start with keys that have a binding of their own, the edit mode then waits for the
+    /// next key to decide which binding applies.
+    ///
+    /// # Panics
+    ///
+    /// If `sequence` is empty or `command` is an empty [`ReedlineEvent::UntilFound`]
+    pub fn add_sequence_binding(&mut self, sequence: Vec<KeyCombination>, command: ReedlineEvent) {
+        assert!(
+            !sequence.is_empty(),
+            "A key sequence needs at least one key"
+        );
+        if let [KeyCombination { modifier, key_code }] = sequence[..] {
+            self.add_binding(modifier, key_code, command);
+            return;
+        }
+        if let ReedlineEvent::UntilFound(subcommands) = &command {
+            assert!(
+                !subcommands.is_empty(),
+                "UntilFound should contain a series of potential events to handle"
+            );
+        }
+
+        let node = sequence
+            .into_iter()
+            .fold(&mut self.sequences, |node, combination| {
+                node.next.entry(combination).or_default()
+            });
+        node.event = Some(command);
+    }
+
+    /// Remove the binding of a sequence of keys
+    ///
+    /// Returns `Some(ReedlineEvent)` if the sequence was previously bound to a particular [`ReedlineEvent`]
+    pub fn remove_sequence_binding(
+        &mut self,
+        sequence: &[KeyCombination],
+    ) -> Option<ReedlineEvent> {
+        match sequence {
+            [] => None,
+            [KeyCombination { modifier, key_code }] => self.remove_binding(*modifier, *key_code),
+            _ => self.sequences.remove(sequence),
+        }
+    }
+
+    /// Find the binding of a sequence of keys
+    ///
+    /// Edit modes supporting key sequences call this with the keys typed so far to decide
+    /// whether to wait for more keys.
+    pub fn find_sequence_binding(&self, sequence: &[KeyCombination]) -> KeySequenceMatch {
+        let node = self.sequences.find(sequence);
+        let event = match sequence {
+            [KeyCombination { modifier, key_code }] => self.find_binding(*modifier, *key_code),
+            _ => node.and_then(|node| node.event.clone()),
+        };
+        match (node, event) {
+            (Some(node), event) if !node.next.is_empty() => KeySequenceMatch::Prefix(event),
+            (_, Some(event)) => KeySequenceMatch::Complete(event),
+            (_, None) => KeySequenceMatch::NoMatch,
+        }
+    }
+
+    /// Get the bindings of sequences with more than one key
+    pub fn get_sequence_bindings(&self) -> Vec<(Vec<KeyCombination>, ReedlineEvent)> {
+        let mut sequences = vec![];
+        self.sequences.collect(&mut vec![], &mut sequences);
+        sequences
+    }
 }
 
 pub fn edit_bind(command: EditCommand) -> ReedlineEvent {
@@ -103,6 +284,38 @@ pub fn add_common_control_bindings(kb: &mut Keybindings) {
     kb.add_binding(KM::CONTROL, KC::Char('r'), ReedlineEvent::SearchHistory);
     kb.add_binding(KM::CONTROL, KC::Char('o'), ReedlineEvent::OpenEditor);
 }
+
+/// Returns the default keybindings of the interactive history search
+///
+/// They take precedence over the bindings of the edit mode while the search is active:
+/// + `Alt-S`, `Alt-W`, `Alt-E`, `Alt-H`, `Alt-P` to toggle the session, cwd, successful, host
+///   and bookmarked filters, `Alt-R` to toggle the regex search
+/// + `Shift-Delete` to delete the selected entry
+/// + `Alt-K` to bookmark the selected entry or remove its bookmark
+pub fn default_history_search_keybindings() -> Keybindings {
+    use HistorySearchFilter as HSF;
+    use KeyCode as KC;
+    use KeyModifiers as KM;
+
+    let mut kb = Keybindings::new();
+    for (c, filter) in [
+        ('s', HSF::Session),
+        ('w', HSF::Cwd),
+        ('e', HSF::Successful),
+        ('h', HSF::Host),
+        ('p', HSF::Bookmarked),
+        ('r', HSF::Regex),
+    ] {
+        kb.add_binding(
+            KM::ALT,
+            KC::Char(c),
+            ReedlineEvent::ToggleHistorySearchFilter(filter),
+        );
+    }
+    kb.add_binding(KM::SHIFT, KC::Delete, ReedlineEvent::DeleteHistoryItem);
+    kb.add_binding(KM::ALT, KC::Char('k'), ReedlineEvent::ToggleHistoryBookmark);
+    kb
+}
 /// Add the arrow navigation and its `Ctrl` variants
 pub fn add_common_navigation_bindings(kb: &mut Keybindings) {
     use EditCommand as EC;
@@ -288,3 +501,83 @@ pub fn add_common_selection_bindings(kb: &mut Keybindings) {
         edit_bind(EC::SelectAll),
     );
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const CTRL_X: KeyCombination = KeyCombination::new(KeyModifiers::CONTROL, KeyCode::Char('x'));
+    const CTRL_E: KeyCombination = KeyCombination::new(KeyModifiers::CONTROL, KeyCode::Char('e'));
+    const U: KeyCombination = KeyCombination::new(KeyModifiers::NONE, KeyCode::Char('u'));
+
+    #[test]
+    fn sequences_are_found_by_prefix() {
+        let mut keybindings = Keybindings::new();
+        keybindings.add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('x'),
+            ReedlineEvent::Esc,
+        );
+        keybindings.add_sequence_binding(vec![CTRL_X, CTRL_E], ReedlineEvent::OpenEditor);
+        keybindings.add_sequence_binding(vec![CTRL_X, U], edit_bind(EditCommand::Undo));
+
+        assert_eq!(
+            keybindings.find_sequence_binding(&[CTRL_X]),
+            KeySequenceMatch::Prefix(Some(ReedlineEvent::Esc))
+        );
+        assert_eq!(
+            keybindings.find_sequence_binding(&[CTRL_X, CTRL_E]),
+            KeySequenceMatch::Complete(ReedlineEvent::OpenEditor)
+        );
+        assert_eq!(
+            keybindings.find_sequence_binding(&[CTRL_X, CTRL_X]),
+            KeySequenceMatch::NoMatch
+        );
+        assert_eq!(keybindings.get_sequence_bindings().len(), 2);
+
+        assert_eq!(
+            keybindings.remove_sequence_binding(&[CTRL_X, CTRL_E]),
+            Some(ReedlineEvent::OpenEditor)
+        );
+        assert_eq!(
+            keybindings.remove_sequence_binding(&[CTRL_X, U]),
+            Some(edit_bind(EditCommand::Undo))
+        );
+        assert_eq!(
+            keybindings.find_sequence_binding(&[CTRL_X]),
+            KeySequenceMatch::Complete(ReedlineEvent::Esc)
+        );
+        assert!(keybindings.get_sequence_bindings().is_empty());
+    }
+
+    #[test]
+    fn history_search_keys_are_only_bound_in_the_history_search() {
+        let search = default_history_search_keybindings();
+        let mut edit_modes = Keybindings::new();
+        add_common_control_bindings(&mut edit_modes);
+        for key in search.get_keybindings().keys() {
+            assert_eq!(edit_modes.find_binding(key.modifier, key.key_code), None);
+        }
+        assert_eq!(
+            search.find_binding(KeyModifiers::ALT, KeyCode::Char('w')),
+            Some(ReedlineEvent::ToggleHistorySearchFilter(
+                HistorySearchFilter::Cwd
+            ))
+        );
+        assert_eq!(
+            search.find_binding(KeyModifiers::SHIFT, KeyCode::Delete),
+            Some(ReedlineEvent::DeleteHistoryItem)
+        );
+    }
+
+    #[test]
+    fn key_sequences_are_displayed_emacs_style() {
+        let meta_ret = KeyCombination::new(KeyModifiers::ALT, KeyCode::Enter);
+        let f5 = KeyCombination::new(KeyModifiers::NONE, KeyCode::F(5));
+        assert_eq!(
+            key_sequence_to_string(&[CTRL_X, U, meta_ret, f5]),
+            "C-x u M-RET <f5>"
+        );
+    }
+}
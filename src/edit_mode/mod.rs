@@ -1,11 +1,18 @@
 mod base;
 mod cursors;
 mod emacs;
+#[cfg(feature = "keybindings_config")]
+mod keybinding_config;
 mod keybindings;
 mod vi;
 
 pub use base::EditMode;
 pub use cursors::CursorConfig;
 pub use emacs::{default_emacs_keybindings, Emacs};
-pub use keybindings::Keybindings;
+#[cfg(feature = "keybindings_config")]
+pub use keybinding_config::{KeybindingMode, KeybindingsConfig};
+pub(crate) use keybindings::key_sequence_to_string;
+pub use keybindings::{
+    default_history_search_keybindings, KeyCombination, KeySequenceMatch, Keybindings,
+};
 pub use vi::{default_vi_insert_keybindings, default_vi_normal_keybindings, Vi};
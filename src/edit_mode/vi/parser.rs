@@ -40,6 +40,7 @@ impl<T> ParseResult<T> {
 #[derive(Debug, PartialEq, Eq)]
 pub struct ParsedViSequence {
     multiplier: Option<usize>,
+    register: Option<char>,
     command: Option<Command>,
     count: Option<usize>,
     motion: ParseResult<Motion>,
@@ -129,10 +130,23 @@ impl ParsedViSequence {
         }
     }
 
+    /// Prefix the edits of a command with the selection of the register given as `"x`
+    fn with_register(&self, options: Vec<ReedlineOption>) -> Vec<ReedlineOption> {
+        match self.register {
+            Some(register) => {
+                std::iter::once(ReedlineOption::Edit(EditCommand::SelectRegister(register)))
+                    .chain(options)
+                    .collect()
+            }
+            None => options,
+        }
+    }
+
     pub fn to_reedline_event(&self, vi_state: &mut Vi) -> ReedlineEvent {
         match (&self.multiplier, &self.command, &self.count, &self.motion) {
             (_, Some(command), None, ParseResult::Incomplete) => {
-                let events = self.apply_multiplier(Some(command.to_reedline(vi_state)));
+                let events =
+                    self.apply_multiplier(Some(self.with_register(command.to_reedline(vi_state))));
                 match &events {
                     ReedlineEvent::None => {}
                     event => vi_state.previous = Some(event.clone()),
@@ -141,8 +155,11 @@ impl ParsedViSequence {
             }
             // This case handles all combinations of commands and motions that could exist
             (_, Some(command), _, ParseResult::Valid(motion)) => {
-                let events =
-                    self.apply_multiplier(command.to_reedline_with_motion(motion, vi_state));
+                let events = self.apply_multiplier(
+                    command
+                        .to_reedline_with_motion(motion, vi_state)
+                        .map(|options| self.with_register(options)),
+                );
                 match &events {
                     ReedlineEvent::None => {}
                     event => vi_state.previous = Some(event.clone()),
@@ -181,17 +198,64 @@ where
     }
 }
 
+/// Parse a register selection like `"a`
+///
+/// Valid registers are the named `a`-`z` (`A`-`Z` to append), the numbered `0`-`9`, the
+/// unnamed `"`, the black hole `_` and the system clipboard `+`/`*`.
+fn parse_register<'iter, I>(input: &mut Peekable<I>) -> ParseResult<Option<char>>
+where
+    I: Iterator<Item = &'iter char>,
+{
+    if input.peek() != Some(&&'"') {
+        return ParseResult::Valid(None);
+    }
+    let _ = input.next();
+    match input.next() {
+        Some(&c) if c.is_ascii_alphanumeric() || matches!(c, '"' | '_' | '+' | '*') => {
+            ParseResult::Valid(Some(c))
+        }
+        Some(_) => ParseResult::Invalid,
+        None => ParseResult::Incomplete,
+    }
+}
+
 pub fn parse<'iter, I>(input: &mut Peekable<I>) -> ParsedViSequence
 where
     I: Iterator<Item = &'iter char>,
 {
-    let multiplier = parse_number(input);
+    let mut multiplier = parse_number(input);
+    let register = match parse_register(input) {
+        ParseResult::Valid(register) => register,
+        ParseResult::Incomplete => {
+            return ParsedViSequence {
+                multiplier,
+                register: None,
+                command: Some(Command::Incomplete),
+                count: None,
+                motion: ParseResult::Incomplete,
+            }
+        }
+        ParseResult::Invalid => {
+            return ParsedViSequence {
+                multiplier,
+                register: None,
+                command: None,
+                count: None,
+                motion: ParseResult::Invalid,
+            }
+        }
+    };
+    // Vim accepts the count before or after the register, e.g. `2"ap` and `"a2p`
+    if register.is_some() && multiplier.is_none() {
+        multiplier = parse_number(input);
+    }
     let command = parse_command(input);
     let count = parse_number(input);
     let motion = parse_motion(input, command.as_ref().and_then(Command::whole_line_char));
 
     ParsedViSequence {
         multiplier,
+        register,
         command,
         count,
         motion,
@@ -217,6 +281,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: None,
+                register: None,
                 command: Some(Command::Delete),
                 count: None,
                 motion: ParseResult::Incomplete,
@@ -236,6 +301,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: None,
+                register: None,
                 command: Some(Command::Delete),
                 count: None,
                 motion: ParseResult::Valid(Motion::NextWord),
@@ -255,6 +321,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: Some(2),
+                register: None,
                 command: Some(Command::Delete),
                 count: None,
                 motion: ParseResult::Incomplete,
@@ -276,6 +343,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: Some(2),
+                register: None,
                 command: Some(Command::Delete),
                 count: None,
                 motion: ParseResult::Valid(Motion::NextWord),
@@ -295,6 +363,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: Some(2),
+                register: None,
                 command: Some(Command::Delete),
                 count: Some(2),
                 motion: ParseResult::Valid(Motion::NextWord),
@@ -314,6 +383,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: Some(2),
+                register: None,
                 command: Some(Command::Delete),
                 count: Some(20),
                 motion: ParseResult::Valid(Motion::NextWord),
@@ -333,6 +403,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: Some(2),
+                register: None,
                 command: Some(Command::Delete),
                 count: None,
                 motion: ParseResult::Valid(Motion::Line),
@@ -352,6 +423,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: None,
+                register: None,
                 command: Some(Command::Delete),
                 count: None,
                 motion: ParseResult::Valid(Motion::RightBefore('d')),
@@ -371,6 +443,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: Some(2),
+                register: None,
                 command: Some(Command::Delete),
                 count: None,
                 motion: ParseResult::Invalid,
@@ -388,6 +461,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: None,
+                register: None,
                 command: Some(Command::Incomplete),
                 count: None,
                 motion: ParseResult::Incomplete,
@@ -399,6 +473,33 @@ mod tests {
         assert_eq!(output.is_complete(ViMode::Visual), false);
     }
 
+    #[test]
+    fn test_register_before_and_after_count() {
+        let expected = ParsedViSequence {
+            multiplier: Some(2),
+            register: Some('a'),
+            command: Some(Command::Delete),
+            count: None,
+            motion: ParseResult::Valid(Motion::Line),
+        };
+
+        assert_eq!(vi_parse(&['"', 'a', '2', 'd', 'd']), expected);
+        assert_eq!(vi_parse(&['2', '"', 'a', 'd', 'd']), expected);
+    }
+
+    #[test]
+    fn test_partial_register() {
+        let output = vi_parse(&['"']);
+        assert_eq!(output.is_valid(), true);
+        assert_eq!(output.is_complete(ViMode::Normal), false);
+
+        let output = vi_parse(&['"', 'a']);
+        assert_eq!(output.is_valid(), true);
+        assert_eq!(output.is_complete(ViMode::Normal), false);
+
+        assert_eq!(vi_parse(&['"', '%']).is_valid(), false);
+    }
+
     #[test]
     fn test_partial_motion() {
         let input = ['f'];
@@ -408,6 +509,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: None,
+                register: None,
                 command: None,
                 count: None,
                 motion: ParseResult::Incomplete,
@@ -427,6 +529,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: None,
+                register: None,
                 command: Some(Command::ReplaceChar('k')),
                 count: None,
                 motion: ParseResult::Incomplete,
@@ -447,6 +550,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: Some(2),
+                register: None,
                 command: None,
                 count: None,
                 motion: ParseResult::Valid(Motion::RightUntil('f')),
@@ -466,6 +570,7 @@ mod tests {
             output,
             ParsedViSequence {
                 multiplier: Some(2),
+                register: None,
                 command: None,
                 count: None,
                 motion: ParseResult::Valid(Motion::Up),
@@ -566,6 +671,20 @@ mod tests {
         ReedlineEvent::Repaint,
     ]))]
     #[case(&['y', '^'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyFromLineNonBlankStart])]))]
+    #[case(&['"', 'a', 'y', 'w'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('a')]),
+        ReedlineEvent::Edit(vec![EditCommand::CopyWordRightToNext])]))]
+    #[case(&['"', '_', 'd', 'd'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('_')]),
+        ReedlineEvent::Edit(vec![EditCommand::CutCurrentLine])]))]
+    #[case(&['"', '+', 'P'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('+')]),
+        ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferBefore])]))]
+    #[case(&['2', '"', 'B', 'p'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('B')]),
+        ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferAfter]),
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('B')]),
+        ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferAfter])]))]
     #[case(&['y', 'g', 'g'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyFromStartLinewise])]))]
     #[case(&['y', 'G'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyToEndLinewise])]))]
     fn test_reedline_move(#[case] input: &[char], #[case] expected: ReedlineEvent) {
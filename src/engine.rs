@@ -6,7 +6,7 @@ use nu_ansi_term::{Color, Style};
 use crate::{enums::ReedlineRawEvent, CursorConfig};
 #[cfg(feature = "bashisms")]
 use crate::{
-    history::SearchFilter,
+    history::SearchRanking,
     menu_functions::{parse_selection_char, ParseAction},
 };
 #[cfg(feature = "external_printer")]
@@ -18,36 +18,45 @@ use {
 use {
     crate::{
         completion::{Completer, DefaultCompleter},
-        core_editor::Editor,
-        edit_mode::{EditMode, Emacs},
+        core_editor::{Editor, KillRing, StructuredEditing},
+        edit_mode::{
+            default_history_search_keybindings, key_sequence_to_string, EditMode, Emacs,
+            Keybindings,
+        },
         enums::{EventStatus, ReedlineEvent},
         highlighter::SimpleMatchHighlighter,
         hinter::Hinter,
         history::{
-            FileBackedHistory, History, HistoryCursor, HistoryItem, HistoryItemId,
-            HistoryNavigationQuery, HistorySessionId, SearchDirection, SearchQuery,
+            CommandLineSearch, FileBackedHistory, History, HistoryCursor, HistoryDuplicates,
+            HistoryItem, HistoryItemId, HistoryNavigationQuery, HistoryPolicy, HistoryRedaction,
+            HistorySearchContext, HistorySessionId, InteractiveHistorySearch, RedactionAction,
+            SearchDirection, SearchFilter, SearchQuery,
         },
         painting::{Painter, PainterSuspendedState, PromptLines, RenderSnapshot},
-        prompt::{PromptEditMode, PromptHistorySearchStatus},
+        prompt::PromptEditMode,
         result::{ReedlineError, ReedlineErrorVariants},
+        terminal_backend::TerminalBackend,
         terminal_extensions::{
             bracketed_paste::BracketedPasteGuard,
             kitty::KittyProtocolGuard,
             semantic_prompt::{Osc133ClickEventsMarkers, SemanticPromptMarkers},
         },
-        utils::text_manipulation,
         EditCommand, ExampleHighlighter, Highlighter, LineBuffer, Menu, MenuEvent, MouseButton,
         Prompt, PromptHistorySearch, ReedlineMenu, Signal, UndoBehavior, ValidationResult,
         Validator,
     },
     crossterm::{
         cursor::{SetCursorStyle, Show},
-        event,
         event::{Event, KeyCode, KeyEvent, KeyModifiers},
-        terminal, QueueableCommand,
+        QueueableCommand,
     },
     std::{
-        fs::File, io, io::Result, io::Write, process::Command, time::Duration, time::SystemTime,
+        fs::File,
+        io,
+        io::Result,
+        io::Write,
+        process::Command,
+        time::{Duration, Instant, SystemTime},
     },
 };
 
@@ -67,6 +76,9 @@ const EVENTS_THRESHOLD: usize = 10;
 /// idle callback).
 const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
+// Number of history search results listed below the prompt by default
+const DEFAULT_HISTORY_SEARCH_ROWS: usize = 10;
+
 /// Determines if inputs should be used to extend the regular line buffer,
 /// traverse the history in the standard prompt or edit the search string in the
 /// reverse search
@@ -85,6 +97,26 @@ enum InputMode {
     HistoryTraversal,
 }
 
+/// How the history is shared with other sessions using the same history file or database
+///
+/// Similar to zsh's `SHARE_HISTORY`. Entries of other sessions are merged with
+/// [`History::sync`], which is postponed while the history is being navigated or searched so
+/// the current position isn't disturbed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HistorySharing {
+    /// Only exchange entries on [`Reedline::sync_history`]
+    #[default]
+    Manual,
+    /// Write every submitted entry right away and pick up new entries before each prompt
+    OnPrompt,
+    /// Like [`HistorySharing::OnPrompt`], and also pick up new entries in the given interval
+    /// while waiting for input
+    ///
+    /// Event driven hosts pick them up when calling [`Reedline::handle_raw_events`], which can
+    /// also be called without any events when the host's timer fires.
+    Periodic(Duration),
+}
+
 /// Configuration for mouse click-to-cursor support.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum MouseClickMode {
@@ -132,8 +164,26 @@ pub struct Reedline {
     // none if history doesn't support this
     history_last_run_id: Option<HistoryItemId>,
     history_exclusion_prefix: Option<String>,
+    history_policy: HistoryPolicy,
+    history_redaction: Option<HistoryRedaction>,
+    history_sharing: HistorySharing,
+    history_last_shared: Instant,
+    // Excluded from the history, or waiting for its exit status if failed commands are ignored
     history_excluded_item: Option<HistoryItem>,
-    history_cursor_on_excluded: bool,
+    // Lines with a secret kept in memory for this session only, oldest first
+    history_session_items: Vec<HistoryItem>,
+    // Position in the recallable excluded lines while navigating them, newest first
+    history_excluded_cursor: Option<usize>,
+    // Entry submitted with `ReedlineEvent::OperateAndGetNext`, the one after it is recalled next
+    history_recall_next: Option<HistoryItem>,
+    // State of the interactive history search (`Ctrl-R`)
+    history_search: InteractiveHistorySearch,
+    // Maximum number of history search results listed below the prompt
+    history_search_rows: usize,
+    // Bindings taking precedence over the edit mode during the history search
+    history_search_keybindings: Keybindings,
+    // Host the session runs on, used by the host filter of the history search
+    hostname: Option<String>,
     input_mode: InputMode,
 
     // State of the painter after a `ReedlineEvent::ExecuteHostCommand` was requested, used after
@@ -216,22 +266,26 @@ struct BufferEditor {
 
 impl Drop for Reedline {
     fn drop(&mut self) {
+        let _ignore = self.save_pending_history_item();
+        let terminal = self.painter.backend_mut();
         if self.cursor_shapes.is_some() {
-            let _ignore = terminal::enable_raw_mode();
-            let mut stdout = std::io::stdout();
-            let _ignore = stdout.queue(SetCursorStyle::DefaultUserShape);
-            let _ignore = stdout.queue(Show);
-            let _ignore = stdout.flush();
+            let _ignore = terminal.enable_raw_mode();
+            let _ignore = terminal.queue(SetCursorStyle::DefaultUserShape);
+            let _ignore = terminal.queue(Show);
+            let _ignore = terminal.flush();
         }
 
         // Ensures that the terminal is in a good state if we panic semigracefully
         // Calling `disable_raw_mode()` twice is fine with Linux
-        let _ignore = terminal::disable_raw_mode();
+        let _ignore = terminal.disable_raw_mode();
     }
 }
 
 impl Reedline {
     const FILTERED_ITEM_ID: HistoryItemId = HistoryItemId(i64::MAX);
+    const PENDING_ITEM_ID: HistoryItemId = HistoryItemId(i64::MAX - 1);
+    const DROPPED_ITEM_ID: HistoryItemId = HistoryItemId(i64::MAX - 2);
+    const SESSION_ITEM_ID: HistoryItemId = HistoryItemId(i64::MAX - 3);
 
     /// Create a new [`Reedline`] engine with a local [`History`] that is not synchronized to a file.
     #[must_use]
@@ -256,8 +310,18 @@ impl Reedline {
             history_session_id: hist_session_id,
             history_last_run_id: None,
             history_exclusion_prefix: None,
+            history_policy: HistoryPolicy::default(),
+            history_redaction: None,
+            history_sharing: HistorySharing::Manual,
+            history_last_shared: Instant::now(),
             history_excluded_item: None,
-            history_cursor_on_excluded: false,
+            history_session_items: Vec::new(),
+            history_excluded_cursor: None,
+            history_recall_next: None,
+            history_search: InteractiveHistorySearch::default(),
+            history_search_rows: DEFAULT_HISTORY_SEARCH_ROWS,
+            history_search_keybindings: default_history_search_keybindings(),
+            hostname: None,
             input_mode: InputMode::Regular,
             suspended_state: None,
             last_render_snapshot: None,
@@ -432,6 +496,17 @@ impl Reedline {
         self
     }
 
+    /// A builder that replaces the terminal reedline reads input from and paints to.
+    ///
+    /// By default the real terminal is used through [`CrosstermBackend`](crate::CrosstermBackend). Use a
+    /// [`HeadlessBackend`](crate::HeadlessBackend) to drive the line editor with scripted
+    /// input and inspect the rendered screen, e.g. in tests.
+    #[must_use]
+    pub fn with_terminal_backend(mut self, backend: Box<dyn TerminalBackend>) -> Self {
+        self.painter.set_backend(backend);
+        self
+    }
+
     /// Update current working directory.
     #[must_use]
     pub fn with_cwd(mut self, cwd: Option<String>) -> Self {
@@ -439,6 +514,52 @@ impl Reedline {
         self
     }
 
+    /// Set the hostname of the machine reedline runs on.
+    ///
+    /// The history search uses it to only show entries recorded on this host.
+    #[must_use]
+    pub fn with_hostname(mut self, hostname: Option<String>) -> Self {
+        self.hostname = hostname;
+        self
+    }
+
+    /// A builder that sets the maximum number of results listed by the history search
+    ///
+    /// The list is further limited by the height of the terminal.
+    #[must_use]
+    pub fn with_history_search_rows(mut self, rows: usize) -> Self {
+        self.history_search_rows = rows;
+        self
+    }
+
+    /// A builder that sets the keybindings of the history search
+    ///
+    /// They take precedence over the bindings of the edit mode while the search is active.
+    /// Defaults to [`default_history_search_keybindings`].
+    #[must_use]
+    pub fn with_history_search_keybindings(mut self, keybindings: Keybindings) -> Self {
+        self.history_search_keybindings = keybindings;
+        self
+    }
+
+    /// A builder that enables auto-indentation and bracket auto-pairing
+    ///
+    /// See [`StructuredEditing`] for the details and configuration.
+    #[must_use]
+    pub fn with_structured_editing(mut self, structured_editing: StructuredEditing) -> Self {
+        self.editor.set_structured_editing(Some(structured_editing));
+        self
+    }
+
+    /// A builder that sets how many killed texts are kept for yanking
+    ///
+    /// Defaults to [`DEFAULT_KILL_RING_SIZE`](crate::DEFAULT_KILL_RING_SIZE).
+    #[must_use]
+    pub fn with_kill_ring_size(mut self, size: usize) -> Self {
+        self.editor.kill_ring_mut().set_capacity(size);
+        self
+    }
+
     /// A builder that configures the highlighter for your instance of the Reedline engine
     /// # Example
     /// ```rust
@@ -509,6 +630,69 @@ impl Reedline {
         self
     }
 
+    /// A builder which configures which submitted lines are saved to the history
+    ///
+    /// Covers bash/zsh style deduplication, ignore patterns and dropping failed commands,
+    /// see [`HistoryPolicy`]. Applied in addition to
+    /// [`Reedline::with_history_exclusion_prefix`].
+    /// # Example
+    /// ```rust
+    /// use reedline::{HistoryDuplicates, HistoryIgnorePattern, HistoryPolicy, Reedline};
+    ///
+    /// let mut line_editor = Reedline::create().with_history_policy(
+    ///     HistoryPolicy::default()
+    ///         .with_duplicates(HistoryDuplicates::IgnoreConsecutive)
+    ///         .with_ignore_pattern(HistoryIgnorePattern::glob("exit")),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_history_policy(mut self, policy: HistoryPolicy) -> Self {
+        self.history_policy = policy;
+        self
+    }
+
+    /// A builder which configures sharing the history with concurrently running sessions
+    ///
+    /// Useful with a history file or database used by several shells at once, see
+    /// [`HistorySharing`]. [`Reedline::toggle_history_session_matching`] still restricts the
+    /// navigation to the entries of the own session, if the [`History`] supports sessions.
+    /// # Example
+    /// ```rust,no_run
+    /// use reedline::{FileBackedHistory, HistorySharing, Reedline};
+    /// use std::time::Duration;
+    ///
+    /// let history = Box::new(
+    ///     FileBackedHistory::with_file(1000, "history.txt".into())
+    ///         .expect("Error configuring history with file"),
+    /// );
+    /// let mut line_editor = Reedline::create()
+    ///     .with_history(history)
+    ///     .with_history_sharing(HistorySharing::Periodic(Duration::from_secs(2)));
+    /// ```
+    #[must_use]
+    pub fn with_history_sharing(mut self, sharing: HistorySharing) -> Self {
+        self.history_sharing = sharing;
+        self
+    }
+
+    /// A builder which configures the redaction of secrets before lines are saved to the history
+    ///
+    /// Lines containing a secret found by the [`HistoryRedaction`] are masked, dropped or only
+    /// kept in memory, depending on its [`RedactionAction`].
+    /// # Example
+    /// ```rust
+    /// use reedline::{HistoryRedaction, RedactionAction, Reedline};
+    ///
+    /// let mut line_editor = Reedline::create().with_history_redaction(Some(
+    ///     HistoryRedaction::default().with_action(RedactionAction::SessionOnly),
+    /// ));
+    /// ```
+    #[must_use]
+    pub fn with_history_redaction(mut self, redaction: Option<HistoryRedaction>) -> Self {
+        self.history_redaction = redaction;
+        self
+    }
+
     /// A builder that configures the validator for your instance of the Reedline engine
     /// # Example
     /// ```rust
@@ -690,9 +874,21 @@ impl Reedline {
         &mut *self.history
     }
 
+    /// Read-only view of the kill ring used by the cut and paste commands
+    pub fn kill_ring(&self) -> &KillRing {
+        self.editor.kill_ring()
+    }
+
+    /// Mutable view of the kill ring, e.g. to restore entries persisted by the host
+    pub fn kill_ring_mut(&mut self) -> &mut KillRing {
+        self.editor.kill_ring_mut()
+    }
+
     /// Update the underlying [`History`] to/from disk
     pub fn sync_history(&mut self) -> std::io::Result<()> {
         // TODO: check for interactions in the non-submitting events
+        self.save_pending_history_item()
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
         self.history.sync()
     }
 
@@ -710,10 +906,30 @@ impl Reedline {
         f: &dyn Fn(HistoryItem) -> HistoryItem,
     ) -> crate::Result<()> {
         match &self.history_last_run_id {
-            Some(Self::FILTERED_ITEM_ID) => {
+            Some(Self::FILTERED_ITEM_ID | Self::DROPPED_ITEM_ID) => {
                 self.history_excluded_item = Some(f(self.history_excluded_item.take().unwrap()));
                 Ok(())
             }
+            Some(Self::SESSION_ITEM_ID) => {
+                let entry = self.history_session_items.pop().unwrap();
+                self.history_session_items.push(f(entry));
+                Ok(())
+            }
+            Some(Self::PENDING_ITEM_ID) => {
+                let entry = f(self.history_excluded_item.take().unwrap());
+                match entry.exit_status {
+                    Some(0) => self.save_history_item(entry),
+                    Some(_) => {
+                        self.history_last_run_id = Some(Self::FILTERED_ITEM_ID);
+                        self.history_excluded_item = Some(entry);
+                        Ok(())
+                    }
+                    None => {
+                        self.history_excluded_item = Some(entry);
+                        Ok(())
+                    }
+                }
+            }
             Some(r) => self.history.update(*r, f),
             None => Err(ReedlineError(ReedlineErrorVariants::OtherHistoryError(
                 "No command run",
@@ -726,7 +942,7 @@ impl Reedline {
     /// Returns a [`std::io::Result`] in which the `Err` type is [`std::io::Result`]
     /// and the `Ok` variant wraps a [`Signal`] which handles user inputs.
     pub fn read_line(&mut self, prompt: &dyn Prompt) -> Result<Signal> {
-        terminal::enable_raw_mode()?;
+        self.painter.backend_mut().enable_raw_mode()?;
         self.bracketed_paste.enter();
         self.kitty_protocol.enter();
 
@@ -734,10 +950,127 @@ impl Reedline {
 
         self.bracketed_paste.exit();
         self.kitty_protocol.exit();
-        terminal::disable_raw_mode()?;
+        self.painter.backend_mut().disable_raw_mode()?;
+        result
+    }
+
+    /// Start an event driven line editing session without blocking the calling thread.
+    ///
+    /// This is the non-blocking counterpart to [`Reedline::read_line`] for hosts that run
+    /// their own event loop (e.g. an async runtime multiplexing input with timers, sockets or
+    /// job notifications). Reedline will not read from the terminal itself, instead the host
+    /// reads the terminal events (e.g. with crossterm's `EventStream`) and passes them to
+    /// [`Reedline::handle_raw_events`] until a [`Signal`] is returned.
+    ///
+    /// Enters raw mode and paints the prompt.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use reedline::{DefaultPrompt, Reedline, ReedlineRawEvent, Signal};
+    /// use std::time::Duration;
+    ///
+    /// let mut line_editor = Reedline::create();
+    /// let prompt = DefaultPrompt::default();
+    ///
+    /// line_editor.begin_read_line(&prompt).unwrap();
+    /// let signal = loop {
+    ///     if !crossterm::event::poll(Duration::from_millis(250)).unwrap() {
+    ///         // Do other work in between
+    ///         continue;
+    ///     }
+    ///     let event = crossterm::event::read().unwrap();
+    ///     if let Ok(event) = ReedlineRawEvent::try_from(event) {
+    ///         if let Some(signal) = line_editor.handle_raw_events(&prompt, [event]).unwrap() {
+    ///             break signal;
+    ///         }
+    ///     }
+    /// };
+    /// ```
+    pub fn begin_read_line(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        self.painter.backend_mut().enable_raw_mode()?;
+        self.bracketed_paste.enter();
+        self.kitty_protocol.enter();
+
+        if let Err(err) = self.prepare_prompt(prompt) {
+            self.end_read_line()?;
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Feed terminal events into a session started with [`Reedline::begin_read_line`].
+    ///
+    /// Events that arrive together (e.g. from a paste) should be passed in one batch, so
+    /// consecutive edits are fused and the buffer is only repainted once.
+    ///
+    /// Returns `Ok(None)` while the line is still being edited. Once a [`Signal`] is
+    /// returned, the session is finished and the terminal is restored as if
+    /// [`Reedline::read_line`] had returned.
+    pub fn handle_raw_events(
+        &mut self,
+        prompt: &dyn Prompt,
+        events: impl IntoIterator<Item = ReedlineRawEvent>,
+    ) -> Result<Option<Signal>> {
+        let result = self
+            .share_history_if_due()
+            .and_then(|()| self.process_raw_events(prompt, events));
+        if !matches!(result, Ok(None)) {
+            self.end_read_line()?;
+        }
+        result
+    }
+
+    /// Restore the terminal after a session started with [`Reedline::begin_read_line`].
+    ///
+    /// This happens automatically when [`Reedline::handle_raw_events`] returns a [`Signal`],
+    /// only call it to abandon a session still in progress.
+    pub fn end_read_line(&mut self) -> Result<()> {
+        self.bracketed_paste.exit();
+        self.kitty_protocol.exit();
+        self.painter.backend_mut().disable_raw_mode()
+    }
+
+    /// Time left to type the next key of an incomplete key sequence binding, like `C-x C-e`.
+    ///
+    /// Event driven hosts should call [`Reedline::handle_key_sequence_timeout`] if no
+    /// event arrives within this time. `None` if no key sequence is pending or it waits
+    /// for the next key indefinitely.
+    pub fn key_sequence_timeout(&self) -> Option<Duration> {
+        self.edit_mode.pending_key_sequence_timeout()
+    }
+
+    /// Abandon an incomplete key sequence after [`Reedline::key_sequence_timeout`] elapsed.
+    ///
+    /// Runs the binding of the keys typed so far, if any. Like
+    /// [`Reedline::handle_raw_events`] it returns a [`Signal`] once the session is finished.
+    pub fn handle_key_sequence_timeout(&mut self, prompt: &dyn Prompt) -> Result<Option<Signal>> {
+        let result = self.flush_key_sequence(prompt);
+        if !matches!(result, Ok(None)) {
+            self.end_read_line()?;
+        }
         result
     }
 
+    fn flush_key_sequence(&mut self, prompt: &dyn Prompt) -> Result<Option<Signal>> {
+        if self.edit_mode.pending_key_sequence().is_empty() {
+            return Ok(None);
+        }
+        let event = self.edit_mode.flush_pending_key_sequence();
+        self.handle_reedline_events(prompt, vec![event], true)
+    }
+
+    /// Print a message above the prompt of a running event driven session and repaint it.
+    ///
+    /// Multiple lines are separated by `\n`.
+    /// Useful to report job-control notifications and other asynchronous output without
+    /// garbling the line being edited.
+    pub fn print_external_message(&mut self, prompt: &dyn Prompt, message: &str) -> Result<()> {
+        let messages = message.lines().map(String::from).collect();
+        self.painter
+            .print_external_message(messages, self.editor.line_buffer(), prompt)?;
+        self.repaint(prompt)
+    }
+
     /// Returns the current insertion point of the input buffer.
     pub fn current_insertion_point(&self) -> usize {
         self.editor.insertion_point()
@@ -771,16 +1104,7 @@ impl Reedline {
     /// Helper implementing the logic for [`Reedline::read_line()`] to be wrapped
     /// in a `raw_mode` context.
     fn read_line_helper(&mut self, prompt: &dyn Prompt) -> Result<Signal> {
-        self.painter
-            .initialize_prompt_position(self.suspended_state.as_ref())?;
-        if self.suspended_state.is_some() {
-            // Last editor was suspended to run a ExecuteHostCommand event,
-            // we are resuming operation now.
-            self.suspended_state = None;
-        }
-        self.hide_hints = false;
-
-        self.repaint(prompt)?;
+        self.prepare_prompt(prompt)?;
 
         loop {
             // Call idle callback if set (for processing external events like GUI updates)
@@ -789,6 +1113,8 @@ impl Reedline {
                 callback();
             }
 
+            self.share_history_if_due()?;
+
             #[cfg(feature = "external_printer")]
             if let Some(ref external_printer) = self.external_printer {
                 // get messages from printer as crlf separated "lines"
@@ -829,7 +1155,7 @@ impl Reedline {
                 // using the shared poll_interval for the timeout.
                 let needs_polling = {
                     #[allow(unused_mut)]
-                    let mut result = false;
+                    let mut result = matches!(self.history_sharing, HistorySharing::Periodic(_));
                     #[cfg(feature = "external_printer")]
                     if self.external_printer.is_some() {
                         result = true;
@@ -841,19 +1167,36 @@ impl Reedline {
                     result
                 };
 
-                if needs_polling {
-                    if event::poll(self.poll_interval)? {
-                        events.push(crossterm::event::read()?);
+                let key_sequence_timeout = self.edit_mode.pending_key_sequence_timeout();
+                let terminal = self.painter.backend_mut();
+                if let Some(timeout) = key_sequence_timeout {
+                    let timeout = if needs_polling {
+                        timeout.min(self.poll_interval)
+                    } else {
+                        timeout
+                    };
+                    if terminal.poll_event(timeout)? {
+                        events.push(terminal.read_event()?);
+                    } else if self.edit_mode.pending_key_sequence_timeout() == Some(Duration::ZERO)
+                    {
+                        if let Some(signal) = self.flush_key_sequence(prompt)? {
+                            return Ok(signal);
+                        }
+                        continue;
+                    }
+                } else if needs_polling {
+                    if terminal.poll_event(self.poll_interval)? {
+                        events.push(terminal.read_event()?);
                     }
                 } else {
                     // Block until we receive an event
-                    events.push(crossterm::event::read()?);
+                    events.push(terminal.read_event()?);
                 }
 
                 // Receive all events in the queue without blocking. Will stop when
                 // a line of input is completed.
-                while !completed(&events) && event::poll(Duration::from_millis(0))? {
-                    events.push(crossterm::event::read()?);
+                while !completed(&events) && terminal.poll_event(Duration::from_millis(0))? {
+                    events.push(terminal.read_event()?);
                 }
 
                 // If we believe there's text pasting or resizing going on, batch
@@ -861,84 +1204,186 @@ impl Reedline {
                 if events.len() > EVENTS_THRESHOLD
                     || events.iter().any(|e| matches!(e, Event::Resize(_, _)))
                 {
-                    while !completed(&events) && event::poll(POLL_WAIT)? {
-                        events.push(crossterm::event::read()?);
+                    while !completed(&events) && terminal.poll_event(POLL_WAIT)? {
+                        events.push(terminal.read_event()?);
                     }
                 }
             }
 
-            // Convert `Event` into `ReedlineEvent`. Also, fuse consecutive
-            // `ReedlineEvent::EditCommand` into one. Also, if there're multiple
-            // `ReedlineEvent::Resize`, only keep the last one.
-            let mut reedline_events: Vec<ReedlineEvent> = vec![];
-            let mut edits = vec![];
-            let mut resize = None;
-            for event in events {
-                if let Ok(event) = ReedlineRawEvent::try_from(event) {
-                    match self.edit_mode.parse_event(event) {
-                        ReedlineEvent::Edit(edit) => edits.extend(edit),
-                        ReedlineEvent::Resize(x, y) => resize = Some((x, y)),
-                        event => {
-                            if !edits.is_empty() {
-                                reedline_events
-                                    .push(ReedlineEvent::Edit(std::mem::take(&mut edits)));
-                            }
-                            reedline_events.push(event);
-                        }
-                    }
-                }
-            }
-            if !edits.is_empty() {
-                reedline_events.push(ReedlineEvent::Edit(edits));
+            let events = events
+                .into_iter()
+                .filter_map(|event| ReedlineRawEvent::try_from(event).ok());
+            if let Some(signal) = self.process_raw_events(prompt, events)? {
+                return Ok(signal);
             }
-            if let Some((x, y)) = resize {
-                reedline_events.push(ReedlineEvent::Resize(x, y));
-            }
-            if self.immediately_accept {
-                reedline_events.push(ReedlineEvent::Submit);
+        }
+    }
+
+    /// Sets up the painter for a new line editing session and paints the prompt.
+    fn prepare_prompt(&mut self, prompt: &dyn Prompt) -> Result<()> {
+        self.painter
+            .initialize_prompt_position(self.suspended_state.as_ref())?;
+        if self.suspended_state.is_some() {
+            // Last editor was suspended to run a ExecuteHostCommand event,
+            // we are resuming operation now.
+            self.suspended_state = None;
+        }
+        self.hide_hints = false;
+        self.share_history()?;
+        if let Some(item) = self.history_recall_next.take() {
+            self.recall_history_after(&item);
+        }
+
+        self.repaint(prompt)
+    }
+
+    /// Exchange entries with other sessions according to the [`HistorySharing`]
+    fn share_history(&mut self) -> Result<()> {
+        // Merging could shift the entries under the history cursor or the search results
+        if self.history_sharing == HistorySharing::Manual || self.input_mode != InputMode::Regular {
+            return Ok(());
+        }
+        self.history_last_shared = Instant::now();
+        self.sync_history()
+    }
+
+    /// Exchange entries with other sessions if the interval of [`HistorySharing::Periodic`]
+    /// elapsed
+    fn share_history_if_due(&mut self) -> Result<()> {
+        if let HistorySharing::Periodic(interval) = self.history_sharing {
+            if self.history_last_shared.elapsed() >= interval {
+                self.share_history()?;
             }
+        }
+        Ok(())
+    }
 
-            // Handle reedline events.
-            let mut need_repaint = false;
-            for event in reedline_events {
-                match self.handle_event(prompt, event)? {
-                    EventStatus::Exits(signal) => {
-                        // Check if we are merely suspended (to process an ExecuteHostCommand event)
-                        // or if we're about to quit the editor.
-                        if self.suspended_state.is_none() {
-                            // We are about to quit the editor, move the cursor below the input
-                            // area, for external commands or new read_line call
-                            self.painter.move_cursor_to_end()?;
-                        }
-                        return Ok(signal);
-                    }
-                    EventStatus::Handled => {
-                        need_repaint = true;
+    /// Runs a batch of raw events through the edit mode and the event handlers.
+    ///
+    /// Returns `Some(signal)` once the line editing session is over.
+    fn process_raw_events(
+        &mut self,
+        prompt: &dyn Prompt,
+        events: impl IntoIterator<Item = ReedlineRawEvent>,
+    ) -> Result<Option<Signal>> {
+        // Convert `ReedlineRawEvent` into `ReedlineEvent`. Also, fuse consecutive
+        // `ReedlineEvent::EditCommand` into one. Also, if there're multiple
+        // `ReedlineEvent::Resize`, only keep the last one.
+        let had_pending_input = self.has_pending_input();
+        let mut reedline_events: Vec<ReedlineEvent> = vec![];
+        let mut edits = vec![];
+        let mut resize = None;
+        let mut events = events.into_iter().peekable();
+        while let Some(event) = events.next() {
+            match self.parse_raw_event(event) {
+                ReedlineEvent::Edit(edit) => edits.extend(edit),
+                ReedlineEvent::Resize(x, y) => resize = Some((x, y)),
+                event => {
+                    if !edits.is_empty() {
+                        reedline_events.push(ReedlineEvent::Edit(std::mem::take(&mut edits)));
                     }
-                    EventStatus::Inapplicable => {
-                        // Nothing changed, no need to repaint
+                    reedline_events.push(event);
+                    // The event may enter or leave the history search, which has its own
+                    // bindings, so it is handled before parsing the following events
+                    if events.peek().is_some() {
+                        let handled = std::mem::take(&mut reedline_events);
+                        if let Some(signal) = self.handle_reedline_events(prompt, handled, false)? {
+                            return Ok(Some(signal));
+                        }
                     }
                 }
             }
-            if need_repaint {
-                self.repaint(prompt)?;
+        }
+        if !edits.is_empty() {
+            reedline_events.push(ReedlineEvent::Edit(edits));
+        }
+        if let Some((x, y)) = resize {
+            reedline_events.push(ReedlineEvent::Resize(x, y));
+        }
+        if self.immediately_accept {
+            reedline_events.push(ReedlineEvent::Submit);
+        }
+
+        // Pending keys and numeric arguments are shown in the prompt indicator
+        let need_repaint = had_pending_input || self.has_pending_input();
+        self.handle_reedline_events(prompt, reedline_events, need_repaint)
+    }
+
+    /// Converts a raw event with the bindings of the history search, if it is active, or the
+    /// edit mode
+    fn parse_raw_event(&mut self, event: ReedlineRawEvent) -> ReedlineEvent {
+        let history_search_binding = (self.input_mode == InputMode::HistorySearch)
+            .then(|| event.key())
+            .flatten()
+            .and_then(|(modifiers, code)| {
+                self.history_search_keybindings
+                    .find_binding(modifiers, code)
+            });
+        match history_search_binding {
+            Some(event) => event,
+            None => self.edit_mode.parse_event(event),
+        }
+    }
+
+    /// Whether the edit mode waits for more keys of a key sequence or for the command
+    /// of a numeric argument
+    fn has_pending_input(&self) -> bool {
+        !self.edit_mode.pending_key_sequence().is_empty()
+            || self.edit_mode.pending_numeric_argument().is_some()
+    }
+
+    /// Handles parsed events and repaints if any of them changed the state.
+    ///
+    /// Returns `Some(signal)` once the line editing session is over.
+    fn handle_reedline_events(
+        &mut self,
+        prompt: &dyn Prompt,
+        reedline_events: Vec<ReedlineEvent>,
+        mut need_repaint: bool,
+    ) -> Result<Option<Signal>> {
+        for event in reedline_events {
+            match self.handle_event(prompt, event)? {
+                EventStatus::Exits(signal) => {
+                    // Check if we are merely suspended (to process an ExecuteHostCommand event)
+                    // or if we're about to quit the editor.
+                    if self.suspended_state.is_none() {
+                        // We are about to quit the editor, move the cursor below the input
+                        // area, for external commands or new read_line call
+                        self.painter.move_cursor_to_end()?;
+                    }
+                    return Ok(Some(signal));
+                }
+                EventStatus::Handled => {
+                    need_repaint = true;
+                }
+                EventStatus::Inapplicable => {
+                    // Nothing changed, no need to repaint
+                }
             }
         }
+        if need_repaint {
+            self.repaint(prompt)?;
+        }
+        Ok(None)
     }
 
     fn handle_event(&mut self, prompt: &dyn Prompt, event: ReedlineEvent) -> Result<EventStatus> {
         if self.input_mode == InputMode::HistorySearch {
-            self.handle_history_search_event(event)
+            self.handle_history_search_event(prompt, event)
         } else {
             self.handle_editor_event(prompt, event)
         }
     }
 
-    fn handle_history_search_event(&mut self, event: ReedlineEvent) -> io::Result<EventStatus> {
+    fn handle_history_search_event(
+        &mut self,
+        prompt: &dyn Prompt,
+        event: ReedlineEvent,
+    ) -> io::Result<EventStatus> {
         match event {
             ReedlineEvent::UntilFound(events) => {
                 for event in events {
-                    match self.handle_history_search_event(event)? {
+                    match self.handle_history_search_event(prompt, event)? {
                         EventStatus::Inapplicable => {
                             // Try again with the next event handler
                         }
@@ -976,12 +1421,7 @@ impl Reedline {
             | ReedlineEvent::HistoryHintComplete
             | ReedlineEvent::Submit
             | ReedlineEvent::SubmitOrNewline => {
-                if let Some(string) = self.history_cursor.string_at_cursor() {
-                    self.editor
-                        .set_buffer(string, UndoBehavior::CreateUndoPoint);
-                }
-
-                self.input_mode = InputMode::Regular;
+                self.accept_history_search();
                 Ok(EventStatus::Handled)
             }
             ReedlineEvent::ExecuteHostCommand(host_command) => {
@@ -1012,45 +1452,80 @@ impl Reedline {
                 // A handled Event causes a repaint
                 Ok(EventStatus::Handled)
             }
-            ReedlineEvent::PreviousHistory | ReedlineEvent::Up | ReedlineEvent::SearchHistory => {
-                self.history_cursor
-                    .back(self.history.as_ref())
-                    .expect("todo: error handling");
+            // The most recent result is listed first, right below the prompt
+            ReedlineEvent::PreviousHistory
+            | ReedlineEvent::Down
+            | ReedlineEvent::MenuDown
+            | ReedlineEvent::MenuNext
+            | ReedlineEvent::SearchHistory => {
+                self.history_search.select_older();
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::NextHistory
+            | ReedlineEvent::Up
+            | ReedlineEvent::MenuUp
+            | ReedlineEvent::MenuPrevious => {
+                self.history_search.select_newer();
                 Ok(EventStatus::Handled)
             }
-            ReedlineEvent::NextHistory | ReedlineEvent::Down => {
-                self.history_cursor
-                    .forward(self.history.as_ref())
-                    .expect("todo: error handling");
-                // Hacky way to ensure that we don't fall of into failed search going forward
-                if self.history_cursor.string_at_cursor().is_none() {
-                    self.history_cursor
-                        .back(self.history.as_ref())
-                        .expect("todo: error handling");
-                }
+            ReedlineEvent::ToggleHistorySearchFilter(filter) => {
+                self.history_search.toggle_filter(filter);
+                self.refresh_history_search();
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::DeleteHistoryItem => {
+                let context = self.history_search_context();
+                self.history_search
+                    .delete_selected(self.history.as_mut(), &context);
+                Ok(EventStatus::Handled)
+            }
+            ReedlineEvent::ToggleHistoryBookmark => {
+                let context = self.history_search_context();
+                self.history_search
+                    .toggle_bookmark_selected(self.history.as_mut(), &context);
                 Ok(EventStatus::Handled)
             }
+            ReedlineEvent::OperateAndGetNext => {
+                let recalled = self
+                    .history_search
+                    .selected_item()
+                    .filter(|item| item.id.is_some())
+                    .cloned();
+                self.accept_history_search();
+                self.operate_and_get_next(prompt, recalled)
+            }
             ReedlineEvent::Esc => {
                 self.input_mode = InputMode::Regular;
                 Ok(EventStatus::Handled)
             }
+            // Moving the cursor continues editing the selected entry in the regular prompt
+            ReedlineEvent::Right | ReedlineEvent::Left => {
+                self.accept_history_search();
+                self.handle_editor_event(prompt, event)
+            }
+            ReedlineEvent::Multiple(events) => {
+                let mut latest_signal = EventStatus::Inapplicable;
+                for event in events {
+                    match self.handle_event(prompt, event)? {
+                        EventStatus::Handled => latest_signal = EventStatus::Handled,
+                        EventStatus::Inapplicable => {}
+                        exit @ EventStatus::Exits(_) => return Ok(exit),
+                    }
+                }
+                Ok(latest_signal)
+            }
             // TODO: Check if events should be handled
-            ReedlineEvent::Right
-            | ReedlineEvent::Left
-            | ReedlineEvent::Multiple(_)
-            | ReedlineEvent::None
+            ReedlineEvent::None
             | ReedlineEvent::HistoryHintWordComplete
             | ReedlineEvent::OpenEditor
             | ReedlineEvent::Menu(_)
-            | ReedlineEvent::MenuNext
-            | ReedlineEvent::MenuPrevious
-            | ReedlineEvent::MenuUp
-            | ReedlineEvent::MenuDown
             | ReedlineEvent::MenuLeft
             | ReedlineEvent::MenuRight
             | ReedlineEvent::MenuPageNext
             | ReedlineEvent::MenuPagePrevious
-            | ReedlineEvent::ViChangeMode(_) => Ok(EventStatus::Inapplicable),
+            | ReedlineEvent::ViChangeMode(_)
+            | ReedlineEvent::DigitArgument
+            | ReedlineEvent::UniversalArgument => Ok(EventStatus::Inapplicable),
         }
     }
 
@@ -1362,6 +1837,23 @@ impl Reedline {
                 self.enter_history_search();
                 Ok(EventStatus::Handled)
             }
+            ReedlineEvent::ToggleHistoryBookmark => Ok(self.toggle_history_bookmark()),
+            ReedlineEvent::OperateAndGetNext => {
+                let recalled = if self.input_mode == InputMode::HistoryTraversal
+                    && self.history_excluded_cursor.is_none()
+                {
+                    self.history_cursor
+                        .id_at_cursor()
+                        .and_then(|id| self.history.load(id).ok())
+                } else {
+                    None
+                };
+                self.operate_and_get_next(prompt, recalled)
+            }
+            ReedlineEvent::ToggleHistorySearchFilter(_)
+            | ReedlineEvent::DeleteHistoryItem
+            | ReedlineEvent::DigitArgument
+            | ReedlineEvent::UniversalArgument => Ok(EventStatus::Inapplicable),
             ReedlineEvent::Multiple(events) => {
                 let mut latest_signal = EventStatus::Inapplicable;
                 for event in events {
@@ -1443,20 +1935,20 @@ impl Reedline {
     }
 
     fn previous_history(&mut self) {
-        self.history_cursor_on_excluded = false;
-        if self.input_mode != InputMode::HistoryTraversal {
+        let excluded_position = if self.input_mode != InputMode::HistoryTraversal {
             self.input_mode = InputMode::HistoryTraversal;
             self.history_cursor = HistoryCursor::new(
                 self.get_history_navigation_based_on_line_buffer(),
                 self.get_history_session_id(),
             );
+            Some(0)
+        } else {
+            self.history_excluded_cursor.map(|position| position + 1)
+        };
+        self.history_excluded_cursor = excluded_position
+            .filter(|&position| self.recallable_excluded_items().nth(position).is_some());
 
-            if self.history_excluded_item.is_some() {
-                self.history_cursor_on_excluded = true;
-            }
-        }
-
-        if !self.history_cursor_on_excluded {
+        if self.history_excluded_cursor.is_none() {
             self.history_cursor
                 .back(self.history.as_ref())
                 .expect("todo: error handling");
@@ -1468,6 +1960,17 @@ impl Reedline {
             .update_undo_state(UndoBehavior::HistoryNavigation);
     }
 
+    /// Excluded lines recalled before the saved history, newest first
+    ///
+    /// Lines dropped because of a secret are not recalled. The last excluded line is
+    /// followed by every line kept for this session by [`RedactionAction::SessionOnly`].
+    fn recallable_excluded_items(&self) -> impl Iterator<Item = &HistoryItem> {
+        self.history_excluded_item
+            .iter()
+            .filter(|_| self.history_last_run_id != Some(Self::DROPPED_ITEM_ID))
+            .chain(self.history_session_items.iter().rev())
+    }
+
     fn next_history(&mut self) {
         if self.input_mode != InputMode::HistoryTraversal {
             self.input_mode = InputMode::HistoryTraversal;
@@ -1475,25 +1978,26 @@ impl Reedline {
                 self.get_history_navigation_based_on_line_buffer(),
                 self.get_history_session_id(),
             );
+            self.history_excluded_cursor = None;
         }
 
-        if self.history_cursor_on_excluded {
-            self.history_cursor_on_excluded = false;
+        if let Some(position) = self.history_excluded_cursor {
+            self.history_excluded_cursor = position.checked_sub(1);
         } else {
             let cursor_was_on_item = self.history_cursor.string_at_cursor().is_some();
             self.history_cursor
                 .forward(self.history.as_ref())
                 .expect("todo: error handling");
 
-            if cursor_was_on_item
-                && self.history_cursor.string_at_cursor().is_none()
-                && self.history_excluded_item.is_some()
-            {
-                self.history_cursor_on_excluded = true;
+            if cursor_was_on_item && self.history_cursor.string_at_cursor().is_none() {
+                self.history_excluded_cursor =
+                    self.recallable_excluded_items().count().checked_sub(1);
             }
         }
 
-        if self.history_cursor.string_at_cursor().is_none() && !self.history_cursor_on_excluded {
+        if self.history_cursor.string_at_cursor().is_none()
+            && self.history_excluded_cursor.is_none()
+        {
             self.input_mode = InputMode::Regular;
         }
         self.update_buffer_from_history();
@@ -1527,54 +2031,148 @@ impl Reedline {
     ///
     /// This mode uses a separate prompt and handles keybindings slightly differently!
     fn enter_history_search(&mut self) {
-        self.history_cursor = HistoryCursor::new(
-            HistoryNavigationQuery::SubstringSearch("".to_string()),
-            self.get_history_session_id(),
-        );
+        self.history_search = InteractiveHistorySearch::new(self.history_session_id.is_some());
+        self.refresh_history_search();
         self.input_mode = InputMode::HistorySearch;
     }
 
-    /// Dispatches the applicable [`EditCommand`] actions for editing the history search string.
+    /// Submit the buffer and remember the history entry it was recalled from, if any
+    fn operate_and_get_next(
+        &mut self,
+        prompt: &dyn Prompt,
+        recalled: Option<HistoryItem>,
+    ) -> io::Result<EventStatus> {
+        let status = self.handle_editor_event(prompt, ReedlineEvent::Submit)?;
+        if let EventStatus::Exits(Signal::Success(_)) = status {
+            self.history_recall_next = recalled;
+        }
+        Ok(status)
+    }
+
+    /// Start navigating the history at the entry following the recalled one
     ///
-    /// Only modifies internal state, does not perform regular output!
-    fn run_history_commands(&mut self, commands: &[EditCommand]) {
-        for command in commands {
-            match command {
+    /// Leaves the buffer empty if there is no such entry.
+    fn recall_history_after(&mut self, recalled: &HistoryItem) {
+        let Some(id) = self.find_recalled_entry(recalled) else {
+            return;
+        };
+        let mut cursor = HistoryCursor::new(
+            HistoryNavigationQuery::Normal(LineBuffer::default()),
+            self.get_history_session_id(),
+        );
+        let found = cursor
+            .move_to(self.history.as_ref(), id)
+            .and_then(|()| cursor.forward(self.history.as_ref()));
+        if found.is_err() || cursor.string_at_cursor().is_none() {
+            return;
+        }
+        self.history_cursor = cursor;
+        self.history_excluded_cursor = None;
+        self.input_mode = InputMode::HistoryTraversal;
+        self.update_buffer_from_history();
+        self.editor.move_to_end(false);
+        self.editor
+            .update_undo_state(UndoBehavior::HistoryNavigation);
+    }
+
+    /// The current id of the recalled entry
+    ///
+    /// Saving the submitted line or sharing the history can shift the ids, e.g. when a full
+    /// [`FileBackedHistory`] drops its oldest entry. So this looks for the entry with the same
+    /// command line closest to where it was.
+    fn find_recalled_entry(&self, recalled: &HistoryItem) -> Option<HistoryItemId> {
+        let id = recalled.id?;
+        let session = self.get_history_session_id();
+        let closest = |direction, start_id| {
+            self.history
+                .search(SearchQuery {
+                    start_id: Some(HistoryItemId::new(start_id)),
+                    limit: Some(1),
+                    filter: SearchFilter::from_text_search(
+                        CommandLineSearch::Exact(recalled.command_line.clone()),
+                        session,
+                    ),
+                    ..SearchQuery::everything(direction, session)
+                })
+                .ok()
+                .and_then(|items| items.into_iter().next())
+                .and_then(|item| item.id)
+        };
+        // The bounds are exclusive, so both searches include the old id
+        let before = closest(SearchDirection::Backward, id.0 + 1);
+        let after = closest(SearchDirection::Forward, id.0 - 1);
+        match (before, after) {
+            (Some(before), Some(after)) if after.0 - id.0 < id.0 - before.0 => Some(after),
+            (Some(before), _) => Some(before),
+            (None, after) => after,
+        }
+    }
+
+    /// Bookmark the entry shown while navigating the history or remove its bookmark
+    fn toggle_history_bookmark(&mut self) -> EventStatus {
+        if self.input_mode != InputMode::HistoryTraversal || self.history_excluded_cursor.is_some()
+        {
+            return EventStatus::Inapplicable;
+        }
+        let Some(id) = self.history_cursor.id_at_cursor() else {
+            return EventStatus::Inapplicable;
+        };
+        let toggled = self.history.update(id, &|item| HistoryItem {
+            bookmarked: !item.bookmarked,
+            ..item
+        });
+        match toggled {
+            Ok(()) => EventStatus::Handled,
+            Err(_) => EventStatus::Inapplicable,
+        }
+    }
+
+    fn history_search_context(&self) -> HistorySearchContext {
+        HistorySearchContext {
+            session: self.get_history_session_id(),
+            cwd: self.cwd.clone().unwrap_or_else(|| {
+                std::env::current_dir()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string()
+            }),
+            hostname: self.hostname.clone(),
+        }
+    }
+
+    fn refresh_history_search(&mut self) {
+        let context = self.history_search_context();
+        self.history_search.refresh(self.history.as_ref(), &context);
+    }
+
+    /// Leave the history search with the selected entry in the buffer
+    fn accept_history_search(&mut self) {
+        if let Some(item) = self.history_search.selected_item() {
+            let command_line = item.command_line.clone();
+            self.editor
+                .set_buffer(command_line, UndoBehavior::CreateUndoPoint);
+        }
+        self.input_mode = InputMode::Regular;
+    }
+
+    /// Dispatches the applicable [`EditCommand`] actions for editing the history search string.
+    ///
+    /// Any other command leaves the search and is applied to the selected entry.
+    fn run_history_commands(&mut self, commands: &[EditCommand]) {
+        for (index, command) in commands.iter().enumerate() {
+            match command {
                 EditCommand::InsertChar(c) => {
-                    let navigation = self.history_cursor.get_navigation();
-                    if let HistoryNavigationQuery::SubstringSearch(mut substring) = navigation {
-                        substring.push(*c);
-                        self.history_cursor = HistoryCursor::new(
-                            HistoryNavigationQuery::SubstringSearch(substring),
-                            self.get_history_session_id(),
-                        );
-                    } else {
-                        self.history_cursor = HistoryCursor::new(
-                            HistoryNavigationQuery::SubstringSearch(String::from(*c)),
-                            self.get_history_session_id(),
-                        );
-                    }
-                    self.history_cursor
-                        .back(self.history.as_mut())
-                        .expect("todo: error handling");
+                    self.history_search.push_char(*c);
+                    self.refresh_history_search();
                 }
                 EditCommand::Backspace => {
-                    let navigation = self.history_cursor.get_navigation();
-
-                    if let HistoryNavigationQuery::SubstringSearch(substring) = navigation {
-                        let new_substring = text_manipulation::remove_last_grapheme(&substring);
-
-                        self.history_cursor = HistoryCursor::new(
-                            HistoryNavigationQuery::SubstringSearch(new_substring.to_string()),
-                            self.get_history_session_id(),
-                        );
-                        self.history_cursor
-                            .back(self.history.as_mut())
-                            .expect("todo: error handling");
-                    }
+                    self.history_search.remove_last_grapheme();
+                    self.refresh_history_search();
                 }
                 _ => {
-                    self.input_mode = InputMode::Regular;
+                    self.accept_history_search();
+                    self.run_edit_commands(&commands[index..]);
+                    return;
                 }
             }
         }
@@ -1585,15 +2183,17 @@ impl Reedline {
     /// When using the up/down traversal or fish/zsh style prefix search update the main line buffer accordingly.
     /// Not used for the separate modal reverse search!
     fn update_buffer_from_history(&mut self) {
+        let excluded_line = self.history_excluded_cursor.and_then(|position| {
+            self.recallable_excluded_items()
+                .nth(position)
+                .map(|item| item.command_line.clone())
+        });
+        if let Some(line) = excluded_line {
+            self.editor
+                .set_buffer(line, UndoBehavior::HistoryNavigation);
+            return;
+        }
         match self.history_cursor.get_navigation() {
-            _ if self.history_cursor_on_excluded => self.editor.set_buffer(
-                self.history_excluded_item
-                    .as_ref()
-                    .unwrap()
-                    .command_line
-                    .clone(),
-                UndoBehavior::HistoryNavigation,
-            ),
             HistoryNavigationQuery::Normal(original) => {
                 if let Some(buffer_to_paint) = self.history_cursor.string_at_cursor() {
                     self.editor
@@ -1709,6 +2309,7 @@ impl Reedline {
                         end_id: None,
                         limit: Some(1), // fetch the latest one entries
                         filter: SearchFilter::anything(self.get_history_session_id()),
+                        ranking: SearchRanking::Chronological,
                     })
                     .unwrap_or_else(|_| Vec::new())
                     .get(index.saturating_sub(1))
@@ -1729,6 +2330,7 @@ impl Reedline {
                         end_id: None,
                         limit: Some(index as i64), // fetch the latest n entries
                         filter: SearchFilter::anything(self.get_history_session_id()),
+                        ranking: SearchRanking::Chronological,
                     })
                     .unwrap_or_else(|_| Vec::new())
                     .get(index.saturating_sub(1))
@@ -1792,6 +2394,7 @@ impl Reedline {
                         end_id: None,
                         limit: Some((index + 1) as i64), // fetch the oldest n entries
                         filter: SearchFilter::anything(self.get_history_session_id()),
+                        ranking: SearchRanking::Chronological,
                     })
                     .unwrap_or_else(|_| Vec::new())
                     .get(index)
@@ -1860,49 +2463,51 @@ impl Reedline {
     /// Overwrites the prompt indicator and highlights the search string
     /// separately from the result buffer.
     fn history_search_paint(&mut self, prompt: &dyn Prompt) -> Result<()> {
-        let navigation = self.history_cursor.get_navigation();
-
-        if let HistoryNavigationQuery::SubstringSearch(substring) = navigation {
-            let status =
-                if !substring.is_empty() && self.history_cursor.string_at_cursor().is_none() {
-                    PromptHistorySearchStatus::Failing
-                } else {
-                    PromptHistorySearchStatus::Passing
-                };
-
-            let prompt_history_search = PromptHistorySearch::new(status, substring.clone());
-
-            let res_string = self.history_cursor.string_at_cursor().unwrap_or_default();
-
-            // Highlight matches
-            let res_string = if self.use_ansi_coloring {
-                let match_highlighter = SimpleMatchHighlighter::new(substring);
-                let styled = match_highlighter.highlight(&res_string, 0);
-                styled.render_simple()
-            } else {
-                res_string
-            };
+        let term = self.history_search.term().to_string();
+        let prompt_history_search =
+            PromptHistorySearch::new(self.history_search.status(), term.clone());
+
+        let res_string = self
+            .history_search
+            .selected_item()
+            .map(|item| item.command_line.clone())
+            .unwrap_or_default();
+
+        // Highlight matches
+        let res_string = if self.use_ansi_coloring {
+            let match_highlighter = SimpleMatchHighlighter::new(term);
+            let styled = match_highlighter.highlight(&res_string, 0);
+            styled.render_simple()
+        } else {
+            res_string
+        };
 
-            let lines = PromptLines::new(
-                prompt,
-                self.prompt_edit_mode(),
-                Some(prompt_history_search),
-                &res_string,
-                "",
-                "",
-            );
+        // Leave room for the buffer and the status line below the results
+        let rows = self
+            .history_search_rows
+            .min(self.painter.screen_height().saturating_sub(2) as usize);
+        let width = self.painter.screen_width().saturating_sub(1) as usize;
+        let results = self
+            .history_search
+            .render_list(rows, width, self.use_ansi_coloring);
 
-            self.painter.repaint_buffer(
-                prompt,
-                &lines,
-                self.prompt_edit_mode(),
-                None,
-                self.use_ansi_coloring,
-                &self.cursor_shapes,
-            )?;
-        }
+        let lines = PromptLines::new(
+            prompt,
+            self.prompt_edit_mode(),
+            Some(prompt_history_search),
+            &res_string,
+            "",
+            &results,
+        );
 
-        Ok(())
+        self.painter.repaint_buffer(
+            prompt,
+            &lines,
+            self.prompt_edit_mode(),
+            None,
+            self.use_ansi_coloring,
+            &self.cursor_shapes,
+        )
     }
 
     /// Triggers a full repaint including the prompt parts
@@ -1957,6 +2562,17 @@ impl Reedline {
             &hint,
         );
 
+        let pending_keys = self.edit_mode.pending_key_sequence();
+        if !pending_keys.is_empty() {
+            let pending_keys =
+                prompt.render_prompt_pending_keys(&key_sequence_to_string(pending_keys));
+            lines.prompt_indicator = format!("{pending_keys}{}", lines.prompt_indicator).into();
+        }
+        if let Some(argument) = self.edit_mode.pending_numeric_argument() {
+            let argument = prompt.render_prompt_numeric_argument(argument);
+            lines.prompt_indicator = format!("{argument}{}", lines.prompt_indicator).into();
+        }
+
         // Updating the working details of the active menu
         for menu in self.menus.iter_mut() {
             if menu.is_active() {
@@ -2088,6 +2704,59 @@ impl Reedline {
         Ok(messages)
     }
 
+    /// Whether the most recent history entry equals the command line and the policy ignores
+    /// consecutive duplicates
+    fn repeats_previous_entry(&self, command_line: &str) -> crate::Result<bool> {
+        if self.history_policy.duplicates() != HistoryDuplicates::IgnoreConsecutive {
+            return Ok(false);
+        }
+        let previous = self.history.search(SearchQuery {
+            limit: Some(1),
+            ..SearchQuery::everything(SearchDirection::Backward, None)
+        })?;
+        Ok(previous
+            .first()
+            .map_or(false, |previous| previous.command_line == command_line))
+    }
+
+    /// Save an entry to the history, erasing older copies if the policy asks for it
+    fn save_history_item(&mut self, mut entry: HistoryItem) -> crate::Result<()> {
+        entry.id = None;
+        if self.history_policy.duplicates() == HistoryDuplicates::EraseAll {
+            let copies = self.history.search(SearchQuery {
+                filter: SearchFilter::from_text_search(
+                    CommandLineSearch::Exact(entry.command_line.clone()),
+                    None,
+                ),
+                ..SearchQuery::everything(SearchDirection::Forward, None)
+            })?;
+            for id in copies.into_iter().filter_map(|copy| copy.id) {
+                match self.history.delete(id) {
+                    // Histories without deletion keep their older copies
+                    Err(ReedlineError(ReedlineErrorVariants::HistoryFeatureUnsupported {
+                        ..
+                    })) => break,
+                    result => result?,
+                }
+            }
+        }
+        let entry = self.history.save(entry)?;
+        self.history_last_run_id = entry.id;
+        self.history_excluded_item = None;
+        Ok(())
+    }
+
+    /// Save the entry still waiting for its exit status, unless it is known to have failed
+    fn save_pending_history_item(&mut self) -> crate::Result<()> {
+        if self.history_last_run_id != Some(Self::PENDING_ITEM_ID) {
+            return Ok(());
+        }
+        match self.history_excluded_item.take() {
+            Some(entry) => self.save_history_item(entry),
+            None => Ok(()),
+        }
+    }
+
     fn submit_buffer(&mut self, prompt: &dyn Prompt) -> io::Result<EventStatus> {
         let buffer = self.editor.get_buffer().to_string();
         self.hide_hints = true;
@@ -2099,24 +2768,62 @@ impl Reedline {
             self.repaint(prompt)?;
         }
         if !buffer.is_empty() {
+            self.save_pending_history_item()
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
             let mut entry = HistoryItem::from_command_line(&buffer);
             entry.session_id = self.get_history_session_id();
+            let redaction = self.history_redaction.as_ref().and_then(|redaction| {
+                redaction
+                    .redact(&buffer)
+                    .map(|redacted| (redaction.action(), redacted))
+            });
+            let saved_line = match &redaction {
+                Some((RedactionAction::Mask, redacted)) => redacted.as_str(),
+                _ => buffer.as_str(),
+            };
 
             if self
                 .history_exclusion_prefix
                 .as_ref()
                 .map(|prefix| buffer.starts_with(prefix))
                 .unwrap_or(false)
+                || self.history_policy.ignores(&buffer)
             {
                 entry.id = Some(Self::FILTERED_ITEM_ID);
                 self.history_last_run_id = entry.id;
                 self.history_excluded_item = Some(entry);
-            } else {
-                entry = self.history.save(entry).expect("todo: error handling");
+            } else if matches!(redaction, Some((RedactionAction::SessionOnly, _))) {
+                entry.id = Some(Self::SESSION_ITEM_ID);
                 self.history_last_run_id = entry.id;
                 self.history_excluded_item = None;
+                self.history_session_items.push(entry);
+            } else if matches!(redaction, Some((RedactionAction::Drop, _))) {
+                entry.id = Some(Self::DROPPED_ITEM_ID);
+                self.history_last_run_id = entry.id;
+                self.history_excluded_item = Some(entry);
+            } else if self
+                .repeats_previous_entry(saved_line)
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?
+            {
+                // Not saved again, the context of this run mustn't overwrite the previous entry
+                entry.id = Some(Self::DROPPED_ITEM_ID);
+                self.history_last_run_id = entry.id;
+                self.history_excluded_item = Some(entry);
+            } else {
+                entry.command_line = saved_line.to_string();
+                if self.history_policy.ignores_failed() {
+                    entry.id = Some(Self::PENDING_ITEM_ID);
+                    self.history_last_run_id = entry.id;
+                    self.history_excluded_item = Some(entry);
+                } else {
+                    self.save_history_item(entry)
+                        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+                }
             }
         }
+        if self.history_sharing != HistorySharing::Manual {
+            self.history.sync()?;
+        }
         self.run_edit_commands(&[EditCommand::Clear]);
         self.editor.reset_undo_stack();
 
@@ -2128,7 +2835,10 @@ impl Reedline {
 mod tests {
     use super::*;
     use crate::terminal_extensions::semantic_prompt::PromptKind;
-    use crate::DefaultPrompt;
+    use crate::{
+        default_emacs_keybindings, DefaultHinter, DefaultPrompt, DefaultPromptSegment,
+        HeadlessBackend, KeyCombination, MenuBuilder,
+    };
 
     #[test]
     fn test_cursor_position_after_multiline_history_navigation() {
@@ -2258,6 +2968,692 @@ mod tests {
         assert_eq!(reedline.current_insertion_point(), 0);
     }
 
+    fn headless_reedline(width: u16, height: u16) -> (Reedline, HeadlessBackend) {
+        let terminal = HeadlessBackend::new(width, height);
+        let reedline = Reedline::create().with_terminal_backend(Box::new(terminal.clone()));
+        (reedline, terminal)
+    }
+
+    fn plain_prompt(left: &str) -> DefaultPrompt {
+        DefaultPrompt::new(
+            DefaultPromptSegment::Basic(left.to_string()),
+            DefaultPromptSegment::Empty,
+        )
+    }
+
+    #[test]
+    fn handle_raw_events_returns_signal_on_submit() {
+        let (mut reedline, terminal) = headless_reedline(40, 5);
+        let prompt = plain_prompt("~");
+        let key = |code| {
+            ReedlineRawEvent::try_from(Event::Key(KeyEvent::new(code, KeyModifiers::NONE))).unwrap()
+        };
+
+        reedline.begin_read_line(&prompt).unwrap();
+        assert!(terminal.is_raw_mode_enabled());
+
+        let signal = reedline
+            .handle_raw_events(&prompt, [key(KeyCode::Char('l')), key(KeyCode::Char('s'))])
+            .unwrap();
+        assert!(signal.is_none());
+        assert_eq!(reedline.current_buffer_contents(), "ls");
+        assert_eq!(terminal.screen_lines()[0], "~〉ls");
+
+        let signal = reedline
+            .handle_raw_events(&prompt, [key(KeyCode::Enter)])
+            .unwrap();
+        assert!(matches!(signal, Some(Signal::Success(buffer)) if buffer == "ls"));
+        assert!(reedline.current_buffer_contents().is_empty());
+        assert!(!terminal.is_raw_mode_enabled());
+    }
+
+    #[test]
+    fn headless_read_line_renders_prompt_and_buffer() {
+        let (mut reedline, terminal) = headless_reedline(40, 5);
+        let prompt = plain_prompt("~");
+
+        terminal.type_str("hello wrld");
+        terminal.push_key(KeyCode::Left, KeyModifiers::NONE);
+        terminal.push_key(KeyCode::Left, KeyModifiers::NONE);
+        terminal.push_key(KeyCode::Left, KeyModifiers::NONE);
+        terminal.type_str("o");
+        terminal.push_key(KeyCode::Enter, KeyModifiers::NONE);
+
+        let signal = reedline.read_line(&prompt).unwrap();
+        assert!(matches!(signal, Signal::Success(buffer) if buffer == "hello world"));
+        assert_eq!(terminal.screen_lines()[0], "~〉hello world");
+        // The cursor is moved below the submitted line
+        assert_eq!(terminal.cursor(), (0, 1));
+        assert!(!terminal.is_raw_mode_enabled());
+    }
+
+    #[test]
+    fn headless_read_line_errors_when_input_is_exhausted() {
+        let (mut reedline, terminal) = headless_reedline(40, 5);
+        terminal.type_str("partial");
+
+        let result = reedline.read_line(&plain_prompt("~"));
+        assert!(result.is_err());
+        assert_eq!(reedline.current_buffer_contents(), "partial");
+        assert_eq!(terminal.screen_lines()[0], "~〉partial");
+    }
+
+    #[test]
+    fn headless_transient_prompt_replaces_prompt_on_submit() {
+        let (reedline, terminal) = headless_reedline(40, 5);
+        let mut reedline = reedline.with_transient_prompt(Box::new(plain_prompt("$")));
+
+        terminal.type_str("ls");
+        terminal.push_key(KeyCode::Enter, KeyModifiers::NONE);
+        reedline.read_line(&plain_prompt("~/long/path")).unwrap();
+
+        assert_eq!(terminal.screen_lines()[0], "$〉ls");
+    }
+
+    #[test]
+    fn headless_hint_is_rendered_after_buffer() {
+        let (reedline, terminal) = headless_reedline(40, 5);
+        let mut reedline = reedline.with_hinter(Box::new(DefaultHinter::default()));
+        reedline
+            .history
+            .save(HistoryItem::from_command_line("git status"))
+            .unwrap();
+
+        terminal.type_str("git s");
+        let _ = reedline.read_line(&plain_prompt("~"));
+
+        assert_eq!(terminal.screen_lines()[0], "~〉git status");
+        assert_eq!(terminal.cursor(), (8, 0));
+    }
+
+    #[test]
+    fn headless_completion_menu_lists_suggestions() {
+        let mut keybindings = crate::default_emacs_keybindings();
+        keybindings.add_binding(
+            KeyModifiers::NONE,
+            KeyCode::Tab,
+            ReedlineEvent::UntilFound(vec![
+                ReedlineEvent::Menu("completion_menu".to_string()),
+                ReedlineEvent::MenuNext,
+            ]),
+        );
+        let completer = DefaultCompleter::new_with_wordlen(
+            vec!["hello".into(), "help".into(), "world".into()],
+            2,
+        );
+        let menu = crate::ColumnarMenu::default().with_name("completion_menu");
+        let (reedline, terminal) = headless_reedline(40, 5);
+        let mut reedline = reedline
+            .with_completer(Box::new(completer))
+            .with_menu(ReedlineMenu::EngineCompleter(Box::new(menu)))
+            .with_edit_mode(Box::new(Emacs::new(keybindings)))
+            .with_ansi_colors(false);
+
+        terminal.type_str("he");
+        terminal.push_key(KeyCode::Tab, KeyModifiers::NONE);
+        let _ = reedline.read_line(&plain_prompt("~"));
+
+        // Without ansi coloring the selected entry is marked by upper case
+        assert_eq!(
+            terminal.screen_lines()[..3],
+            ["~| he", ">HELLO    help", ""]
+        );
+    }
+
+    #[test]
+    fn pending_key_sequence_is_shown_in_the_prompt() {
+        let (reedline, terminal) = headless_reedline(40, 5);
+        let mut keybindings = default_emacs_keybindings();
+        let plain = |c| KeyCombination::new(KeyModifiers::NONE, KeyCode::Char(c));
+        keybindings.add_sequence_binding(
+            vec![
+                KeyCombination::new(KeyModifiers::CONTROL, KeyCode::Char('x')),
+                plain('u'),
+            ],
+            ReedlineEvent::Edit(vec![EditCommand::Undo]),
+        );
+        keybindings.add_sequence_binding(vec![plain('j'), plain('k')], ReedlineEvent::Esc);
+        let mut reedline = reedline.with_edit_mode(Box::new(
+            Emacs::new(keybindings).with_key_sequence_timeout(None),
+        ));
+        let prompt = plain_prompt("~");
+        let key = |modifiers, c| {
+            ReedlineRawEvent::try_from(Event::Key(KeyEvent::new(KeyCode::Char(c), modifiers)))
+                .unwrap()
+        };
+
+        reedline.begin_read_line(&prompt).unwrap();
+        reedline
+            .handle_raw_events(
+                &prompt,
+                [
+                    key(KeyModifiers::NONE, 'a'),
+                    key(KeyModifiers::CONTROL, 'x'),
+                ],
+            )
+            .unwrap();
+        assert_eq!(terminal.screen_lines()[0], "~C-x 〉a");
+
+        reedline
+            .handle_raw_events(&prompt, [key(KeyModifiers::NONE, 'u')])
+            .unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "");
+        assert_eq!(terminal.screen_lines()[0], "~〉");
+
+        reedline
+            .handle_raw_events(&prompt, [key(KeyModifiers::NONE, 'j')])
+            .unwrap();
+        assert_eq!(terminal.screen_lines()[0], "~j 〉");
+        assert_eq!(reedline.key_sequence_timeout(), None);
+        reedline.handle_key_sequence_timeout(&prompt).unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "j");
+        assert_eq!(terminal.screen_lines()[0], "~〉j");
+    }
+
+    #[test]
+    fn numeric_argument_is_shown_in_the_prompt() {
+        let (mut reedline, terminal) = headless_reedline(40, 5);
+        let prompt = plain_prompt("~");
+        let key = |modifiers, c| {
+            ReedlineRawEvent::try_from(Event::Key(KeyEvent::new(KeyCode::Char(c), modifiers)))
+                .unwrap()
+        };
+
+        reedline.begin_read_line(&prompt).unwrap();
+        reedline
+            .handle_raw_events(&prompt, [key(KeyModifiers::ALT, '3')])
+            .unwrap();
+        assert_eq!(terminal.screen_lines()[0], "~(arg: 3) 〉");
+
+        reedline
+            .handle_raw_events(&prompt, [key(KeyModifiers::NONE, 'x')])
+            .unwrap();
+        assert_eq!(reedline.current_buffer_contents(), "xxx");
+        assert_eq!(terminal.screen_lines()[0], "~〉xxx");
+    }
+
+    fn headless_reedline_with_history(entries: &[&str]) -> (Reedline, HeadlessBackend) {
+        let (reedline, terminal) = headless_reedline(40, 8);
+        let mut reedline = reedline.with_ansi_colors(false);
+        for entry in entries {
+            reedline
+                .history
+                .save(HistoryItem::from_command_line(*entry))
+                .unwrap();
+        }
+        (reedline, terminal)
+    }
+
+    #[test]
+    fn headless_history_search_lists_matching_entries() {
+        let (mut reedline, terminal) =
+            headless_reedline_with_history(&["git status", "ls", "git log", "git status"]);
+
+        terminal.push_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        terminal.type_str("git");
+        terminal.push_key(KeyCode::Down, KeyModifiers::NONE);
+        let _ = reedline.read_line(&plain_prompt("~"));
+
+        assert_eq!(
+            terminal.screen_lines()[..5],
+            [
+                "~(reverse-search: git) git log",
+                "  git status",
+                "> git log",
+                "  2/2",
+                ""
+            ]
+        );
+    }
+
+    #[test]
+    fn headless_history_search_accepts_selected_entry() {
+        let (mut reedline, terminal) = headless_reedline_with_history(&["git status", "git log"]);
+
+        terminal.push_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        terminal.type_str("git");
+        terminal.push_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        terminal.push_key(KeyCode::Enter, KeyModifiers::NONE);
+        terminal.type_str(" -s");
+        let _ = reedline.read_line(&plain_prompt("~"));
+
+        assert_eq!(reedline.current_buffer_contents(), "git status -s");
+        assert_eq!(terminal.screen_lines()[..2], ["~〉git status -s", ""]);
+    }
+
+    #[test]
+    fn headless_history_search_edit_command_continues_with_selection() {
+        let (mut reedline, terminal) = headless_reedline_with_history(&["git status", "git log"]);
+
+        terminal.push_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        terminal.type_str("status");
+        terminal.push_key(KeyCode::Char('a'), KeyModifiers::CONTROL);
+        terminal.type_str("sudo ");
+        let _ = reedline.read_line(&plain_prompt("~"));
+
+        assert_eq!(reedline.current_buffer_contents(), "sudo git status");
+    }
+
+    #[test]
+    fn headless_history_search_reports_unsupported_filter() {
+        let (mut reedline, terminal) = headless_reedline_with_history(&["ls"]);
+
+        terminal.push_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        terminal.push_key(KeyCode::Char('w'), KeyModifiers::ALT);
+        let _ = reedline.read_line(&plain_prompt("~"));
+
+        let lines = terminal.screen_lines();
+        assert_eq!(lines[0], "~(failing reverse-search: )");
+        assert_eq!(lines[1], "  the history FileBackedHistory does no");
+    }
+
+    #[test]
+    fn bookmarking_in_the_history_search_reports_unsupported_histories() {
+        let (mut reedline, terminal) = headless_reedline_with_history(&["ls"]);
+
+        terminal.push_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        terminal.push_key(KeyCode::Char('k'), KeyModifiers::ALT);
+        let _ = reedline.read_line(&plain_prompt("~"));
+
+        let lines = terminal.screen_lines();
+        assert_eq!(lines[0], "~(failing reverse-search: ) ls");
+        assert_eq!(lines[2], "  the history FileBackedHistory does no");
+    }
+
+    #[test]
+    fn history_search_keybindings_apply_only_during_the_search() {
+        let (reedline, terminal) = headless_reedline_with_history(&["ls"]);
+        let mut keybindings = Keybindings::new();
+        keybindings.add_binding(
+            KeyModifiers::CONTROL,
+            KeyCode::Char('g'),
+            ReedlineEvent::ToggleHistorySearchFilter(crate::HistorySearchFilter::Cwd),
+        );
+        let mut reedline = reedline.with_history_search_keybindings(keybindings);
+
+        // Outside of the search the key keeps the binding of the edit mode, `Redo`
+        terminal.type_str("pwd");
+        terminal.push_key(KeyCode::Char('z'), KeyModifiers::CONTROL);
+        terminal.push_key(KeyCode::Char('g'), KeyModifiers::CONTROL);
+        terminal.push_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        terminal.push_key(KeyCode::Char('g'), KeyModifiers::CONTROL);
+        let _ = reedline.read_line(&plain_prompt("~"));
+
+        let lines = terminal.screen_lines();
+        assert_eq!(lines[0], "~(failing reverse-search: )");
+        assert_eq!(lines[1], "  the history FileBackedHistory does no");
+        assert_eq!(reedline.current_buffer_contents(), "pwd");
+    }
+
+    #[test]
+    fn operate_and_get_next_recalls_the_following_entry() {
+        let (mut reedline, _) =
+            headless_reedline_with_history(&["cd project", "make", "make install"]);
+        let prompt = plain_prompt("~");
+        let operate_and_get_next = |reedline: &mut Reedline| {
+            let status = reedline
+                .handle_event(&prompt, ReedlineEvent::OperateAndGetNext)
+                .unwrap();
+            reedline.end_read_line().unwrap();
+            reedline.begin_read_line(&prompt).unwrap();
+            match status {
+                EventStatus::Exits(Signal::Success(line)) => line,
+                _ => panic!("the line wasn't submitted"),
+            }
+        };
+
+        reedline.begin_read_line(&prompt).unwrap();
+        for _ in 0..3 {
+            reedline.previous_history();
+        }
+        assert_eq!(operate_and_get_next(&mut reedline), "cd project");
+        assert_eq!(reedline.current_buffer_contents(), "make");
+        assert_eq!(operate_and_get_next(&mut reedline), "make");
+        assert_eq!(reedline.current_buffer_contents(), "make install");
+
+        // Navigating continues from the recalled entry
+        reedline.previous_history();
+        assert_eq!(reedline.current_buffer_contents(), "make");
+
+        // A line that wasn't recalled is only submitted
+        reedline.run_edit_commands(&[EditCommand::Clear, EditCommand::InsertString("ls".into())]);
+        assert_eq!(operate_and_get_next(&mut reedline), "ls");
+        assert!(reedline.current_buffer_contents().is_empty());
+    }
+
+    #[test]
+    fn operate_and_get_next_follows_the_entry_in_a_full_history() {
+        let (reedline, _) = headless_reedline(40, 8);
+        let mut reedline = reedline.with_history(Box::new(FileBackedHistory::new(3).unwrap()));
+        for entry in ["cd project", "make", "make install"] {
+            reedline
+                .history
+                .save(HistoryItem::from_command_line(entry))
+                .unwrap();
+        }
+        let prompt = plain_prompt("~");
+
+        reedline.begin_read_line(&prompt).unwrap();
+        reedline.previous_history();
+        reedline.previous_history();
+        assert_eq!(reedline.current_buffer_contents(), "make");
+        // Saving drops "cd project", so the ids of the remaining entries shift down
+        let status = reedline
+            .handle_event(&prompt, ReedlineEvent::OperateAndGetNext)
+            .unwrap();
+        assert!(matches!(status, EventStatus::Exits(Signal::Success(line)) if line == "make"));
+        reedline.end_read_line().unwrap();
+        reedline.begin_read_line(&prompt).unwrap();
+
+        assert_eq!(reedline.current_buffer_contents(), "make install");
+    }
+
+    #[test]
+    fn operate_and_get_next_continues_after_the_history_search_selection() {
+        let (mut reedline, terminal) =
+            headless_reedline_with_history(&["git pull", "cargo build", "git log"]);
+
+        terminal.push_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        terminal.type_str("pull");
+        let _ = reedline.read_line(&plain_prompt("~"));
+        let status = reedline
+            .handle_event(&plain_prompt("~"), ReedlineEvent::OperateAndGetNext)
+            .unwrap();
+        assert!(matches!(status, EventStatus::Exits(Signal::Success(line)) if line == "git pull"));
+
+        let _ = reedline.read_line(&plain_prompt("~"));
+        assert_eq!(reedline.current_buffer_contents(), "cargo build");
+    }
+
+    fn submit_lines(reedline: &mut Reedline, lines: &[&str]) {
+        let prompt = plain_prompt("~");
+        for line in lines {
+            reedline.begin_read_line(&prompt).unwrap();
+            reedline
+                .editor
+                .set_buffer(line.to_string(), UndoBehavior::CreateUndoPoint);
+            reedline.submit_buffer(&prompt).unwrap();
+            reedline.end_read_line().unwrap();
+        }
+    }
+
+    fn history_lines(reedline: &Reedline) -> Vec<String> {
+        reedline
+            .history
+            .search(SearchQuery::everything(SearchDirection::Forward, None))
+            .unwrap()
+            .into_iter()
+            .map(|item| item.command_line)
+            .collect()
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn toggles_bookmark_of_the_navigated_entry() {
+        let (reedline, _) = headless_reedline(40, 5);
+        let mut reedline =
+            reedline.with_history(Box::new(crate::SqliteBackedHistory::in_memory().unwrap()));
+        submit_lines(&mut reedline, &["ls", "make"]);
+        let bookmarked = |reedline: &Reedline| {
+            let mut query = SearchQuery::everything(SearchDirection::Forward, None);
+            query.filter.bookmarked = Some(true);
+            let items = reedline.history.search(query).unwrap();
+            items
+                .into_iter()
+                .map(|item| item.command_line)
+                .collect::<Vec<_>>()
+        };
+
+        assert!(matches!(
+            reedline.toggle_history_bookmark(),
+            EventStatus::Inapplicable
+        ));
+        reedline.previous_history();
+        reedline.previous_history();
+        assert!(matches!(
+            reedline.toggle_history_bookmark(),
+            EventStatus::Handled
+        ));
+        assert_eq!(bookmarked(&reedline), ["ls"]);
+
+        reedline.toggle_history_bookmark();
+        assert!(bookmarked(&reedline).is_empty());
+    }
+
+    #[test]
+    fn history_policy_ignores_lines() {
+        let (reedline, _) = headless_reedline(40, 5);
+        let mut reedline = reedline.with_history_policy(
+            HistoryPolicy::default()
+                .with_ignore_space(true)
+                .with_min_length(2)
+                .with_ignore_pattern(crate::HistoryIgnorePattern::glob("exit*")),
+        );
+
+        submit_lines(
+            &mut reedline,
+            &["ls -l", " cat secret", "x", "exit 1", "pwd"],
+        );
+
+        assert_eq!(history_lines(&reedline), ["ls -l", "pwd"]);
+    }
+
+    #[test]
+    fn history_policy_ignores_failed_commands() {
+        let (reedline, _) = headless_reedline(40, 5);
+        let mut reedline =
+            reedline.with_history_policy(HistoryPolicy::default().with_ignore_failed(true));
+        let exit_status = |status| {
+            move |item: HistoryItem| HistoryItem {
+                exit_status: Some(status),
+                ..item
+            }
+        };
+
+        submit_lines(&mut reedline, &["make"]);
+        reedline
+            .update_last_command_context(&exit_status(2))
+            .unwrap();
+        submit_lines(&mut reedline, &["make test"]);
+        reedline
+            .update_last_command_context(&exit_status(0))
+            .unwrap();
+        // Without a known exit status the entry is saved with the next line
+        submit_lines(&mut reedline, &["ls", "pwd"]);
+
+        assert_eq!(history_lines(&reedline), ["make test", "ls"]);
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn history_policy_erases_older_duplicates() {
+        let (reedline, _) = headless_reedline(40, 5);
+        let mut reedline = reedline
+            .with_history(Box::new(crate::SqliteBackedHistory::in_memory().unwrap()))
+            .with_history_policy(
+                HistoryPolicy::default().with_duplicates(HistoryDuplicates::EraseAll),
+            );
+
+        submit_lines(&mut reedline, &["ls", "pwd", "ls", "cd", "pwd"]);
+
+        assert_eq!(history_lines(&reedline), ["ls", "cd", "pwd"]);
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn history_policy_ignores_consecutive_duplicates() {
+        let (reedline, _) = headless_reedline(40, 5);
+        let mut reedline = reedline
+            .with_history(Box::new(crate::SqliteBackedHistory::in_memory().unwrap()))
+            .with_history_policy(
+                HistoryPolicy::default().with_duplicates(HistoryDuplicates::IgnoreConsecutive),
+            );
+
+        submit_lines(&mut reedline, &["ls", "ls", "pwd", "ls"]);
+
+        assert_eq!(history_lines(&reedline), ["ls", "pwd", "ls"]);
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn skipped_duplicate_keeps_the_context_of_the_previous_entry() {
+        let (reedline, _) = headless_reedline(40, 5);
+        let mut reedline = reedline
+            .with_history(Box::new(crate::SqliteBackedHistory::in_memory().unwrap()))
+            .with_history_policy(
+                HistoryPolicy::default().with_duplicates(HistoryDuplicates::IgnoreConsecutive),
+            );
+        let exit_status = |status| {
+            move |item: HistoryItem| HistoryItem {
+                exit_status: Some(status),
+                ..item
+            }
+        };
+
+        submit_lines(&mut reedline, &["make"]);
+        reedline
+            .update_last_command_context(&exit_status(0))
+            .unwrap();
+        submit_lines(&mut reedline, &["make"]);
+        reedline
+            .update_last_command_context(&exit_status(2))
+            .unwrap();
+
+        let items = reedline
+            .history
+            .search(SearchQuery::everything(SearchDirection::Forward, None))
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].exit_status, Some(0));
+
+        // The skipped line isn't recalled a second time either
+        reedline.previous_history();
+        assert_eq!(reedline.current_buffer_contents(), "make");
+        reedline.previous_history();
+        assert_eq!(reedline.current_buffer_contents(), "make");
+    }
+
+    #[test]
+    fn history_redaction_masks_drops_or_keeps_secrets_in_memory() {
+        let (reedline, _) = headless_reedline(40, 5);
+        let mut reedline = reedline.with_history_redaction(Some(HistoryRedaction::default()));
+        submit_lines(&mut reedline, &["export API_TOKEN=hunter2", "ls"]);
+        assert_eq!(history_lines(&reedline), ["export API_TOKEN=***", "ls"]);
+
+        for (action, recalled) in [
+            (RedactionAction::Drop, "ls"),
+            (RedactionAction::SessionOnly, "export API_TOKEN=hunter2"),
+        ] {
+            let (reedline, _) = headless_reedline(40, 5);
+            let mut reedline = reedline
+                .with_history_redaction(Some(HistoryRedaction::default().with_action(action)));
+            submit_lines(&mut reedline, &["ls", "export API_TOKEN=hunter2"]);
+            assert_eq!(history_lines(&reedline), ["ls"]);
+            assert!(reedline.has_last_command_context());
+
+            reedline.previous_history();
+            assert_eq!(reedline.current_buffer_contents(), recalled);
+        }
+    }
+
+    #[test]
+    fn session_only_redaction_keeps_every_secret_line_of_the_session() {
+        let (reedline, _) = headless_reedline(40, 5);
+        let mut reedline = reedline.with_history_redaction(Some(
+            HistoryRedaction::default().with_action(RedactionAction::SessionOnly),
+        ));
+        submit_lines(
+            &mut reedline,
+            &["export API_TOKEN=first", "ls", "export API_TOKEN=second"],
+        );
+        reedline
+            .update_last_command_context(&|item| HistoryItem {
+                exit_status: Some(1),
+                ..item
+            })
+            .unwrap();
+        assert_eq!(history_lines(&reedline), ["ls"]);
+        assert_eq!(
+            reedline.history_session_items.last().unwrap().exit_status,
+            Some(1)
+        );
+
+        let mut recalled = vec![];
+        for _ in 0..4 {
+            reedline.previous_history();
+            recalled.push(reedline.current_buffer_contents().to_string());
+        }
+        assert_eq!(
+            recalled,
+            [
+                "export API_TOKEN=second",
+                "export API_TOKEN=first",
+                "ls",
+                "ls"
+            ]
+        );
+
+        for expected in ["export API_TOKEN=first", "export API_TOKEN=second", ""] {
+            reedline.next_history();
+            assert_eq!(reedline.current_buffer_contents(), expected);
+        }
+    }
+
+    #[test]
+    fn shared_history_picks_up_entries_of_other_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("history.txt");
+        let shared_reedline = || {
+            let (reedline, _) = headless_reedline(40, 5);
+            reedline
+                .with_history(Box::new(
+                    FileBackedHistory::with_file(100, file.clone()).unwrap(),
+                ))
+                .with_history_sharing(HistorySharing::OnPrompt)
+        };
+        let mut first = shared_reedline();
+        let mut second = shared_reedline();
+
+        submit_lines(&mut first, &["from first"]);
+        submit_lines(&mut second, &["from second"]);
+        assert_eq!(history_lines(&second), ["from first", "from second"]);
+
+        // The navigation of the first session isn't disturbed by the merge
+        first.previous_history();
+        first.share_history().unwrap();
+        assert_eq!(history_lines(&first), ["from first"]);
+        assert_eq!(first.current_buffer_contents(), "from first");
+
+        first.run_edit_commands(&[EditCommand::Clear]);
+        first.share_history().unwrap();
+        assert_eq!(history_lines(&first), ["from first", "from second"]);
+    }
+
+    #[test]
+    fn periodic_sharing_picks_up_entries_while_handling_raw_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("history.txt");
+        let shared_reedline = || {
+            let (reedline, _) = headless_reedline(40, 5);
+            reedline
+                .with_history(Box::new(
+                    FileBackedHistory::with_file(100, file.clone()).unwrap(),
+                ))
+                .with_history_sharing(HistorySharing::Periodic(Duration::ZERO))
+        };
+        let mut first = shared_reedline();
+        let mut second = shared_reedline();
+        let prompt = plain_prompt("~");
+
+        second.begin_read_line(&prompt).unwrap();
+        submit_lines(&mut first, &["from first"]);
+        assert!(history_lines(&second).is_empty());
+
+        assert!(second.handle_raw_events(&prompt, []).unwrap().is_none());
+        assert_eq!(history_lines(&second), ["from first"]);
+        second.end_read_line().unwrap();
+    }
+
     #[test]
     fn mouse_click_osc133_sets_semantic_markers() {
         let reedline = Reedline::create().with_mouse_click(MouseClickMode::EnabledWithOsc133);
@@ -1,4 +1,4 @@
-use crossterm::event::{Event, KeyEvent, KeyEventKind};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 use strum_macros::EnumIter;
@@ -274,6 +274,16 @@ pub enum EditCommand {
     /// Paste the cut buffer in front of the insertion point (vi `p`)
     PasteCutBufferAfter,
 
+    /// Replace the text inserted by the directly preceding paste or yank-pop with the next
+    /// older kill ring entry (Emacs `M-y`)
+    YankPop,
+
+    /// Use the given vi register (`a`-`z`, `A`-`Z` to append, `0`-`9`, `"`, `_`, `+`, `*`)
+    /// instead of the unnamed one for the next cut, copy or paste
+    ///
+    /// `+` and `*` use the system clipboard if the `system_clipboard` feature is enabled.
+    SelectRegister(char),
+
     /// Upper case the current word
     UppercaseWord,
 
@@ -546,6 +556,8 @@ impl Display for EditCommand {
             EditCommand::CutBigWordRightToNext => write!(f, "CutBigWordRightToNext"),
             EditCommand::PasteCutBufferBefore => write!(f, "PasteCutBufferBefore"),
             EditCommand::PasteCutBufferAfter => write!(f, "PasteCutBufferAfter"),
+            EditCommand::YankPop => write!(f, "YankPop"),
+            EditCommand::SelectRegister(_) => write!(f, "SelectRegister Value: <char>"),
             EditCommand::UppercaseWord => write!(f, "UppercaseWord"),
             EditCommand::LowercaseWord => write!(f, "LowercaseWord"),
             EditCommand::SwitchcaseChar => write!(f, "SwitchcaseChar"),
@@ -662,6 +674,7 @@ impl EditCommand {
             | EditCommand::CutBigWordRightToNext
             | EditCommand::PasteCutBufferBefore
             | EditCommand::PasteCutBufferAfter
+            | EditCommand::YankPop
             | EditCommand::UppercaseWord
             | EditCommand::LowercaseWord
             | EditCommand::SwitchcaseChar
@@ -683,7 +696,7 @@ impl EditCommand {
 
             EditCommand::Undo | EditCommand::Redo => EditType::UndoRedo,
 
-            EditCommand::CopySelection => EditType::NoOp,
+            EditCommand::CopySelection | EditCommand::SelectRegister(_) => EditType::NoOp,
             #[cfg(feature = "system_clipboard")]
             EditCommand::CopySelectionSystem => EditType::NoOp,
             EditCommand::CopyFromStart
@@ -780,6 +793,37 @@ impl UndoBehavior {
     }
 }
 
+/// Filters of the interactive history search that can be switched on and off.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HistorySearchFilter {
+    /// Only entries of the current history session
+    #[default]
+    Session,
+    /// Only entries run in the current working directory
+    Cwd,
+    /// Only entries that completed successfully
+    Successful,
+    /// Only entries run on the current host
+    Host,
+    /// Only bookmarked entries
+    Bookmarked,
+    /// Match the search term as a regular expression instead of a substring
+    Regex,
+}
+
+impl Display for HistorySearchFilter {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            HistorySearchFilter::Session => write!(f, "Session"),
+            HistorySearchFilter::Cwd => write!(f, "Cwd"),
+            HistorySearchFilter::Successful => write!(f, "Successful"),
+            HistorySearchFilter::Host => write!(f, "Host"),
+            HistorySearchFilter::Bookmarked => write!(f, "Bookmarked"),
+            HistorySearchFilter::Regex => write!(f, "Regex"),
+        }
+    }
+}
+
 /// Reedline supported actions.
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug, EnumIter)]
 pub enum ReedlineEvent {
@@ -870,6 +914,29 @@ pub enum ReedlineEvent {
     /// Search the history for a string
     SearchHistory,
 
+    /// Switch a filter of the history search on or off
+    ToggleHistorySearchFilter(HistorySearchFilter),
+
+    /// Delete the entry selected in the history search from the history
+    DeleteHistoryItem,
+
+    /// Bookmark the history entry being navigated or selected in the history search,
+    /// or remove its bookmark
+    ///
+    /// Bookmarks need a history that can update entries, like the SQLite or JSONL backed ones.
+    /// The history search shows why bookmarking failed, e.g. with a
+    /// [`FileBackedHistory`](crate::FileBackedHistory). While navigating the history the entry
+    /// is left unchanged.
+    ToggleHistoryBookmark,
+
+    /// Submit the line recalled from the history and recall the entry following it at the
+    /// start of the next line editing session, like `operate-and-get-next` of bash
+    ///
+    /// Replays a sequence of commands from the history one line at a time. Works like
+    /// [`ReedlineEvent::Submit`] if the line wasn't recalled from the history. Bash binds it to
+    /// `Ctrl-O`, which is bound to [`ReedlineEvent::OpenEditor`] by default.
+    OperateAndGetNext,
+
     /// In vi mode multiple reedline events can be chained while parsing the
     /// command or movement characters
     Multiple(Vec<ReedlineEvent>),
@@ -912,6 +979,12 @@ pub enum ReedlineEvent {
 
     /// Change mode (vi mode only)
     ViChangeMode(String),
+
+    /// Start or extend the numeric argument with the digit or `-` of the pressed key (emacs mode only)
+    DigitArgument,
+
+    /// Start the numeric argument with 4 or multiply it by 4 (emacs mode only)
+    UniversalArgument,
 }
 
 impl Display for ReedlineEvent {
@@ -946,6 +1019,14 @@ impl Display for ReedlineEvent {
             ReedlineEvent::Left => write!(f, "Left"),
             ReedlineEvent::NextHistory => write!(f, "NextHistory"),
             ReedlineEvent::SearchHistory => write!(f, "SearchHistory"),
+            ReedlineEvent::ToggleHistorySearchFilter(_) => {
+                write!(f, "ToggleHistorySearchFilter filter: <string>")
+            }
+            ReedlineEvent::DeleteHistoryItem => write!(f, "DeleteHistoryItem"),
+            ReedlineEvent::ToggleHistoryBookmark => write!(f, "ToggleHistoryBookmark"),
+            ReedlineEvent::OperateAndGetNext => write!(f, "OperateAndGetNext"),
+            ReedlineEvent::DigitArgument => write!(f, "DigitArgument"),
+            ReedlineEvent::UniversalArgument => write!(f, "UniversalArgument"),
             ReedlineEvent::Multiple(_) => write!(f, "Multiple[ {{ ReedLineEvents, }} ]"),
             ReedlineEvent::UntilFound(_) => write!(f, "UntilFound [ {{ ReedLineEvents, }} ]"),
             ReedlineEvent::Menu(_) => write!(f, "Menu Name: <string>"),
@@ -1002,6 +1083,18 @@ impl TryFrom<Event> for ReedlineRawEvent {
     }
 }
 
+impl ReedlineRawEvent {
+    /// The modifiers and the key of a key event
+    pub(crate) fn key(&self) -> Option<(KeyModifiers, KeyCode)> {
+        match &self.0 {
+            Event::Key(KeyEvent {
+                code, modifiers, ..
+            }) => Some((*modifiers, *code)),
+            _ => None,
+        }
+    }
+}
+
 impl From<ReedlineRawEvent> for Event {
     fn from(event: ReedlineRawEvent) -> Self {
         event.0
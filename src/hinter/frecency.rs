@@ -0,0 +1,113 @@
+use crate::{
+    hinter::get_first_token,
+    history::{FrecencyContext, SearchQuery},
+    Hinter, History,
+};
+use nu_ansi_term::{Color, Style};
+
+/// A hinter that suggests the most useful history entry instead of the most recent one
+///
+/// Entries are ranked by [`SearchRanking::Frecency`](crate::SearchRanking::Frecency): how often
+/// and how recently they were run, whether in the current directory and session, and whether
+/// they succeeded.
+pub struct FrecencyHinter {
+    style: Style,
+    current_hint: String,
+    min_chars: usize,
+}
+
+impl Hinter for FrecencyHinter {
+    fn handle(
+        &mut self,
+        line: &str,
+        #[allow(unused_variables)] pos: usize,
+        history: &dyn History,
+        use_ansi_coloring: bool,
+        cwd: &str,
+    ) -> String {
+        self.current_hint = if line.chars().count() >= self.min_chars {
+            let cwd = (!cwd.is_empty()).then(|| cwd.to_string());
+            let context = FrecencyContext::new(cwd, history.session());
+            history
+                .search(SearchQuery::frecent_with_prefix(line.to_string(), context))
+                .or_else(|_| {
+                    history.search(SearchQuery::last_with_prefix(
+                        line.to_string(),
+                        history.session(),
+                    ))
+                })
+                .unwrap_or_default()
+                .first()
+                .map_or_else(String::new, |entry| {
+                    entry
+                        .command_line
+                        .get(line.len()..)
+                        .unwrap_or_default()
+                        .to_string()
+                })
+        } else {
+            String::new()
+        };
+
+        if use_ansi_coloring && !self.current_hint.is_empty() {
+            self.style.paint(&self.current_hint).to_string()
+        } else {
+            self.current_hint.clone()
+        }
+    }
+
+    fn complete_hint(&self) -> String {
+        self.current_hint.clone()
+    }
+
+    fn next_hint_token(&self) -> String {
+        get_first_token(&self.current_hint)
+    }
+}
+
+impl Default for FrecencyHinter {
+    fn default() -> Self {
+        FrecencyHinter {
+            style: Style::new().fg(Color::LightGray),
+            current_hint: String::new(),
+            min_chars: 1,
+        }
+    }
+}
+
+impl FrecencyHinter {
+    /// A builder that sets the style applied to the hint as part of the buffer
+    #[must_use]
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// A builder that sets the number of characters that have to be present to enable history hints
+    #[must_use]
+    pub fn with_min_chars(mut self, min_chars: usize) -> Self {
+        self.min_chars = min_chars;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FileBackedHistory, HistoryItem};
+
+    #[test]
+    fn suggests_the_most_frequent_match() {
+        let mut history = FileBackedHistory::default();
+        for command_line in ["cargo test", "cargo build", "cargo test", "cargo bench"] {
+            history
+                .save(HistoryItem::from_command_line(command_line))
+                .unwrap();
+        }
+
+        let mut hinter = FrecencyHinter::default();
+        assert_eq!(hinter.handle("cargo ", 6, &history, false, ""), "test");
+        assert_eq!(hinter.next_hint_token(), "test");
+        assert_eq!(hinter.handle("cargo b", 7, &history, false, ""), "ench");
+    }
+}
@@ -1,7 +1,9 @@
 mod cwd_aware;
 mod default;
+mod frecency;
 pub use cwd_aware::CwdAwareHinter;
 pub use default::DefaultHinter;
+pub use frecency::FrecencyHinter;
 
 use unicode_segmentation::UnicodeSegmentation;
 
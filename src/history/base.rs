@@ -1,6 +1,11 @@
-use super::HistoryItemId;
-use crate::{core_editor::LineBuffer, HistoryItem, HistorySessionId, Result};
+use super::{FrecencyContext, HistoryItemId, SearchPredicate};
+use crate::{
+    core_editor::LineBuffer,
+    result::{ReedlineError, ReedlineErrorVariants},
+    HistoryItem, HistorySessionId, Result,
+};
 use chrono::Utc;
+use regex::Regex;
 
 /// Browsing modes for a [`History`]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -17,6 +22,7 @@ pub enum HistoryNavigationQuery {
 
 /// Ways to search for a particular command line in the [`History`]
 // todo: merge with [HistoryNavigationQuery]
+#[derive(Clone)]
 pub enum CommandLineSearch {
     /// Command line starts with the same string
     Prefix(String),
@@ -26,6 +32,26 @@ pub enum CommandLineSearch {
     ///
     /// Useful to gather statistics
     Exact(String),
+    /// Command line contains the characters of the string in order, not necessarily
+    /// next to each other
+    ///
+    /// Uses smart case like [`crate::FuzzyMatcher`]: the search is case-insensitive unless
+    /// the string contains an uppercase character. Results are returned in the order given by
+    /// the [`SearchDirection`], not ranked by the quality of the match.
+    Fuzzy(String),
+    /// Command line contains all words of the string, compared as whole words ignoring case
+    ///
+    /// Words in double quotes have to appear next to each other as a phrase and a trailing `*`
+    /// also matches longer words, e.g. `"git commit" fix*`. If its full-text index is enabled
+    /// the `SqliteBackedHistory` uses it and returns the results ordered by relevance, the best
+    /// match first, instead of by the [`SearchDirection`].
+    FullText(String),
+    /// Command line contains a match of the regular expression
+    ///
+    /// Uses the syntax of the [`regex`] crate. The search is case-sensitive unless the
+    /// pattern starts with `(?i)`. Searching with an invalid pattern returns
+    /// [`ReedlineErrorVariants::InvalidRegex`](crate::ReedlineErrorVariants::InvalidRegex).
+    Regex(String),
 }
 
 /// Defines how to traverse the history when executing a [`SearchQuery`]
@@ -37,7 +63,34 @@ pub enum SearchDirection {
     Forward,
 }
 
+/// Defines the order of the results of a [`SearchQuery`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SearchRanking {
+    /// In the order of the [`SearchDirection`]
+    #[default]
+    Chronological,
+    /// The most useful command lines first, regardless of the [`SearchDirection`]
+    ///
+    /// Every distinct command line is returned once, as its most recent entry. The score rises
+    /// with how often and how recently the command line was run, and how often in the
+    /// context's directory and session. Failed runs lower it.
+    Frecency(FrecencyContext),
+}
+
+/// Compile the pattern of a [`CommandLineSearch::Regex`]
+pub(crate) fn search_regex(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).map_err(|err| {
+        // Syntax errors point at the error over several lines, keep only the description
+        let message = err.to_string();
+        let description = message.lines().last().unwrap_or_default();
+        ReedlineError(ReedlineErrorVariants::InvalidRegex(
+            description.trim_start_matches("error: ").to_string(),
+        ))
+    })
+}
+
 /// Defines additional filters for querying the [`History`]
+#[derive(Clone)]
 pub struct SearchFilter {
     /// Query for the command line content
     pub command_line: Option<CommandLineSearch>,
@@ -53,6 +106,18 @@ pub struct SearchFilter {
     pub exit_successful: Option<bool>,
     /// Filter on the session id
     pub session: Option<HistorySessionId>,
+    /// Only entries with the tag
+    pub tag: Option<String>,
+    /// Filter whether the entry is bookmarked
+    pub bookmarked: Option<bool>,
+    /// Additional conditions on the fields of the entry
+    pub predicate: Option<SearchPredicate>,
+    /// Only the most recent entry of every command line matching the other filters
+    ///
+    /// Whether an entry is the most recent one doesn't depend on the id and time bounds of the
+    /// [`SearchQuery`], so paging through the results never returns a command line twice.
+    /// Results ranked by [`SearchRanking::Frecency`] are always distinct.
+    pub distinct: bool,
 }
 
 impl SearchFilter {
@@ -88,11 +153,16 @@ impl SearchFilter {
             cwd_prefix: None,
             exit_successful: None,
             session,
+            tag: None,
+            bookmarked: None,
+            predicate: None,
+            distinct: false,
         }
     }
 }
 
 /// Query for search in the potentially rich [`History`]
+#[derive(Clone)]
 pub struct SearchQuery {
     /// Direction to search in
     pub direction: SearchDirection,
@@ -108,6 +178,8 @@ pub struct SearchQuery {
     pub limit: Option<i64>,
     /// Additional filters defined with [`SearchFilter`]
     pub filter: SearchFilter,
+    /// Order of the results
+    pub ranking: SearchRanking,
 }
 
 /// Currently `pub` ways to construct a query
@@ -122,6 +194,7 @@ impl SearchQuery {
             end_id: None,
             limit: None,
             filter: SearchFilter::from_text_search(CommandLineSearch::Substring(contains), None),
+            ranking: SearchRanking::Chronological,
         }
     }
 
@@ -135,6 +208,7 @@ impl SearchQuery {
             end_id: None,
             limit: Some(1),
             filter,
+            ranking: SearchRanking::Chronological,
         }
     }
 
@@ -156,6 +230,17 @@ impl SearchQuery {
         SearchQuery::last_with_search(SearchFilter::from_text_search_cwd(cwd, prefix, session))
     }
 
+    /// Get the highest ranked entry starting with the `prefix`, see [`SearchRanking::Frecency`]
+    ///
+    /// Like [`SearchQuery::last_with_prefix`] filtered on the session of the `context`.
+    pub fn frecent_with_prefix(prefix: String, context: FrecencyContext) -> SearchQuery {
+        let session = context.session;
+        SearchQuery {
+            ranking: SearchRanking::Frecency(context),
+            ..SearchQuery::last_with_prefix(prefix, session)
+        }
+    }
+
     /// Query to get all entries in the given [`SearchDirection`]
     pub fn everything(
         direction: SearchDirection,
@@ -169,6 +254,7 @@ impl SearchQuery {
             end_id: None,
             limit: None,
             filter: SearchFilter::anything(session),
+            ranking: SearchRanking::Chronological,
         }
     }
 }
@@ -191,6 +277,17 @@ pub trait History: Send {
     }
     /// return the results of a query
     fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>>;
+    /// return the results of a query lazily
+    ///
+    /// Chronological results are fetched in pages continuing after the id of the last result,
+    /// so only the consumed part of a large history is read. Results in another order are
+    /// fetched at once. The iteration ends after the first error.
+    fn search_iter(
+        &self,
+        query: SearchQuery,
+    ) -> Box<dyn Iterator<Item = Result<HistoryItem>> + '_> {
+        Box::new(SearchPages::new(self, query))
+    }
 
     /// update an item atomically
     fn update(
@@ -208,6 +305,87 @@ pub trait History: Send {
     fn session(&self) -> Option<HistorySessionId>;
 }
 
+/// Number of results [`History::search_iter`] fetches at once
+const SEARCH_PAGE_SIZE: i64 = 1000;
+
+/// Iterator over the results of a query, see [`History::search_iter`]
+pub(crate) struct SearchPages<'a, H: ?Sized> {
+    history: &'a H,
+    // Its limit is the number of results still to fetch
+    query: SearchQuery,
+    paged: bool,
+    page_size: i64,
+    page: std::vec::IntoIter<HistoryItem>,
+    done: bool,
+}
+
+impl<'a, H: History + ?Sized> SearchPages<'a, H> {
+    /// Pages through chronological results
+    pub(crate) fn new(history: &'a H, query: SearchQuery) -> Self {
+        let paged = query.ranking == SearchRanking::Chronological;
+        Self {
+            history,
+            query,
+            paged,
+            page_size: SEARCH_PAGE_SIZE,
+            page: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+
+    /// Fetches all results with a single search
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    pub(crate) fn unpaged(history: &'a H, query: SearchQuery) -> Self {
+        Self {
+            paged: false,
+            ..Self::new(history, query)
+        }
+    }
+}
+
+impl<H: History + ?Sized> Iterator for SearchPages<'_, H> {
+    type Item = Result<HistoryItem>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.page.next() {
+                return Some(Ok(item));
+            }
+            if self.done {
+                return None;
+            }
+            let mut query = self.query.clone();
+            let page_size =
+                (self.query.limit).map_or(self.page_size, |limit| limit.min(self.page_size));
+            if !self.paged {
+                self.done = true;
+            } else if page_size > 0 {
+                query.limit = Some(page_size);
+            } else {
+                self.done = true;
+                return None;
+            }
+
+            let items = match self.history.search(query) {
+                Ok(items) => items,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            };
+            if self.paged {
+                let fetched = items.len() as i64;
+                self.query.limit = self.query.limit.map(|limit| limit - fetched);
+                match items.last().and_then(|item| item.id) {
+                    Some(last_id) if fetched == page_size => self.query.start_id = Some(last_id),
+                    _ => self.done = true,
+                }
+            }
+            self.page = items.into_iter();
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
@@ -228,6 +406,9 @@ mod test {
             duration: Some(Duration::from_millis(1000)),
             exit_status: Some(exit_status),
             more_info: None,
+            tags: vec![],
+            note: None,
+            bookmarked: false,
         }
     }
     use std::time::Duration;
@@ -355,6 +536,54 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn search_iter_pages_through_results() -> Result<()> {
+        let history = create_filled_example_history()?;
+        let query = || SearchQuery {
+            limit: Some(7),
+            ..SearchQuery::everything(SearchDirection::Backward, None)
+        };
+        let pages = SearchPages {
+            page_size: 3,
+            ..SearchPages::new(&*history, query())
+        };
+        let res = pages.collect::<Result<Vec<_>>>()?;
+        search_returned(&*history, res, vec![12, 11, 10, 9, 8, 7, 6])?;
+
+        let res = history
+            .search_iter(SearchQuery::everything(SearchDirection::Forward, None))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            res,
+            history.search(SearchQuery::everything(SearchDirection::Forward, None))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn search_distinct_command_lines() -> Result<()> {
+        let mut history = create_filled_example_history()?;
+        history.save(create_item(1, "/", "ls", 0))?; // 13
+        let query = |start_id| {
+            let mut filter =
+                SearchFilter::from_text_search(CommandLineSearch::Prefix("ls".to_string()), None);
+            filter.distinct = true;
+            SearchQuery {
+                start_id,
+                filter,
+                ..SearchQuery::everything(SearchDirection::Backward, None)
+            }
+        };
+        let res = history.search(query(None))?;
+        search_returned(&*history, res, vec![13, 9, 6])?;
+        assert_eq!(history.count(query(None))?, 3);
+
+        // An older entry of a command line isn't returned after the latest one was paged past
+        let res = history.search(query(Some(HistoryItemId::new(13))))?;
+        search_returned(&*history, res, vec![9, 6])?;
+        Ok(())
+    }
+
     #[test]
     fn search_includes() -> Result<()> {
         let history = create_filled_example_history()?;
@@ -369,6 +598,144 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn search_fuzzy() -> Result<()> {
+        let history = create_filled_example_history()?;
+        let res = history.search(SearchQuery {
+            filter: SearchFilter::from_text_search(
+                CommandLineSearch::Fuzzy("ngcf".to_string()),
+                None,
+            ),
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        })?;
+        search_returned(&*history, res, vec![10, 12])?;
+        Ok(())
+    }
+
+    #[test]
+    fn search_fuzzy_uses_smart_case() -> Result<()> {
+        let history = create_filled_example_history()?;
+        let res = history.search(SearchQuery {
+            filter: SearchFilter::from_text_search(
+                CommandLineSearch::Fuzzy("NGcf".to_string()),
+                None,
+            ),
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        })?;
+        search_returned(&*history, res, vec![])?;
+        Ok(())
+    }
+
+    #[test]
+    fn search_full_text() -> Result<()> {
+        let history = create_filled_example_history()?;
+        let res = history.search(SearchQuery {
+            filter: SearchFilter::from_text_search(
+                CommandLineSearch::FullText("NGINX conf".to_string()),
+                None,
+            ),
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        })?;
+        search_returned(&*history, res, vec![10, 12])?;
+        Ok(())
+    }
+
+    #[test]
+    fn search_regex() -> Result<()> {
+        let history = create_filled_example_history()?;
+        let search = |pattern: &str| {
+            history.search(SearchQuery {
+                filter: SearchFilter::from_text_search(
+                    CommandLineSearch::Regex(pattern.to_string()),
+                    None,
+                ),
+                ..SearchQuery::everything(SearchDirection::Forward, None)
+            })
+        };
+        search_returned(&*history, search(r"^ls( -\w+)?$")?, vec![5, 6, 9])?;
+        search_returned(&*history, search("(?i)NGINX\\.conf")?, vec![10, 12])?;
+
+        assert!(matches!(
+            search("(ls"),
+            Err(ReedlineError(ReedlineErrorVariants::InvalidRegex(message)))
+                if message == "unclosed group"
+        ));
+        Ok(())
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn full_text_index_is_kept_in_sync_and_ranks_results() -> Result<()> {
+        let mut history = crate::SqliteBackedHistory::in_memory()?;
+        history.save(create_item(1, "/", "git log --oneline", 0))?; // 1
+        history.save(create_item(1, "/", "git commit -m 'log the log'", 0))?; // 2
+        history.enable_full_text_index()?;
+        assert!(history.has_full_text_index());
+        history.save(create_item(1, "/", "log", 0))?; // 3
+        history.save(create_item(1, "/", "git log -p", 0))?; // 4
+
+        let full_text = |text: &str| SearchQuery {
+            filter: SearchFilter::from_text_search(
+                CommandLineSearch::FullText(text.to_string()),
+                None,
+            ),
+            ..SearchQuery::everything(SearchDirection::Backward, None)
+        };
+        let res = history.search(full_text("log"))?;
+        search_returned(&history, res, vec![3, 2, 4, 1])?;
+        let res = history
+            .search_iter(full_text("log"))
+            .collect::<Result<_>>()?;
+        search_returned(&history, res, vec![3, 2, 4, 1])?;
+        let res = history.search(full_text("\"git log\" one*"))?;
+        search_returned(&history, res, vec![1])?;
+
+        history.delete(HistoryItemId::new(3))?;
+        history.update(HistoryItemId::new(1), &|e| HistoryItem {
+            command_line: "git status".to_string(),
+            ..e
+        })?;
+        let res = history.search(full_text("log"))?;
+        search_returned(&history, res, vec![2, 4])?;
+        assert_eq!(history.count(full_text("git"))?, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn search_by_frecency() -> Result<()> {
+        let mut history = create_filled_example_history()?;
+        history.save(create_item(1, "/etc/nginx", "vim nginx.conf", 0))?; // 13
+        let res = history.search(SearchQuery {
+            filter: SearchFilter::from_text_search(
+                CommandLineSearch::Prefix("vim".to_string()),
+                None,
+            ),
+            ranking: SearchRanking::Frecency(FrecencyContext::default()),
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        })?;
+        search_returned(&*history, res, vec![13, 11])?;
+        Ok(())
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn sqlite_ranks_by_frecency_like_the_other_histories() -> Result<()> {
+        let history = create_filled_example_history()?;
+        for cwd in ["/home/me", "/home/me/Downloads", "/etc/nginx"] {
+            let context = FrecencyContext::new(Some(cwd.to_string()), None);
+            let query = || SearchQuery {
+                ranking: SearchRanking::Frecency(context.clone()),
+                ..SearchQuery::everything(SearchDirection::Backward, None)
+            };
+            let newest_first =
+                history.search(SearchQuery::everything(SearchDirection::Backward, None))?;
+            let expected = crate::history::frecency::rank_by_frecency(newest_first, &context, None);
+            assert_eq!(history.search(query())?, expected);
+            assert_eq!(history.count(query())?, 12);
+        }
+        Ok(())
+    }
+
     #[test]
     fn search_includes_limit() -> Result<()> {
         let history = create_filled_example_history()?;
@@ -454,4 +821,36 @@ mod test {
         assert!(crate::FileBackedHistory::new(usize::MAX).is_err());
         assert!(crate::FileBackedHistory::new(HISTORY_SIZE).is_ok());
     }
+
+    #[test]
+    fn file_backed_history_rejects_tags_notes_and_bookmarks() -> Result<()> {
+        let mut history = crate::FileBackedHistory::new(10)?;
+        let item = HistoryItem::from_command_line("ls");
+        for item in [
+            HistoryItem {
+                tags: vec!["files".to_string()],
+                ..item.clone()
+            },
+            HistoryItem {
+                note: Some("list files".to_string()),
+                ..item.clone()
+            },
+            HistoryItem {
+                bookmarked: true,
+                ..item.clone()
+            },
+        ] {
+            assert!(matches!(
+                history.save(item),
+                Err(ReedlineError(
+                    ReedlineErrorVariants::HistoryFeatureUnsupported { .. }
+                ))
+            ));
+        }
+        assert_eq!(history.count_all()?, 0);
+
+        history.save(item)?;
+        assert_eq!(history.count_all()?, 1);
+        Ok(())
+    }
 }
@@ -3,7 +3,9 @@ use crate::{History, HistoryNavigationQuery, HistorySessionId};
 use super::base::CommandLineSearch;
 use super::base::SearchDirection;
 use super::base::SearchFilter;
+use super::base::SearchRanking;
 use super::HistoryItem;
+use super::HistoryItemId;
 use super::SearchQuery;
 use crate::Result;
 
@@ -76,6 +78,7 @@ impl HistoryCursor {
             direction,
             limit: Some(1),
             filter: self.get_search_filter(),
+            ranking: SearchRanking::Chronological,
         })?;
         if next.len() == 1 {
             self.current = Some(next.swap_remove(0));
@@ -86,11 +89,23 @@ impl HistoryCursor {
         Ok(())
     }
 
+    /// Places the cursor on the entry with the id, e.g. to continue navigating from an
+    /// entry that was recalled before
+    pub fn move_to(&mut self, history: &dyn History, id: HistoryItemId) -> Result<()> {
+        self.current = Some(history.load(id)?);
+        Ok(())
+    }
+
     /// Returns the string (if present) at the cursor
     pub fn string_at_cursor(&self) -> Option<String> {
         self.current.as_ref().map(|e| e.command_line.to_string())
     }
 
+    /// Returns the id (if present) of the entry at the cursor
+    pub fn id_at_cursor(&self) -> Option<HistoryItemId> {
+        self.current.as_ref().and_then(|e| e.id)
+    }
+
     /// Poll the current [`HistoryNavigationQuery`] mode
     pub fn get_navigation(&self) -> HistoryNavigationQuery {
         self.query.clone()
@@ -177,6 +192,19 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn navigation_continues_from_the_moved_to_entry() -> Result<()> {
+        let (mut hist, mut cursor) = create_history();
+        hist.save(HistoryItem::from_command_line("command1"))?;
+        let second = hist.save(HistoryItem::from_command_line("command2"))?;
+        hist.save(HistoryItem::from_command_line("command3"))?;
+        cursor.move_to(&*hist, second.id.unwrap())?;
+        assert_eq!(cursor.string_at_cursor(), Some("command2".to_string()));
+        cursor.forward(&*hist)?;
+        assert_eq!(cursor.string_at_cursor(), Some("command3".to_string()));
+        Ok(())
+    }
+
     #[test]
     fn going_forwards_bottoms_out() -> Result<()> {
         let (mut hist, mut cursor) = create_history();
@@ -1,13 +1,16 @@
 use super::{
-    base::CommandLineSearch, History, HistoryItem, HistoryItemId, SearchDirection, SearchQuery,
+    base::{search_regex, CommandLineSearch, SearchRanking},
+    frecency::rank_by_frecency,
+    full_text::FullTextQuery,
+    History, HistoryItem, HistoryItemId, SearchDirection, SearchQuery,
 };
 use crate::{
     result::{ReedlineError, ReedlineErrorVariants},
-    HistorySessionId, Result,
+    FuzzyMatcher, HistorySessionId, Result,
 };
 
 use std::{
-    collections::VecDeque,
+    collections::{HashSet, VecDeque},
     fs::OpenOptions,
     io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write},
     ops::{Deref, DerefMut},
@@ -24,6 +27,8 @@ pub const NEWLINE_ESCAPE: &str = "<\\n>";
 /// Similar to bash's behavior without HISTTIMEFORMAT.
 /// (See <https://www.gnu.org/software/bash/manual/html_node/Bash-History-Facilities.html>)
 /// If the history is associated to a file all new changes within a given history capacity will be written to disk when History is dropped.
+///
+/// Only the command lines are stored, so tags, notes and bookmarks need the SQLite or JSONL backed history.
 #[derive(Debug)]
 pub struct FileBackedHistory {
     capacity: usize,
@@ -59,7 +64,17 @@ fn decode_entry(s: &str) -> String {
 
 impl History for FileBackedHistory {
     /// only saves a value if it's different than the last value
+    ///
+    /// Fails for items with tags, a note or a bookmark as the file only stores command lines
     fn save(&mut self, h: HistoryItem) -> Result<HistoryItem> {
+        if !h.tags.is_empty() || h.note.is_some() || h.bookmarked {
+            return Err(ReedlineError(
+                ReedlineErrorVariants::HistoryFeatureUnsupported {
+                    history: "FileBackedHistory",
+                    feature: "tags, notes and bookmarks",
+                },
+            ));
+        }
         let entry = h.command_line;
         // Don't append if the preceding value is identical or the string empty
         let entry_id =
@@ -109,6 +124,9 @@ impl History for FileBackedHistory {
             || query.filter.cwd_exact.is_some()
             || query.filter.cwd_prefix.is_some()
             || query.filter.exit_successful.is_some()
+            || query.filter.tag.is_some()
+            || query.filter.bookmarked.is_some()
+            || query.filter.predicate.is_some()
         {
             return Err(ReedlineError(
                 ReedlineErrorVariants::HistoryFeatureUnsupported {
@@ -141,20 +159,44 @@ impl History for FileBackedHistory {
         } else {
             intrinsic_limit as usize
         };
-        let filter = |(idx, cmd): (usize, &String)| {
-            if !match &query.filter.command_line {
+        let regex = match &query.filter.command_line {
+            Some(CommandLineSearch::Regex(pattern)) => Some(search_regex(pattern)?),
+            _ => None,
+        };
+        let matches = |cmd: &String| {
+            let command_line_matches = match &query.filter.command_line {
                 Some(CommandLineSearch::Prefix(p)) => cmd.starts_with(p),
                 Some(CommandLineSearch::Substring(p)) => cmd.contains(p),
                 Some(CommandLineSearch::Exact(p)) => cmd == p,
+                Some(CommandLineSearch::Fuzzy(p)) => FuzzyMatcher::default().is_match(cmd, p),
+                Some(CommandLineSearch::FullText(p)) => FullTextQuery::parse(p).matches(cmd),
+                Some(CommandLineSearch::Regex(_)) => {
+                    regex.as_ref().map_or(false, |r| r.is_match(cmd))
+                }
                 None => true,
-            } {
+            };
+            command_line_matches && query.filter.not_command_line.as_ref() != Some(cmd)
+        };
+        // Index of the latest matching entry of every command line, regardless of the bounds
+        let latest: Option<HashSet<usize>> =
+            (query.filter.distinct && query.ranking == SearchRanking::Chronological).then(|| {
+                let mut seen = HashSet::new();
+                self.entries
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .filter(|(_, cmd)| matches(cmd) && seen.insert(*cmd))
+                    .map(|(idx, _)| idx)
+                    .collect()
+            });
+        let filter = |(idx, cmd): (usize, &String)| {
+            if !matches(cmd)
+                || latest
+                    .as_ref()
+                    .map_or(false, |latest| !latest.contains(&idx))
+            {
                 return None;
             }
-            if let Some(str) = &query.filter.not_command_line {
-                if cmd == str {
-                    return None;
-                }
-            }
             Some(FileBackedHistory::construct_entry(
                 Some(HistoryItemId::new(idx as i64)),
                 cmd.to_string(), // todo: this copy might be a perf bottleneck
@@ -167,6 +209,10 @@ impl History for FileBackedHistory {
             .enumerate()
             .skip(min_id as usize)
             .take(intrinsic_limit as usize);
+        if let SearchRanking::Frecency(context) = &query.ranking {
+            let newest_first = iter.rev().filter_map(filter);
+            return Ok(rank_by_frecency(newest_first, context, query.limit));
+        }
         if let SearchDirection::Backward = query.direction {
             Ok(iter.rev().filter_map(filter).take(limit).collect())
         } else {
@@ -335,6 +381,9 @@ impl FileBackedHistory {
             duration: None,
             exit_status: None,
             more_info: None,
+            tags: vec![],
+            note: None,
+            bookmarked: false,
         }
     }
 }
@@ -0,0 +1,177 @@
+use super::{HistoryItem, HistorySessionId};
+use std::collections::HashMap;
+
+/// What entries ranked by [`SearchRanking::Frecency`](super::SearchRanking::Frecency) are
+/// compared with: entries run in the same directory or session score higher
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrecencyContext {
+    /// The current working directory
+    pub cwd: Option<String>,
+    /// The current session
+    pub session: Option<HistorySessionId>,
+}
+
+impl FrecencyContext {
+    /// Create a context for the working directory and session
+    pub fn new(cwd: Option<String>, session: Option<HistorySessionId>) -> Self {
+        Self { cwd, session }
+    }
+}
+
+/// Number of newer entries after which the recency of a command line has halved
+const RECENCY_HALF_LIFE: f64 = 50.0;
+const FREQUENCY_WEIGHT: f64 = 1.0;
+const RECENCY_WEIGHT: f64 = 2.0;
+const CWD_WEIGHT: f64 = 1.0;
+const SESSION_WEIGHT: f64 = 0.5;
+const FAILURE_WEIGHT: f64 = 1.5;
+
+/// Aggregated statistics of all matching entries with the same command line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct FrecencyStats {
+    /// Number of entries
+    pub count: i64,
+    /// Number of newer matching entries than the latest one of the command line
+    pub age: i64,
+    /// Number of entries run in the context's directory
+    pub cwd_count: i64,
+    /// Number of entries run in the context's session
+    pub session_count: i64,
+    /// Number of entries with a successful exit status
+    pub successes: i64,
+    /// Number of entries with a known exit status
+    pub with_status: i64,
+}
+
+impl FrecencyStats {
+    fn add(&mut self, item: &HistoryItem, context: &FrecencyContext) {
+        self.count += 1;
+        if context.cwd.is_some() && item.cwd == context.cwd {
+            self.cwd_count += 1;
+        }
+        if context.session.is_some() && item.session_id == context.session {
+            self.session_count += 1;
+        }
+        if let Some(status) = item.exit_status {
+            self.with_status += 1;
+            if status == 0 {
+                self.successes += 1;
+            }
+        }
+    }
+
+    /// Higher is better. Shared by all histories so they rank the same way.
+    pub(crate) fn score(&self) -> f64 {
+        let frequency = (1.0 + self.count as f64).ln();
+        let recency = 0.5f64.powf(self.age as f64 / RECENCY_HALF_LIFE);
+        let cwd = (1.0 + self.cwd_count as f64).ln();
+        let session = (1.0 + self.session_count as f64).ln();
+        let failure_rate = if self.with_status > 0 {
+            (self.with_status - self.successes) as f64 / self.with_status as f64
+        } else {
+            0.0
+        };
+        FREQUENCY_WEIGHT * frequency
+            + RECENCY_WEIGHT * recency
+            + CWD_WEIGHT * cwd
+            + SESSION_WEIGHT * session
+            - FAILURE_WEIGHT * failure_rate
+    }
+}
+
+/// Rank the matching entries, given newest first, by frecency
+///
+/// Returns the latest entry of each distinct command line, the best first.
+pub(crate) fn rank_by_frecency(
+    newest_first: impl IntoIterator<Item = HistoryItem>,
+    context: &FrecencyContext,
+    limit: Option<i64>,
+) -> Vec<HistoryItem> {
+    let mut ranked: Vec<(HistoryItem, FrecencyStats)> = vec![];
+    let mut index_of_command: HashMap<String, usize> = HashMap::new();
+    for (age, item) in newest_first.into_iter().enumerate() {
+        let index = *index_of_command
+            .entry(item.command_line.clone())
+            .or_insert_with(|| {
+                let stats = FrecencyStats {
+                    age: age as i64,
+                    ..FrecencyStats::default()
+                };
+                ranked.push((item.clone(), stats));
+                ranked.len() - 1
+            });
+        ranked[index].1.add(&item, context);
+    }
+
+    let mut scored: Vec<(f64, HistoryItem)> = ranked
+        .into_iter()
+        .map(|(item, stats)| (stats.score(), item))
+        .collect();
+    // The sort is stable, so equally scored command lines stay newest first
+    scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    let limit = limit.map_or(usize::MAX, |limit| limit.max(0) as usize);
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, item)| item)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn item(cmd: &str, cwd: &str, exit_status: i64) -> HistoryItem {
+        HistoryItem {
+            cwd: Some(cwd.to_string()),
+            exit_status: Some(exit_status),
+            ..HistoryItem::from_command_line(cmd)
+        }
+    }
+
+    fn ranked(newest_first: Vec<HistoryItem>, context: &FrecencyContext) -> Vec<String> {
+        rank_by_frecency(newest_first, context, None)
+            .into_iter()
+            .map(|item| item.command_line)
+            .collect()
+    }
+
+    #[test]
+    fn frequent_commands_beat_recent_ones() {
+        let history = vec![
+            item("cargo bench", "/", 0),
+            item("cargo test", "/", 0),
+            item("cargo test", "/", 0),
+            item("cargo test", "/", 0),
+        ];
+        assert_eq!(
+            ranked(history, &FrecencyContext::default()),
+            ["cargo test", "cargo bench"]
+        );
+    }
+
+    #[test]
+    fn same_directory_and_success_rank_higher() {
+        let history = vec![
+            item("make", "/src/other", 0),
+            item("make install", "/src/project", 0),
+        ];
+        let context = FrecencyContext::new(Some("/src/project".to_string()), None);
+        assert_eq!(ranked(history, &context), ["make install", "make"]);
+
+        let history = vec![item("make", "/", 2), item("make install", "/", 0)];
+        assert_eq!(
+            ranked(history, &FrecencyContext::default()),
+            ["make install", "make"]
+        );
+    }
+
+    #[test]
+    fn ranks_distinct_command_lines_up_to_the_limit() {
+        let history = vec![item("b", "/", 0), item("a", "/", 0), item("b", "/", 0)];
+        let ranked = rank_by_frecency(history, &FrecencyContext::default(), Some(1));
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].command_line, "b");
+    }
+}
@@ -0,0 +1,128 @@
+/// A parsed [`CommandLineSearch::FullText`](super::CommandLineSearch::FullText) query
+///
+/// Words are runs of alphanumeric characters compared case-insensitively, like the
+/// `unicode61` tokenizer of SQLite's FTS5 used by the full-text index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FullTextQuery {
+    // Every phrase has to match, a phrase is a sequence of (word, is_prefix)
+    phrases: Vec<Vec<(String, bool)>>,
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+impl FullTextQuery {
+    pub(crate) fn parse(query: &str) -> Self {
+        let mut phrases = vec![];
+        let mut phrase: Vec<(String, bool)> = vec![];
+        let mut word = String::new();
+        let mut in_quotes = false;
+
+        for c in query.chars().chain(std::iter::once(' ')) {
+            if c.is_alphanumeric() {
+                word.extend(c.to_lowercase());
+                continue;
+            }
+            if !word.is_empty() {
+                phrase.push((std::mem::take(&mut word), c == '*'));
+            }
+            if c == '"' {
+                in_quotes = !in_quotes;
+            }
+            if !in_quotes && !phrase.is_empty() {
+                phrases.push(std::mem::take(&mut phrase));
+            }
+        }
+        // An unclosed quote ends with the query
+        if !phrase.is_empty() {
+            phrases.push(phrase);
+        }
+
+        // Only the last word of a phrase can be a prefix
+        for phrase in &mut phrases {
+            let last = phrase.len() - 1;
+            for (_, is_prefix) in &mut phrase[..last] {
+                *is_prefix = false;
+            }
+        }
+        Self { phrases }
+    }
+
+    /// A query without any words matches every command line
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.phrases.is_empty()
+    }
+
+    pub(crate) fn matches(&self, command_line: &str) -> bool {
+        let words = tokenize(command_line);
+        self.phrases.iter().all(|phrase| {
+            words.windows(phrase.len()).any(|window| {
+                window.iter().zip(phrase).all(|(word, (query, is_prefix))| {
+                    if *is_prefix {
+                        word.starts_with(query.as_str())
+                    } else {
+                        word == query
+                    }
+                })
+            })
+        })
+    }
+
+    /// The query in FTS5 syntax, quoting every phrase so the input can't inject operators
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    pub(crate) fn to_fts5(&self) -> String {
+        self.phrases
+            .iter()
+            .map(|phrase| {
+                let words: Vec<&str> = phrase.iter().map(|(word, _)| word.as_str()).collect();
+                let is_prefix = phrase.last().map_or(false, |(_, is_prefix)| *is_prefix);
+                format!(
+                    "\"{}\"{}",
+                    words.join(" "),
+                    if is_prefix { "*" } else { "" }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("commit", "git commit -m 'fix'", true)]
+    #[case("COMMIT", "git commit -m 'fix'", true)]
+    #[case("comm", "git commit -m 'fix'", false)]
+    #[case("comm*", "git commit -m 'fix'", true)]
+    #[case("fix git", "git commit -m 'fix'", true)]
+    #[case("fix push", "git commit -m 'fix'", false)]
+    #[case("\"git commit\"", "git commit -m 'fix'", true)]
+    #[case("\"commit git\"", "git commit -m 'fix'", false)]
+    #[case("\"commit m\" fix", "git commit -m 'fix'", true)]
+    #[case("\"commit -m", "git commit -m 'fix'", true)]
+    #[case("", "anything", true)]
+    fn matches_words_and_phrases(
+        #[case] query: &str,
+        #[case] command_line: &str,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(FullTextQuery::parse(query).matches(command_line), expected);
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn fts5_syntax_quotes_all_phrases() {
+        assert_eq!(
+            FullTextQuery::parse(r#"cargo "build --rel*" OR NEAR(x)"#).to_fts5(),
+            r#""cargo" "build rel"* "or" "near" "x""#
+        );
+    }
+}
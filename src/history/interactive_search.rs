@@ -0,0 +1,641 @@
+use {
+    super::{
+        base::search_regex, CommandLineSearch, History, HistoryItem, HistoryItemId,
+        HistorySessionId, SearchDirection, SearchFilter, SearchQuery, SearchRanking,
+    },
+    crate::{
+        enums::HistorySearchFilter, utils::text_manipulation, PromptHistorySearchStatus,
+        ReedlineError, ReedlineErrorVariants, Result, StyledText,
+    },
+    nu_ansi_term::{Color, Style},
+    regex::Regex,
+    std::ops::Range,
+    unicode_width::UnicodeWidthChar,
+};
+
+/// Number of distinct command lines fetched for one search
+const SEARCH_LIMIT: i64 = 1000;
+
+/// Information about the running session that the toggleable filters compare against
+pub(crate) struct HistorySearchContext {
+    pub session: Option<HistorySessionId>,
+    pub cwd: String,
+    pub hostname: Option<String>,
+}
+
+/// State of the interactive (`Ctrl-R`) history search
+///
+/// Keeps the search term, the active filters and the list of matching history items,
+/// most recent first and without duplicated command lines.
+#[derive(Default)]
+pub(crate) struct InteractiveHistorySearch {
+    term: String,
+    session: bool,
+    cwd: bool,
+    successful: bool,
+    host: bool,
+    bookmarked: bool,
+    regex: bool,
+    // The compiled term of a regex search, used to highlight the matches
+    pattern: Option<Regex>,
+    results: Vec<HistoryItem>,
+    selected: usize,
+    // Index of the first result shown in the list
+    scroll: usize,
+    error: Option<String>,
+}
+
+impl InteractiveHistorySearch {
+    /// Start a new search, restricted to the current session if `session` is set
+    pub fn new(session: bool) -> Self {
+        InteractiveHistorySearch {
+            session,
+            ..Default::default()
+        }
+    }
+
+    pub fn term(&self) -> &str {
+        &self.term
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        self.term.push(c);
+        self.reset_selection();
+    }
+
+    pub fn remove_last_grapheme(&mut self) {
+        let len = text_manipulation::remove_last_grapheme(&self.term).len();
+        self.term.truncate(len);
+        self.reset_selection();
+    }
+
+    pub fn toggle_filter(&mut self, filter: HistorySearchFilter) {
+        let flag = match filter {
+            HistorySearchFilter::Session => &mut self.session,
+            HistorySearchFilter::Cwd => &mut self.cwd,
+            HistorySearchFilter::Successful => &mut self.successful,
+            HistorySearchFilter::Host => &mut self.host,
+            HistorySearchFilter::Bookmarked => &mut self.bookmarked,
+            HistorySearchFilter::Regex => &mut self.regex,
+        };
+        *flag = !*flag;
+        self.reset_selection();
+    }
+
+    pub fn is_filter_active(&self, filter: HistorySearchFilter) -> bool {
+        match filter {
+            HistorySearchFilter::Session => self.session,
+            HistorySearchFilter::Cwd => self.cwd,
+            HistorySearchFilter::Successful => self.successful,
+            HistorySearchFilter::Host => self.host,
+            HistorySearchFilter::Bookmarked => self.bookmarked,
+            HistorySearchFilter::Regex => self.regex,
+        }
+    }
+
+    fn reset_selection(&mut self) {
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    /// Move the selection towards older entries
+    pub fn select_older(&mut self) {
+        if self.selected + 1 < self.results.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Move the selection towards more recent entries
+    pub fn select_newer(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn selected_item(&self) -> Option<&HistoryItem> {
+        self.results.get(self.selected)
+    }
+
+    pub fn status(&self) -> PromptHistorySearchStatus {
+        if self.error.is_some() || (!self.term.is_empty() && self.results.is_empty()) {
+            PromptHistorySearchStatus::Failing
+        } else {
+            PromptHistorySearchStatus::Passing
+        }
+    }
+
+    fn filter(&self, context: &HistorySearchContext) -> Result<SearchFilter> {
+        let mut filter = SearchFilter::anything(if self.session { context.session } else { None });
+        if self.regex {
+            filter.command_line = Some(CommandLineSearch::Regex(self.term.clone()));
+        } else if !self.term.is_empty() {
+            filter.command_line = Some(CommandLineSearch::Substring(self.term.clone()));
+        }
+        if self.cwd {
+            filter.cwd_exact = Some(context.cwd.clone());
+        }
+        if self.successful {
+            filter.exit_successful = Some(true);
+        }
+        if self.bookmarked {
+            filter.bookmarked = Some(true);
+        }
+        if self.host {
+            match &context.hostname {
+                Some(hostname) => filter.hostname = Some(hostname.clone()),
+                None => {
+                    return Err(ReedlineError(ReedlineErrorVariants::OtherHistoryError(
+                        "the hostname of this session is unknown",
+                    )))
+                }
+            }
+        }
+        Ok(filter)
+    }
+
+    /// Run the search again with the current term and filters
+    ///
+    /// Errors of the history (e.g. an unsupported filter) are kept and shown in the list.
+    pub fn refresh(&mut self, history: &dyn History, context: &HistorySearchContext) {
+        let results = self
+            .compile_pattern()
+            .and_then(|()| self.filter(context))
+            .and_then(|mut filter| {
+                filter.distinct = true;
+                history.search(backward_query(filter, Some(SEARCH_LIMIT)))
+            });
+        match results {
+            Ok(results) => {
+                self.results = results;
+                self.error = None;
+            }
+            Err(err) => {
+                self.results.clear();
+                self.error = Some(err.to_string());
+            }
+        }
+        self.selected = self.selected.min(self.results.len().saturating_sub(1));
+    }
+
+    /// Compile the term of a regex search once for highlighting every result
+    ///
+    /// An invalid pattern is reported even if the history doesn't support regex searches.
+    fn compile_pattern(&mut self) -> Result<()> {
+        self.pattern = None;
+        if self.regex {
+            self.pattern = Some(search_regex(&self.term)?);
+        }
+        Ok(())
+    }
+
+    /// Delete every history entry with the command line of the selected item
+    ///
+    /// Entries with the same command line are shown only once, so they are removed together.
+    pub fn delete_selected(&mut self, history: &mut dyn History, context: &HistorySearchContext) {
+        let Some(command_line) = self.selected_item().map(|item| item.command_line.clone()) else {
+            return;
+        };
+        let deleted = self.filter(context).and_then(|mut filter| {
+            filter.command_line = Some(CommandLineSearch::Exact(command_line));
+            let ids: Vec<HistoryItemId> = history
+                .search(backward_query(filter, None))?
+                .into_iter()
+                .filter_map(|item| item.id)
+                .collect();
+            ids.into_iter().try_for_each(|id| history.delete(id))
+        });
+        self.refresh(history, context);
+        if let Err(err) = deleted {
+            self.error = Some(err.to_string());
+        }
+    }
+
+    /// Bookmark the selected item or remove its bookmark
+    pub fn toggle_bookmark_selected(
+        &mut self,
+        history: &mut dyn History,
+        context: &HistorySearchContext,
+    ) {
+        let Some(id) = self.selected_item().and_then(|item| item.id) else {
+            return;
+        };
+        let toggled = history.update(id, &|item| HistoryItem {
+            bookmarked: !item.bookmarked,
+            ..item
+        });
+        self.refresh(history, context);
+        if let Err(err) = toggled {
+            self.error = Some(err.to_string());
+        }
+    }
+
+    /// Render the result list with at most `max_rows` entries followed by a status line
+    ///
+    /// Every line is prefixed with a newline so the list can be painted below the buffer.
+    pub fn render_list(
+        &mut self,
+        max_rows: usize,
+        width: usize,
+        use_ansi_coloring: bool,
+    ) -> String {
+        let max_rows = max_rows.max(1);
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + max_rows {
+            self.scroll = self.selected + 1 - max_rows;
+        }
+
+        let mut list = String::new();
+        for (index, item) in self
+            .results
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(max_rows)
+        {
+            let selected = index == self.selected;
+            let (marker, base_style) = match (selected, item.bookmarked) {
+                (true, false) => ("> ", Style::new().bold()),
+                (true, true) => (">*", Style::new().bold()),
+                (false, false) => ("  ", Style::new()),
+                (false, true) => (" *", Style::new()),
+            };
+            let entry = self.highlight_entry(
+                &item.command_line,
+                width.saturating_sub(marker.len()),
+                base_style,
+            );
+            list.push('\n');
+            if use_ansi_coloring {
+                list.push_str(&base_style.paint(marker).to_string());
+                list.push_str(&entry.render_simple());
+            } else {
+                list.push_str(marker);
+                list.push_str(&entry.raw_string());
+            }
+        }
+
+        let status = truncate_to_width(&self.status_line(), width);
+        list.push('\n');
+        if use_ansi_coloring {
+            list.push_str(&Style::new().fg(Color::DarkGray).paint(status).to_string());
+        } else {
+            list.push_str(&status);
+        }
+        list
+    }
+
+    fn status_line(&self) -> String {
+        if let Some(error) = &self.error {
+            return format!("  {error}");
+        }
+        let mut status = if self.results.is_empty() {
+            "  0/0".to_string()
+        } else {
+            format!("  {}/{}", self.selected + 1, self.results.len())
+        };
+        let filters: Vec<&str> = [
+            (HistorySearchFilter::Session, "session"),
+            (HistorySearchFilter::Cwd, "cwd"),
+            (HistorySearchFilter::Successful, "successful"),
+            (HistorySearchFilter::Host, "host"),
+            (HistorySearchFilter::Bookmarked, "bookmarked"),
+            (HistorySearchFilter::Regex, "regex"),
+        ]
+        .into_iter()
+        .filter(|(filter, _)| self.is_filter_active(*filter))
+        .map(|(_, name)| name)
+        .collect();
+        if !filters.is_empty() {
+            status.push_str(" [");
+            status.push_str(&filters.join(", "));
+            status.push(']');
+        }
+        status
+    }
+
+    /// Single line version of the entry truncated to `width` with the matches highlighted
+    fn highlight_entry(&self, command_line: &str, width: usize, base_style: Style) -> StyledText {
+        let matches = if let Some(pattern) = &self.pattern {
+            regex_match_ranges(command_line, pattern)
+        } else if self.regex {
+            vec![]
+        } else {
+            match_ranges(command_line, &self.term)
+        };
+        let match_style = base_style.fg(Color::Green);
+        let ellipsis_width = 1;
+
+        let mut styled = StyledText::new();
+        let mut used_width = 0;
+        let total_width: usize = command_line
+            .chars()
+            .map(|c| display_char(c).width().unwrap_or(0))
+            .sum();
+        let available = if total_width > width {
+            width.saturating_sub(ellipsis_width)
+        } else {
+            width
+        };
+        for (index, c) in command_line.char_indices() {
+            let c = display_char(c);
+            let char_width = c.width().unwrap_or(0);
+            if used_width + char_width > available {
+                styled.push((base_style, "…".to_string()));
+                break;
+            }
+            used_width += char_width;
+            let style = if matches.iter().any(|range| range.contains(&index)) {
+                match_style
+            } else {
+                base_style
+            };
+            match styled.buffer.last_mut() {
+                Some((last_style, text)) if *last_style == style => text.push(c),
+                _ => styled.push((style, c.to_string())),
+            }
+        }
+        styled
+    }
+}
+
+fn backward_query(filter: SearchFilter, limit: Option<i64>) -> SearchQuery {
+    SearchQuery {
+        direction: SearchDirection::Backward,
+        start_time: None,
+        end_time: None,
+        start_id: None,
+        end_id: None,
+        limit,
+        filter,
+        ranking: SearchRanking::Chronological,
+    }
+}
+
+/// Multi-line entries are shown on a single line
+fn display_char(c: char) -> char {
+    if c == '\n' || c == '\t' || c == '\r' {
+        ' '
+    } else {
+        c
+    }
+}
+
+/// Byte ranges of the occurrences of `term`
+///
+/// ASCII letters are compared case-insensitively, as some history backends match this way.
+fn match_ranges(command_line: &str, term: &str) -> Vec<Range<usize>> {
+    if term.is_empty() {
+        return vec![];
+    }
+    let haystack = command_line.to_ascii_lowercase();
+    let needle = term.to_ascii_lowercase();
+    haystack
+        .match_indices(&needle)
+        .map(|(start, found)| start..start + found.len())
+        .collect()
+}
+
+/// Byte ranges of the matches of the regular expression
+fn regex_match_ranges(command_line: &str, pattern: &Regex) -> Vec<Range<usize>> {
+    pattern
+        .find_iter(command_line)
+        .map(|found| found.range())
+        .filter(|range| !range.is_empty())
+        .collect()
+}
+
+fn truncate_to_width(line: &str, width: usize) -> String {
+    let mut used_width = 0;
+    line.chars()
+        .take_while(|c| {
+            used_width += c.width().unwrap_or(0);
+            used_width <= width
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileBackedHistory;
+    use pretty_assertions::assert_eq;
+
+    fn context() -> HistorySearchContext {
+        HistorySearchContext {
+            session: None,
+            cwd: "/home".to_string(),
+            hostname: None,
+        }
+    }
+
+    fn history_with(entries: &[&str]) -> FileBackedHistory {
+        let mut history = FileBackedHistory::default();
+        for entry in entries {
+            history
+                .save(HistoryItem::from_command_line(*entry))
+                .unwrap();
+        }
+        history
+    }
+
+    fn commands(search: &InteractiveHistorySearch) -> Vec<&str> {
+        search
+            .results
+            .iter()
+            .map(|item| item.command_line.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn lists_matches_most_recent_first_without_duplicates() {
+        let history = history_with(&["cargo build", "ls", "cargo test", "cargo build"]);
+        let mut search = InteractiveHistorySearch::new(false);
+        "cargo".chars().for_each(|c| search.push_char(c));
+        search.refresh(&history, &context());
+
+        assert_eq!(commands(&search), vec!["cargo build", "cargo test"]);
+        assert!(matches!(
+            search.status(),
+            PromptHistorySearchStatus::Passing
+        ));
+    }
+
+    #[test]
+    fn selection_stays_within_results() {
+        let history = history_with(&["a", "b", "c"]);
+        let mut search = InteractiveHistorySearch::new(false);
+        search.refresh(&history, &context());
+
+        search.select_newer();
+        assert_eq!(search.selected_item().unwrap().command_line, "c");
+        (0..5).for_each(|_| search.select_older());
+        assert_eq!(search.selected_item().unwrap().command_line, "a");
+        search.select_newer();
+        assert_eq!(search.selected_item().unwrap().command_line, "b");
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn bookmarks_the_selected_item() {
+        let mut history = crate::SqliteBackedHistory::in_memory().unwrap();
+        for entry in ["ls", "make", "pwd"] {
+            history.save(HistoryItem::from_command_line(entry)).unwrap();
+        }
+        let mut search = InteractiveHistorySearch::new(false);
+        search.refresh(&history, &context());
+        search.select_older();
+        search.toggle_bookmark_selected(&mut history, &context());
+
+        assert_eq!(
+            search.render_list(5, 20, false),
+            "\n  pwd\n>*make\n  ls\n  2/3"
+        );
+        search.toggle_filter(HistorySearchFilter::Bookmarked);
+        search.refresh(&history, &context());
+        assert_eq!(commands(&search), vec!["make"]);
+
+        search.toggle_bookmark_selected(&mut history, &context());
+        assert!(commands(&search).is_empty());
+    }
+
+    #[test]
+    fn bookmarking_shows_errors_of_the_history() {
+        let mut history = history_with(&["ls"]);
+        let mut search = InteractiveHistorySearch::new(false);
+        search.refresh(&history, &context());
+        search.toggle_bookmark_selected(&mut history, &context());
+
+        assert!(matches!(
+            search.status(),
+            PromptHistorySearchStatus::Failing
+        ));
+    }
+
+    #[test]
+    fn regex_search_highlights_matches_and_reports_invalid_patterns() {
+        let history = history_with(&["docker run -p 80:80 nginx", "docker run redis", "ls -p"]);
+        let mut search = InteractiveHistorySearch::new(false);
+        search.toggle_filter(HistorySearchFilter::Regex);
+        r"^docker run.* -p \d+"
+            .chars()
+            .for_each(|c| search.push_char(c));
+        search.refresh(&history, &context());
+        assert_eq!(commands(&search), vec!["docker run -p 80:80 nginx"]);
+        let pattern = search.pattern.as_ref().unwrap();
+        assert_eq!(
+            regex_match_ranges("docker run -p 80:80 nginx", pattern),
+            vec![0..16]
+        );
+
+        search.push_char('(');
+        search.refresh(&history, &context());
+        assert!(search.pattern.is_none());
+        assert!(matches!(
+            search.status(),
+            PromptHistorySearchStatus::Failing
+        ));
+        assert_eq!(
+            search.render_list(5, 80, false),
+            "\n  invalid regular expression: unclosed group"
+        );
+    }
+
+    #[test]
+    fn list_scrolls_to_keep_the_selection_visible() {
+        let history = history_with(&["one", "two", "three", "four"]);
+        let mut search = InteractiveHistorySearch::new(false);
+        search.refresh(&history, &context());
+        (0..3).for_each(|_| search.select_older());
+
+        assert_eq!(search.render_list(2, 20, false), "\n  two\n> one\n  4/4");
+    }
+
+    #[test]
+    fn long_and_multiline_entries_are_truncated() {
+        let history = history_with(&["echo a\necho bcdefgh"]);
+        let mut search = InteractiveHistorySearch::new(false);
+        search.refresh(&history, &context());
+
+        assert_eq!(search.render_list(5, 12, false), "\n> echo a ec…\n  1/1");
+    }
+
+    #[test]
+    fn matches_are_highlighted() {
+        let history = history_with(&["Git status"]);
+        let mut search = InteractiveHistorySearch::new(false);
+        "git".chars().for_each(|c| search.push_char(c));
+        search.refresh(&history, &context());
+
+        let entry = search.highlight_entry("Git status", 20, Style::new());
+        assert_eq!(
+            entry.buffer,
+            vec![
+                (Style::new().fg(Color::Green), "Git".to_string()),
+                (Style::new(), " status".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unsupported_filters_are_reported_in_the_status_line() {
+        let history = history_with(&["ls"]);
+        let mut search = InteractiveHistorySearch::new(false);
+        search.toggle_filter(HistorySearchFilter::Cwd);
+        search.refresh(&history, &context());
+
+        assert!(search.results.is_empty());
+        assert!(matches!(
+            search.status(),
+            PromptHistorySearchStatus::Failing
+        ));
+        assert_eq!(
+            search.render_list(5, 80, false),
+            "\n  the history FileBackedHistory does not support feature filtering by extra info"
+        );
+    }
+
+    #[test]
+    fn active_filters_are_listed() {
+        let mut search = InteractiveHistorySearch::new(true);
+        search.toggle_filter(HistorySearchFilter::Successful);
+
+        assert_eq!(
+            search.render_list(5, 80, false),
+            "\n  0/0 [session, successful]"
+        );
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn filters_and_deletes_with_sqlite_history() {
+        let mut history = crate::SqliteBackedHistory::in_memory().unwrap();
+        for (command_line, exit_status, cwd) in [
+            ("make", 0, "/home"),
+            ("make test", 2, "/home"),
+            ("make", 0, "/tmp"),
+            ("make install", 0, "/tmp"),
+        ] {
+            let mut item = HistoryItem::from_command_line(command_line);
+            item.exit_status = Some(exit_status);
+            item.cwd = Some(cwd.to_string());
+            history.save(item).unwrap();
+        }
+        let mut search = InteractiveHistorySearch::new(false);
+        search.refresh(&history, &context());
+        assert_eq!(commands(&search), vec!["make install", "make", "make test"]);
+
+        search.toggle_filter(HistorySearchFilter::Successful);
+        search.refresh(&history, &context());
+        assert_eq!(commands(&search), vec!["make install", "make"]);
+
+        search.toggle_filter(HistorySearchFilter::Cwd);
+        search.refresh(&history, &context());
+        assert_eq!(commands(&search), vec!["make"]);
+
+        // Only the entries matching the active filters are deleted
+        search.delete_selected(&mut history, &context());
+        assert!(search.selected_item().is_none());
+        search.toggle_filter(HistorySearchFilter::Cwd);
+        search.refresh(&history, &context());
+        assert_eq!(commands(&search), vec!["make install", "make"]);
+    }
+}
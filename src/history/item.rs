@@ -103,6 +103,15 @@ pub struct HistoryItem<ExtraInfo: HistoryItemExtraInfo = IgnoreAllExtraInfo> {
     ///       (see <https://github.com/serde-rs/serde/issues/1296#issuecomment-394056188> for the fix)
     #[serde(deserialize_with = "Option::<ExtraInfo>::deserialize")]
     pub more_info: Option<ExtraInfo>,
+    /// user defined tags to organize and filter the entries
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// a free-text note describing the command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// whether the user bookmarked the entry to find it again
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bookmarked: bool,
 }
 
 impl HistoryItem {
@@ -118,6 +127,9 @@ impl HistoryItem {
             duration: None,
             exit_status: None,
             more_info: None,
+            tags: vec![],
+            note: None,
+            bookmarked: false,
         }
     }
 }
@@ -158,6 +170,9 @@ mod tests {
                 mode: "shell".to_string(),
                 tags: vec!["test".to_string()],
             }),
+            tags: vec![],
+            note: None,
+            bookmarked: false,
         };
 
         assert_eq!(item.command_line, "echo hello");
@@ -181,6 +196,9 @@ mod tests {
                 mode: "r".to_string(),
                 tags: vec!["data".to_string(), "analysis".to_string()],
             }),
+            tags: vec![],
+            note: None,
+            bookmarked: false,
         };
 
         // Serialize to JSON
@@ -195,6 +213,26 @@ mod tests {
         assert_eq!(deserialized.more_info.as_ref().unwrap().mode, "r");
     }
 
+    #[test]
+    fn test_tags_notes_and_bookmarks_are_optional_in_json() {
+        let item: HistoryItem =
+            serde_json::from_str(r#"{"id":null,"start_timestamp":null,"command_line":"ls","session_id":null,"hostname":null,"cwd":null,"duration":null,"exit_status":null,"more_info":null}"#)
+                .expect("deserialization should succeed");
+        assert_eq!(item, HistoryItem::from_command_line("ls"));
+
+        let item = HistoryItem {
+            tags: vec!["files".to_string()],
+            note: Some("list files".to_string()),
+            bookmarked: true,
+            ..HistoryItem::from_command_line("ls")
+        };
+        let json = serde_json::to_string(&item).expect("serialization should succeed");
+        assert!(json.contains(r#""tags":["files"],"note":"list files","bookmarked":true"#));
+        let deserialized: HistoryItem =
+            serde_json::from_str(&json).expect("deserialization should succeed");
+        assert_eq!(deserialized, item);
+    }
+
     #[test]
     fn test_ignore_all_extra_info_serialization() {
         let item = HistoryItem::from_command_line("pwd");
@@ -0,0 +1,799 @@
+use super::{
+    base::{
+        search_regex, CommandLineSearch, SearchDirection, SearchFilter, SearchQuery, SearchRanking,
+    },
+    frecency::rank_by_frecency,
+    full_text::FullTextQuery,
+    History, HistoryItem, HistoryItemId, HistorySessionId, SearchPredicate,
+};
+use crate::{
+    result::{ReedlineError, ReedlineErrorVariants},
+    FuzzyMatcher, Result,
+};
+use chrono::Utc;
+use regex::Regex;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::OpenOptions,
+    io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write},
+    ops::{Deref, DerefMut},
+    path::PathBuf,
+    sync::atomic::{AtomicU8, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// The low bits of an id tell the instances apart, the bits above count the seconds since
+// `ID_EPOCH`. The ids stay below 2^57 until 2092, as required by a `LayeredHistory`.
+const ID_TAG_BITS: u32 = 26;
+const INSTANCE_BITS: u32 = 4;
+// 2024-01-01T00:00:00Z
+const ID_EPOCH: u64 = 1_704_067_200;
+// Numbers the instances within a process, the process id tells the processes apart
+static INSTANCE_COUNTER: AtomicU8 = AtomicU8::new(0);
+
+/// Tag of a new instance: the process id followed by its number within the process
+fn new_id_tag() -> i64 {
+    let process = i64::from(std::process::id()) & ((1 << (ID_TAG_BITS - INSTANCE_BITS)) - 1);
+    let instance = INSTANCE_COUNTER.fetch_add(1, Ordering::Relaxed) % (1 << INSTANCE_BITS);
+    (process << INSTANCE_BITS) | i64::from(instance)
+}
+
+/// A history that stores every field of its [`HistoryItem`]s as one JSON object per line.
+///
+/// In contrast to the [`FileBackedHistory`](crate::FileBackedHistory) it keeps the timestamps,
+/// working directory, hostname, session, duration and exit status, supports all
+/// [`SearchFilter`](crate::SearchFilter) fields and can update and delete entries, without
+/// requiring SQLite.
+///
+/// Changes are written on [`History::sync`] and when the history is dropped. Like the
+/// [`FileBackedHistory`](crate::FileBackedHistory) the file is locked while syncing and the
+/// entries written by other sessions in the meantime are merged into this one.
+/// Ids combine the time of saving with a tag unique to the history instance among the running
+/// processes of a machine, so entries of concurrent sessions stay in order and never get the
+/// same id. Up to 16 instances of a process get distinct tags.
+///
+/// ## Required feature:
+/// `jsonl`
+#[derive(Debug)]
+pub struct JsonlBackedHistory {
+    capacity: usize,
+    entries: BTreeMap<HistoryItemId, HistoryItem>,
+    // Saved (`Some`) or deleted (`None`) entries not yet written to the file
+    changes: BTreeMap<HistoryItemId, Option<HistoryItem>>,
+    ids_on_disk: HashSet<HistoryItemId>,
+    file: Option<PathBuf>,
+    session: Option<HistorySessionId>,
+    session_timestamp: Option<chrono::DateTime<Utc>>,
+    id_tag: i64,
+    // Highest id handed out or seen, ids are never reused
+    last_id: i64,
+}
+
+fn encode_item(item: &HistoryItem) -> std::io::Result<String> {
+    serde_json::to_string(item).map_err(std::io::Error::from)
+}
+
+fn decode_item(line: &str, line_number: usize) -> std::io::Result<HistoryItem> {
+    let item: HistoryItem = serde_json::from_str(line).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("invalid history entry in line {line_number}: {e}"),
+        )
+    })?;
+    if item.id.is_none() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("history entry in line {line_number} has no id"),
+        ));
+    }
+    Ok(item)
+}
+
+impl History for JsonlBackedHistory {
+    /// Saves a new entry, or replaces the entry if an existing id is given
+    fn save(&mut self, mut h: HistoryItem) -> Result<HistoryItem> {
+        let id = match h.id {
+            Some(id) => id,
+            None => self.next_id(),
+        };
+        h.id = Some(id);
+        if self.capacity > 0 {
+            self.entries.insert(id, h.clone());
+            self.changes.insert(id, Some(h.clone()));
+            self.truncate_to_capacity();
+        }
+        Ok(h)
+    }
+
+    fn load(&self, id: HistoryItemId) -> Result<HistoryItem> {
+        self.entries.get(&id).cloned().ok_or(ReedlineError(
+            ReedlineErrorVariants::OtherHistoryError("Item does not exist"),
+        ))
+    }
+
+    fn count(&self, query: SearchQuery) -> Result<i64> {
+        Ok(self.search(query)?.len() as i64)
+    }
+
+    fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>> {
+        let predicate = query.filter.predicate.as_ref();
+        if predicate.map_or(false, SearchPredicate::uses_more_info) {
+            return Err(ReedlineError(
+                ReedlineErrorVariants::HistoryFeatureUnsupported {
+                    history: "JsonlBackedHistory",
+                    feature: "querying extra info",
+                },
+            ));
+        }
+        let limit = query
+            .limit
+            .map_or(usize::MAX, |limit| limit.max(0) as usize);
+        let regex = match &query.filter.command_line {
+            Some(CommandLineSearch::Regex(pattern)) => Some(search_regex(pattern)?),
+            _ => None,
+        };
+        let matches_filter =
+            |item: &HistoryItem| self.matches_filter(&query.filter, regex.as_ref(), item);
+        let latest_ids: Option<HashSet<HistoryItemId>> =
+            (query.filter.distinct && query.ranking == SearchRanking::Chronological).then(|| {
+                let mut seen = HashSet::new();
+                self.entries
+                    .values()
+                    .rev()
+                    .filter(|item| matches_filter(item))
+                    .filter(|item| seen.insert(&item.command_line))
+                    .filter_map(|item| item.id)
+                    .collect()
+            });
+        let matching = |item: &&HistoryItem| {
+            Self::in_bounds(&query, item)
+                && matches_filter(item)
+                && (latest_ids.as_ref())
+                    .map_or(true, |ids| item.id.map_or(false, |id| ids.contains(&id)))
+        };
+        if let SearchRanking::Frecency(context) = &query.ranking {
+            let newest_first = self.entries.values().rev().filter(matching).cloned();
+            return Ok(rank_by_frecency(newest_first, context, query.limit));
+        }
+        let results = match query.direction {
+            SearchDirection::Backward => self
+                .entries
+                .values()
+                .rev()
+                .filter(matching)
+                .take(limit)
+                .cloned()
+                .collect(),
+            SearchDirection::Forward => self
+                .entries
+                .values()
+                .filter(matching)
+                .take(limit)
+                .cloned()
+                .collect(),
+        };
+        Ok(results)
+    }
+
+    fn update(
+        &mut self,
+        id: HistoryItemId,
+        updater: &dyn Fn(HistoryItem) -> HistoryItem,
+    ) -> Result<()> {
+        let item = updater(self.load(id)?);
+        self.save(HistoryItem {
+            id: Some(id),
+            ..item
+        })?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.entries.clear();
+        self.changes.clear();
+        self.ids_on_disk.clear();
+
+        if let Some(file) = &self.file {
+            match std::fs::remove_file(file) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(ReedlineError(ReedlineErrorVariants::IOError(err)));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn delete(&mut self, h: HistoryItemId) -> Result<()> {
+        if self.entries.remove(&h).is_none() {
+            return Err(ReedlineError(ReedlineErrorVariants::OtherHistoryError(
+                "Item does not exist",
+            )));
+        }
+        self.changes.insert(h, None);
+        Ok(())
+    }
+
+    /// Merges the entries written by other sessions and writes the own changes to disk.
+    ///
+    /// New entries are appended, the file is only rewritten to apply updates, deletions or
+    /// to drop the oldest entries beyond `capacity`.
+    fn sync(&mut self) -> std::io::Result<()> {
+        let Some(fname) = &self.file else {
+            return Ok(());
+        };
+        if let Some(base_dir) = fname.parent() {
+            std::fs::create_dir_all(base_dir)?;
+        }
+
+        let mut f_lock = fd_lock::RwLock::new(
+            OpenOptions::new()
+                .create(true)
+                .write(true)
+                .read(true)
+                .truncate(false)
+                .open(fname)?,
+        );
+        let mut writer_guard = f_lock.write()?;
+        let mut merged = BTreeMap::new();
+        {
+            let reader = BufReader::new(writer_guard.deref());
+            for (index, line) in reader.lines().enumerate() {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    let item = decode_item(&line, index + 1)?;
+                    merged.insert(item.id.unwrap_or(HistoryItemId::new(0)), item);
+                }
+            }
+        }
+
+        let mut rewrite = false;
+        let mut appended = Vec::new();
+        for (id, change) in std::mem::take(&mut self.changes) {
+            match change {
+                // Another session deleted the entry in the meantime, don't bring it back
+                Some(_) if self.ids_on_disk.contains(&id) && !merged.contains_key(&id) => {}
+                Some(item) => {
+                    if merged.contains_key(&id) {
+                        rewrite = true;
+                    } else {
+                        appended.push(item.clone());
+                    }
+                    merged.insert(id, item);
+                }
+                None => rewrite |= merged.remove(&id).is_some(),
+            }
+        }
+        while merged.len() > self.capacity {
+            rewrite = true;
+            if let Some(oldest) = merged.keys().next().copied() {
+                merged.remove(&oldest);
+            }
+        }
+
+        {
+            let mut writer = BufWriter::new(writer_guard.deref_mut());
+            let lines: Vec<&HistoryItem> = if rewrite {
+                writer.rewind()?;
+                merged.values().collect()
+            } else {
+                writer.seek(SeekFrom::End(0))?;
+                appended.iter().collect()
+            };
+            for item in lines {
+                writer.write_all(encode_item(item)?.as_bytes())?;
+                writer.write_all("\n".as_bytes())?;
+            }
+            writer.flush()?;
+        }
+        if rewrite {
+            let file = writer_guard.deref_mut();
+            let file_len = file.stream_position()?;
+            file.set_len(file_len)?;
+        }
+
+        self.ids_on_disk = merged.keys().copied().collect();
+        if let Some(last_id) = merged.keys().next_back() {
+            self.last_id = self.last_id.max(last_id.0);
+        }
+        self.entries = merged;
+        Ok(())
+    }
+
+    fn session(&self) -> Option<HistorySessionId> {
+        self.session
+    }
+}
+
+impl JsonlBackedHistory {
+    /// Creates a new in-memory history that remembers `n <= capacity` elements
+    pub fn new(capacity: usize) -> Self {
+        JsonlBackedHistory {
+            capacity,
+            entries: BTreeMap::new(),
+            changes: BTreeMap::new(),
+            ids_on_disk: HashSet::new(),
+            file: None,
+            session: None,
+            session_timestamp: None,
+            id_tag: new_id_tag(),
+            last_id: 0,
+        }
+    }
+
+    /// Creates a new history with an associated JSON-lines file.
+    ///
+    /// If the file exists its entries are loaded, otherwise an empty file is created.
+    /// `session` and `session_timestamp` behave like for the `SqliteBackedHistory`: session
+    /// filtered searches return the entries of this session and those saved before it started.
+    /// Without a `session_timestamp` they only return the entries of this session.
+    ///
+    /// **Side effects:** creates all nested directories to the file
+    pub fn with_file(
+        capacity: usize,
+        file: PathBuf,
+        session: Option<HistorySessionId>,
+        session_timestamp: Option<chrono::DateTime<Utc>>,
+    ) -> Result<Self> {
+        let mut hist = Self::new(capacity);
+        hist.file = Some(file);
+        hist.session = session;
+        hist.session_timestamp = session_timestamp;
+        hist.sync()?;
+        Ok(hist)
+    }
+
+    /// Id of a new entry: the seconds since `ID_EPOCH` followed by the tag of this instance,
+    /// but always above the ids handed out or seen so far
+    fn next_id(&mut self) -> HistoryItemId {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| {
+                elapsed.as_secs().saturating_sub(ID_EPOCH) as i64
+            });
+        let seconds = now.max((self.last_id >> ID_TAG_BITS) + 1);
+        self.last_id = (seconds << ID_TAG_BITS) | self.id_tag;
+        HistoryItemId::new(self.last_id)
+    }
+
+    fn truncate_to_capacity(&mut self) {
+        while self.entries.len() > self.capacity {
+            if let Some(oldest) = self.entries.keys().next().copied() {
+                self.entries.remove(&oldest);
+                // Entries only known to this session never have to be written
+                if !self.ids_on_disk.contains(&oldest) {
+                    self.changes.remove(&oldest);
+                }
+            }
+        }
+    }
+
+    /// Whether the item is within the id and time bounds of the query
+    fn in_bounds(query: &SearchQuery, item: &HistoryItem) -> bool {
+        let is_forward = query.direction == SearchDirection::Forward;
+        let id = item.id.unwrap_or(HistoryItemId::new(0));
+
+        if let Some(start) = query.start_id {
+            if (is_forward && id <= start) || (!is_forward && id >= start) {
+                return false;
+            }
+        }
+        if let Some(end) = query.end_id {
+            if (is_forward && id > end) || (!is_forward && id < end) {
+                return false;
+            }
+        }
+        if query.start_time.is_some() || query.end_time.is_some() {
+            let Some(timestamp) = item.start_timestamp else {
+                return false;
+            };
+            if let Some(start) = query.start_time {
+                if (is_forward && timestamp <= start) || (!is_forward && timestamp >= start) {
+                    return false;
+                }
+            }
+            if let Some(end) = query.end_time {
+                if (is_forward && timestamp > end) || (!is_forward && timestamp < end) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    fn matches_filter(
+        &self,
+        filter: &SearchFilter,
+        regex: Option<&Regex>,
+        item: &HistoryItem,
+    ) -> bool {
+        let cmd = &item.command_line;
+        let command_line_matches = match &filter.command_line {
+            Some(CommandLineSearch::Prefix(p)) => cmd.starts_with(p),
+            Some(CommandLineSearch::Substring(p)) => cmd.contains(p),
+            Some(CommandLineSearch::Exact(p)) => cmd == p,
+            Some(CommandLineSearch::Fuzzy(p)) => FuzzyMatcher::default().is_match(cmd, p),
+            Some(CommandLineSearch::FullText(p)) => FullTextQuery::parse(p).matches(cmd),
+            Some(CommandLineSearch::Regex(_)) => regex.map_or(false, |r| r.is_match(cmd)),
+            None => true,
+        };
+        if !command_line_matches || filter.not_command_line.as_ref() == Some(cmd) {
+            return false;
+        }
+        if filter.hostname.is_some() && item.hostname != filter.hostname {
+            return false;
+        }
+        if filter.cwd_exact.is_some() && item.cwd != filter.cwd_exact {
+            return false;
+        }
+        if let Some(cwd_prefix) = &filter.cwd_prefix {
+            if !item
+                .cwd
+                .as_ref()
+                .map_or(false, |cwd| cwd.starts_with(cwd_prefix))
+            {
+                return false;
+            }
+        }
+        if let Some(exit_successful) = filter.exit_successful {
+            match item.exit_status {
+                Some(status) if (status == 0) == exit_successful => {}
+                _ => return false,
+            }
+        }
+        if let Some(predicate) = &filter.predicate {
+            if !predicate.matches(item) {
+                return false;
+            }
+        }
+        if let Some(tag) = &filter.tag {
+            if !item.tags.contains(tag) {
+                return false;
+            }
+        }
+        if filter
+            .bookmarked
+            .map_or(false, |bookmarked| item.bookmarked != bookmarked)
+        {
+            return false;
+        }
+        if let Some(session) = filter.session {
+            let before_session = self.session_timestamp.map_or(false, |session_timestamp| {
+                item.start_timestamp
+                    .map_or(false, |timestamp| timestamp < session_timestamp)
+            });
+            if item.session_id != Some(session) && !before_session {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl Drop for JsonlBackedHistory {
+    /// On drop the changes will be written to the file if specified via [`JsonlBackedHistory::with_file()`].
+    fn drop(&mut self) {
+        let _res = self.sync();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SearchFilter;
+    use chrono::TimeZone;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    fn item(session: i64, cwd: &str, cmd: &str, exit_status: i64, timestamp: i64) -> HistoryItem {
+        HistoryItem {
+            id: None,
+            start_timestamp: Utc.timestamp_opt(timestamp, 0).single(),
+            command_line: cmd.to_string(),
+            session_id: Some(HistorySessionId::new(session)),
+            hostname: Some("foohost".to_string()),
+            cwd: Some(cwd.to_string()),
+            duration: Some(Duration::from_millis(1500)),
+            exit_status: Some(exit_status),
+            more_info: None,
+            tags: vec![],
+            note: None,
+            bookmarked: false,
+        }
+    }
+
+    fn commands(items: Vec<HistoryItem>) -> Vec<String> {
+        items.into_iter().map(|item| item.command_line).collect()
+    }
+
+    fn search(history: &JsonlBackedHistory, filter: SearchFilter) -> Vec<String> {
+        let query = SearchQuery {
+            filter,
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        };
+        commands(history.search(query).unwrap())
+    }
+
+    #[test]
+    fn persists_all_fields() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("history.jsonl");
+
+        let mut history = JsonlBackedHistory::with_file(100, file.clone(), None, None)?;
+        let saved = history.save(HistoryItem {
+            tags: vec!["files".to_string()],
+            note: Some("long listing".to_string()),
+            bookmarked: true,
+            ..item(1, "/home/me", "ls -l", 0, 1_700_000_000)
+        })?;
+        drop(history);
+
+        let history = JsonlBackedHistory::with_file(100, file, None, None)?;
+        assert_eq!(history.load(saved.id.unwrap())?, saved);
+        assert_eq!(history.count_all()?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn filters_on_all_fields() -> Result<()> {
+        let mut history = JsonlBackedHistory::new(100);
+        history.save(item(1, "/home/me", "cd Downloads", 0, 100))?;
+        history.save(item(1, "/home/me/Downloads", "unzp foo.zip", 1, 200))?;
+        history.save(item(2, "/etc", "cat hosts", 0, 300))?;
+
+        let mut filter = SearchFilter::anything(None);
+        filter.cwd_prefix = Some("/home/me".to_string());
+        assert_eq!(search(&history, filter), ["cd Downloads", "unzp foo.zip"]);
+
+        let mut filter = SearchFilter::anything(None);
+        filter.exit_successful = Some(false);
+        assert_eq!(search(&history, filter), ["unzp foo.zip"]);
+
+        let mut filter = SearchFilter::anything(None);
+        filter.cwd_exact = Some("/etc".to_string());
+        filter.hostname = Some("foohost".to_string());
+        assert_eq!(search(&history, filter), ["cat hosts"]);
+
+        let query = SearchQuery {
+            start_time: Utc.timestamp_opt(100, 0).single(),
+            end_time: Utc.timestamp_opt(200, 0).single(),
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        };
+        assert_eq!(commands(history.search(query)?), ["unzp foo.zip"]);
+
+        let query = SearchQuery::last_with_prefix("c".to_string(), None);
+        assert_eq!(commands(history.search(query)?), ["cat hosts"]);
+
+        let filter =
+            SearchFilter::from_text_search(CommandLineSearch::Regex("^un?zi?p ".to_string()), None);
+        assert_eq!(search(&history, filter), ["unzp foo.zip"]);
+        Ok(())
+    }
+
+    #[test]
+    fn distinct_keeps_the_latest_matching_entry() -> Result<()> {
+        let mut history = JsonlBackedHistory::new(100);
+        history.save(item(1, "/a", "make", 0, 100))?;
+        history.save(item(1, "/a", "ls", 0, 200))?;
+        history.save(item(1, "/b", "make", 0, 300))?;
+
+        let mut filter = SearchFilter::anything(None);
+        filter.distinct = true;
+        assert_eq!(search(&history, filter), ["ls", "make"]);
+
+        let mut filter = SearchFilter::anything(None);
+        filter.cwd_exact = Some("/a".to_string());
+        filter.distinct = true;
+        assert_eq!(search(&history, filter), ["make", "ls"]);
+        Ok(())
+    }
+
+    #[test]
+    fn filters_on_tags_and_bookmarks() -> Result<()> {
+        let mut history = JsonlBackedHistory::new(100);
+        history.save(HistoryItem {
+            tags: vec!["net".to_string(), "debug".to_string()],
+            ..item(1, "/", "ping localhost", 0, 100)
+        })?;
+        history.save(HistoryItem {
+            bookmarked: true,
+            ..item(1, "/", "cargo test", 0, 200)
+        })?;
+        history.save(item(1, "/", "ls", 0, 300))?;
+
+        let mut filter = SearchFilter::anything(None);
+        filter.tag = Some("debug".to_string());
+        assert_eq!(search(&history, filter), ["ping localhost"]);
+
+        let mut filter = SearchFilter::anything(None);
+        filter.bookmarked = Some(true);
+        assert_eq!(search(&history, filter), ["cargo test"]);
+
+        let mut filter = SearchFilter::anything(None);
+        filter.bookmarked = Some(false);
+        assert_eq!(search(&history, filter), ["ping localhost", "ls"]);
+        Ok(())
+    }
+
+    #[test]
+    fn filters_by_predicates() -> Result<()> {
+        let mut history = JsonlBackedHistory::new(100);
+        history.save(item(1, "/home/me", "cargo build", 101, 100))?;
+        history.save(item(1, "/home/me/project", "cargo test", 0, 200))?;
+        history.save(item(1, "/etc", "cat hosts", 1, 300))?;
+
+        let mut filter = SearchFilter::anything(None);
+        filter.predicate = Some(
+            SearchPredicate::ExitStatus(100..=127)
+                .or(SearchPredicate::CwdGlob("*/project".to_string())),
+        );
+        assert_eq!(search(&history, filter), ["cargo build", "cargo test"]);
+
+        let mut filter = SearchFilter::anything(None);
+        filter.predicate = Some(!SearchPredicate::MoreInfo {
+            path: "$.mode".to_string(),
+            value: "1".to_string(),
+        });
+        let query = SearchQuery {
+            filter,
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        };
+        assert!(matches!(
+            history.search(query),
+            Err(ReedlineError(
+                ReedlineErrorVariants::HistoryFeatureUnsupported { .. }
+            ))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn filters_on_session() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("history.jsonl");
+        let session = Some(HistorySessionId::new(2));
+        let started = Utc.timestamp_opt(150, 0).single();
+        let mut history = JsonlBackedHistory::with_file(100, file, session, started)?;
+        history.save(item(1, "/", "before", 0, 100))?;
+        history.save(item(1, "/", "other session", 0, 200))?;
+        history.save(item(2, "/", "own session", 0, 300))?;
+
+        assert_eq!(
+            search(&history, SearchFilter::anything(session)),
+            ["before", "own session"]
+        );
+
+        // Without the start of the session only its own entries are returned
+        let file = dir.path().join("without_timestamp.jsonl");
+        let mut history = JsonlBackedHistory::with_file(100, file, session, None)?;
+        history.save(item(1, "/", "other session", 0, 100))?;
+        history.save(item(2, "/", "own session", 0, 200))?;
+        assert_eq!(
+            search(&history, SearchFilter::anything(session)),
+            ["own session"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn updates_and_deletes_are_written() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("history.jsonl");
+
+        let mut history = JsonlBackedHistory::with_file(100, file.clone(), None, None)?;
+        let first = history.save(item(1, "/", "first", 0, 100))?;
+        let second = history.save(item(1, "/", "second", 0, 200))?;
+        history.sync()?;
+        history.update(first.id.unwrap(), &|mut item| {
+            item.exit_status = Some(127);
+            item
+        })?;
+        history.delete(second.id.unwrap())?;
+        drop(history);
+
+        let history = JsonlBackedHistory::with_file(100, file.clone(), None, None)?;
+        assert_eq!(history.load(first.id.unwrap())?.exit_status, Some(127));
+        assert!(history.load(second.id.unwrap()).is_err());
+        assert_eq!(std::fs::read_to_string(file)?.lines().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn merges_concurrent_sessions() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("history.jsonl");
+
+        let mut one = JsonlBackedHistory::with_file(3, file.clone(), None, None)?;
+        let mut two = JsonlBackedHistory::with_file(3, file.clone(), None, None)?;
+        one.save(item(1, "/", "one a", 0, 100))?;
+        two.save(item(2, "/", "two a", 0, 100))?;
+        one.save(item(1, "/", "one b", 0, 100))?;
+        two.save(item(2, "/", "two b", 0, 100))?;
+        one.sync()?;
+        two.sync()?;
+
+        // One of the oldest entries is dropped to stay within the capacity. Which one depends
+        // on the order of the sessions, which decides between entries of the same second.
+        let all = SearchQuery::everything(SearchDirection::Forward, None);
+        let merged = commands(two.search(all)?);
+        assert_eq!(merged.len(), 3);
+        assert!(merged[..1] == ["one a"] || merged[..1] == ["two a"]);
+        assert!(merged[1..] == ["one b", "two b"] || merged[1..] == ["two b", "one b"]);
+
+        one.sync()?;
+        let all = SearchQuery::everything(SearchDirection::Forward, None);
+        assert_eq!(commands(one.search(all)?), merged);
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_sessions_update_and_delete_their_own_entries() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("history.jsonl");
+
+        let mut one = JsonlBackedHistory::with_file(100, file.clone(), None, None)?;
+        let mut two = JsonlBackedHistory::with_file(100, file.clone(), None, None)?;
+        let from_one = one.save(item(1, "/", "from one", 0, 100))?.id.unwrap();
+        let from_two = two.save(item(2, "/", "from two", 0, 100))?.id.unwrap();
+        let also_from_two = two.save(item(2, "/", "also from two", 0, 100))?.id.unwrap();
+        assert_ne!(from_one, from_two);
+        assert_ne!(from_one, also_from_two);
+        one.sync()?;
+        two.sync()?;
+        one.sync()?;
+
+        // The ids held before syncing still refer to the own entries
+        one.update(from_one, &|mut item| {
+            item.exit_status = Some(127);
+            item
+        })?;
+        two.delete(from_two)?;
+        one.sync()?;
+        two.sync()?;
+        one.sync()?;
+
+        for history in [&one, &two] {
+            let all = SearchQuery::everything(SearchDirection::Forward, None);
+            assert_eq!(
+                commands(history.search(all)?),
+                ["from one", "also from two"]
+            );
+            assert_eq!(history.load(from_one)?.exit_status, Some(127));
+            assert_eq!(history.load(also_from_two)?.exit_status, Some(0));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn doesnt_bring_back_entries_deleted_by_another_session() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("history.jsonl");
+
+        let mut one = JsonlBackedHistory::with_file(100, file.clone(), None, None)?;
+        let id = one.save(item(1, "/", "ls", 0, 100))?.id.unwrap();
+        one.sync()?;
+        let mut two = JsonlBackedHistory::with_file(100, file.clone(), None, None)?;
+        one.delete(id)?;
+        one.sync()?;
+
+        two.update(id, &|mut item| {
+            item.bookmarked = true;
+            item
+        })?;
+        two.sync()?;
+
+        assert!(two.load(id).is_err());
+        assert_eq!(std::fs::read_to_string(file)?.lines().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn reports_corrupt_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("history.jsonl");
+        std::fs::write(&file, "not json\n").unwrap();
+
+        assert!(JsonlBackedHistory::with_file(100, file, None, None).is_err());
+    }
+}
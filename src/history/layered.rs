@@ -0,0 +1,512 @@
+use super::{
+    base::SearchRanking, History, HistoryItem, HistoryItemId, HistorySessionId, SearchDirection,
+    SearchFilter, SearchQuery,
+};
+use crate::{
+    result::{ReedlineError, ReedlineErrorVariants},
+    Result,
+};
+use std::collections::HashSet;
+
+/// Maximum number of layers of a [`LayeredHistory`]
+const MAX_LAYERS: usize = 64;
+/// Every layer owns a range of this many ids, the ids of its entries have to be below it
+const LAYER_ID_STRIDE: i64 = 1 << 57;
+
+/// A history combining several [`History`]s, e.g. a per-project history shown before the
+/// global one and a read-only history of shared commands.
+///
+/// The layers are ordered by priority, the first one added has the highest. Searches return
+/// the entries of a layer before those of the lower priority layers: going back through the
+/// history lists every entry of the first layer, newest first, before the second layer.
+/// New entries are saved to the writable layer, the other layers are read-only.
+///
+/// The ids of the entries are mapped so they identify the layer as well, a layer's ids have
+/// to be below 2^57. Distinct and frecency ranked searches skip the command lines that match
+/// in a higher priority layer, for which they read all of its matching command lines.
+/// Frecency ranked searches rank the entries of every layer on their own, the layers are still
+/// listed by priority.
+///
+/// # Example
+/// ```rust
+/// use reedline::{FileBackedHistory, LayeredHistory};
+///
+/// let project = FileBackedHistory::new(100).unwrap();
+/// let global = FileBackedHistory::new(1000).unwrap();
+/// let runbook = FileBackedHistory::new(100).unwrap();
+///
+/// let history = LayeredHistory::new()
+///     .with_layer(Box::new(project))
+///     .with_writable_layer(Box::new(global))
+///     .with_layer(Box::new(runbook));
+/// ```
+#[derive(Default)]
+pub struct LayeredHistory {
+    layers: Vec<Box<dyn History>>,
+    writable: Option<usize>,
+}
+
+impl LayeredHistory {
+    /// Creates a history without any layers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a read-only layer with a lower priority than the layers added before
+    ///
+    /// # Panics
+    /// If the history already has 64 layers
+    #[must_use]
+    pub fn with_layer(mut self, history: Box<dyn History>) -> Self {
+        assert!(
+            self.layers.len() < MAX_LAYERS,
+            "a LayeredHistory supports at most {MAX_LAYERS} layers"
+        );
+        self.layers.push(history);
+        self
+    }
+
+    /// Adds the layer new entries are saved to, with a lower priority than the layers added
+    /// before
+    ///
+    /// A previously added writable layer becomes read-only.
+    ///
+    /// # Panics
+    /// If the history already has 64 layers
+    #[must_use]
+    pub fn with_writable_layer(self, history: Box<dyn History>) -> Self {
+        let mut layered = self.with_layer(history);
+        layered.writable = Some(layered.layers.len() - 1);
+        layered
+    }
+
+    /// The first id of the layer, higher priority layers get higher ids
+    fn layer_base(layer: usize) -> i64 {
+        (MAX_LAYERS - 1 - layer) as i64 * LAYER_ID_STRIDE
+    }
+
+    /// Maps the id of an entry of the layer to the id in this history
+    fn layered_item(layer: usize, mut item: HistoryItem) -> Result<HistoryItem> {
+        if let Some(id) = item.id {
+            if !(0..LAYER_ID_STRIDE).contains(&id.0) {
+                return Err(ReedlineError(ReedlineErrorVariants::OtherHistoryError(
+                    "The id of the history layer's entry is out of range",
+                )));
+            }
+            item.id = Some(HistoryItemId::new(Self::layer_base(layer) + id.0));
+        }
+        Ok(item)
+    }
+
+    /// Maps an id of this history to the layer and the id within it
+    fn layer_of(&self, id: HistoryItemId) -> Result<(usize, HistoryItemId)> {
+        let layer = (MAX_LAYERS - 1).checked_sub(id.0.div_euclid(LAYER_ID_STRIDE) as usize);
+        match layer {
+            Some(layer) if id.0 >= 0 && layer < self.layers.len() => {
+                Ok((layer, HistoryItemId::new(id.0 - Self::layer_base(layer))))
+            }
+            _ => Err(ReedlineError(ReedlineErrorVariants::OtherHistoryError(
+                "Item does not exist",
+            ))),
+        }
+    }
+
+    fn writable_layer(&self) -> Result<usize> {
+        self.writable
+            .ok_or(ReedlineError(ReedlineErrorVariants::OtherHistoryError(
+                "The layered history has no writable layer",
+            )))
+    }
+
+    /// Maps an id of this history to the id within the writable layer
+    fn writable_id(&self, id: HistoryItemId) -> Result<(usize, HistoryItemId)> {
+        let writable = self.writable_layer()?;
+        match self.layer_of(id)? {
+            (layer, id) if layer == writable => Ok((layer, id)),
+            _ => Err(ReedlineError(ReedlineErrorVariants::OtherHistoryError(
+                "The history layer of the item is read-only",
+            ))),
+        }
+    }
+
+    /// The query for the layer, `None` if the id bounds exclude all of its entries
+    fn layer_query(layer: usize, query: &SearchQuery) -> Option<SearchQuery> {
+        let base = Self::layer_base(layer);
+        let is_forward = query.direction == SearchDirection::Forward;
+        let start_id = match query.start_id {
+            Some(start) => layer_bound(start.0.saturating_sub(base), !is_forward, false)?,
+            None => None,
+        };
+        let end_id = match query.end_id {
+            Some(end) => layer_bound(end.0.saturating_sub(base), is_forward, true)?,
+            None => None,
+        };
+        Some(SearchQuery {
+            start_id,
+            end_id,
+            ..query.clone()
+        })
+    }
+
+    /// The command lines matching the filter in the layers with a higher priority
+    fn shadowed_command_lines(
+        &self,
+        layer: usize,
+        filter: &SearchFilter,
+    ) -> Result<HashSet<String>> {
+        let mut shadowed = HashSet::new();
+        for higher in &self.layers[..layer] {
+            let mut filter = filter.clone();
+            filter.distinct = true;
+            let query = SearchQuery {
+                filter,
+                ..SearchQuery::everything(SearchDirection::Backward, None)
+            };
+            for item in higher.search_iter(query) {
+                shadowed.insert(item?.command_line);
+            }
+        }
+        Ok(shadowed)
+    }
+
+    fn layer_results(
+        &self,
+        layer: usize,
+        query: &SearchQuery,
+    ) -> Box<dyn Iterator<Item = Result<HistoryItem>> + '_> {
+        let Some(mut layer_query) = Self::layer_query(layer, query) else {
+            return Box::new(std::iter::empty());
+        };
+        let shadowed = if query.filter.distinct || query.ranking != SearchRanking::Chronological {
+            match self.shadowed_command_lines(layer, &query.filter) {
+                Ok(shadowed) => shadowed,
+                Err(err) => return Box::new(std::iter::once(Err(err))),
+            }
+        } else {
+            HashSet::new()
+        };
+        if !shadowed.is_empty() {
+            // The limit is applied after skipping the shadowed entries
+            layer_query.limit = None;
+        }
+        Box::new(
+            self.layers[layer]
+                .search_iter(layer_query)
+                .filter(move |item| {
+                    (item.as_ref()).map_or(true, |item| !shadowed.contains(&item.command_line))
+                })
+                .map(move |item| Self::layered_item(layer, item?)),
+        )
+    }
+}
+
+/// Translates an id bound to the ids of a layer, `relative` is the bound minus the first id of
+/// the layer
+///
+/// Returns `None` if the bound excludes every id of the layer and `Some(None)` if it excludes
+/// none.
+fn layer_bound(relative: i64, below: bool, inclusive: bool) -> Option<Option<HistoryItemId>> {
+    let bound = Some(Some(HistoryItemId::new(relative)));
+    if below {
+        let last = if inclusive {
+            relative
+        } else {
+            relative.saturating_sub(1)
+        };
+        if last < 0 {
+            None
+        } else if last >= LAYER_ID_STRIDE - 1 {
+            Some(None)
+        } else {
+            bound
+        }
+    } else {
+        let first = if inclusive {
+            relative
+        } else {
+            relative.saturating_add(1)
+        };
+        if first > LAYER_ID_STRIDE - 1 {
+            None
+        } else if first <= 0 {
+            Some(None)
+        } else {
+            bound
+        }
+    }
+}
+
+impl History for LayeredHistory {
+    fn save(&mut self, h: HistoryItem) -> Result<HistoryItem> {
+        let (layer, id) = match h.id {
+            Some(id) => {
+                let (layer, id) = self.writable_id(id)?;
+                (layer, Some(id))
+            }
+            None => (self.writable_layer()?, None),
+        };
+        let saved = self.layers[layer].save(HistoryItem { id, ..h })?;
+        Self::layered_item(layer, saved)
+    }
+
+    fn load(&self, id: HistoryItemId) -> Result<HistoryItem> {
+        let (layer, layer_id) = self.layer_of(id)?;
+        Self::layered_item(layer, self.layers[layer].load(layer_id)?)
+    }
+
+    fn count(&self, query: SearchQuery) -> Result<i64> {
+        if query.filter.distinct || query.ranking != SearchRanking::Chronological {
+            return Ok(self.search(query)?.len() as i64);
+        }
+        let mut count = 0;
+        for (layer, history) in self.layers.iter().enumerate() {
+            if let Some(layer_query) = Self::layer_query(layer, &query) {
+                count += history.count(layer_query)?;
+            }
+        }
+        // Every layer applies the limit on its own
+        Ok(query.limit.map_or(count, |limit| count.min(limit.max(0))))
+    }
+
+    fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>> {
+        self.search_iter(query).collect()
+    }
+
+    fn search_iter(
+        &self,
+        query: SearchQuery,
+    ) -> Box<dyn Iterator<Item = Result<HistoryItem>> + '_> {
+        // Higher priority layers have the higher ids, so they come first going backward
+        let mut layers: Vec<usize> = (0..self.layers.len()).collect();
+        if query.direction == SearchDirection::Forward
+            && query.ranking == SearchRanking::Chronological
+        {
+            layers.reverse();
+        }
+        let limit = query
+            .limit
+            .map_or(usize::MAX, |limit| limit.max(0) as usize);
+        Box::new(
+            layers
+                .into_iter()
+                .flat_map(move |layer| self.layer_results(layer, &query))
+                .scan(false, |failed, item| {
+                    if *failed {
+                        return None;
+                    }
+                    *failed = item.is_err();
+                    Some(item)
+                })
+                .take(limit),
+        )
+    }
+
+    fn update(
+        &mut self,
+        id: HistoryItemId,
+        updater: &dyn Fn(HistoryItem) -> HistoryItem,
+    ) -> Result<()> {
+        let (layer, layer_id) = self.writable_id(id)?;
+        self.layers[layer].update(layer_id, &|item| HistoryItem {
+            id: Some(layer_id),
+            ..updater(HistoryItem {
+                id: Some(id),
+                ..item
+            })
+        })
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        let layer = self.writable_layer()?;
+        self.layers[layer].clear()
+    }
+
+    fn delete(&mut self, h: HistoryItemId) -> Result<()> {
+        let (layer, layer_id) = self.writable_id(h)?;
+        self.layers[layer].delete(layer_id)
+    }
+
+    fn sync(&mut self) -> std::io::Result<()> {
+        match self.writable {
+            Some(layer) => self.layers[layer].sync(),
+            None => Ok(()),
+        }
+    }
+
+    fn session(&self) -> Option<HistorySessionId> {
+        self.writable.and_then(|layer| self.layers[layer].session())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommandLineSearch, FileBackedHistory};
+    use pretty_assertions::assert_eq;
+
+    fn file_backed(command_lines: &[&str]) -> Box<dyn History> {
+        let mut history = FileBackedHistory::new(100).unwrap();
+        for command_line in command_lines {
+            history
+                .save(HistoryItem::from_command_line(*command_line))
+                .unwrap();
+        }
+        Box::new(history)
+    }
+
+    fn layered() -> LayeredHistory {
+        LayeredHistory::new()
+            .with_layer(file_backed(&["make", "make test"]))
+            .with_writable_layer(file_backed(&["ls", "make", "cd /"]))
+            .with_layer(file_backed(&["deploy"]))
+    }
+
+    fn command_lines(items: Vec<HistoryItem>) -> Vec<String> {
+        items.into_iter().map(|item| item.command_line).collect()
+    }
+
+    #[test]
+    fn searches_the_layers_by_priority() -> Result<()> {
+        let history = layered();
+        let backward = SearchQuery::everything(SearchDirection::Backward, None);
+        assert_eq!(
+            command_lines(history.search(backward)?),
+            ["make test", "make", "cd /", "make", "ls", "deploy"]
+        );
+        let forward = SearchQuery::everything(SearchDirection::Forward, None);
+        assert_eq!(
+            command_lines(history.search(forward)?),
+            ["deploy", "ls", "make", "cd /", "make", "make test"]
+        );
+        assert_eq!(history.count_all()?, 6);
+        Ok(())
+    }
+
+    #[test]
+    fn count_stays_within_the_limit() -> Result<()> {
+        let history = layered();
+        let query = SearchQuery {
+            limit: Some(4),
+            ..SearchQuery::everything(SearchDirection::Backward, None)
+        };
+        assert_eq!(history.count(query)?, 4);
+        Ok(())
+    }
+
+    #[test]
+    fn frecency_ranks_every_layer_on_its_own() -> Result<()> {
+        let history = LayeredHistory::new()
+            .with_layer(file_backed(&["make", "ls"]))
+            .with_writable_layer(file_backed(&[
+                "cd /", "git pull", "cd /", "git log", "cd /",
+            ]));
+        let query = SearchQuery {
+            ranking: SearchRanking::Frecency(crate::FrecencyContext::default()),
+            ..SearchQuery::everything(SearchDirection::Backward, None)
+        };
+        // "cd /" is run most often but the entries of the first layer come first
+        assert_eq!(
+            command_lines(history.search(query)?),
+            ["ls", "make", "cd /", "git log", "git pull"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ids_continue_across_layers() -> Result<()> {
+        let history = layered();
+        let all = history.search(SearchQuery::everything(SearchDirection::Backward, None))?;
+        for item in &all {
+            assert_eq!(&history.load(item.id.unwrap())?, item);
+        }
+
+        // Going back from the last entry of a layer continues with the next layer
+        let query = SearchQuery {
+            start_id: all[1].id,
+            limit: Some(2),
+            ..SearchQuery::everything(SearchDirection::Backward, None)
+        };
+        assert_eq!(command_lines(history.search(query)?), ["cd /", "make"]);
+
+        let query = SearchQuery {
+            start_id: all[4].id,
+            limit: Some(3),
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        };
+        assert_eq!(
+            command_lines(history.search(query)?),
+            ["make", "cd /", "make"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn distinct_skips_command_lines_of_higher_layers() -> Result<()> {
+        let history = layered();
+        let mut filter =
+            SearchFilter::from_text_search(CommandLineSearch::Prefix("m".to_string()), None);
+        filter.distinct = true;
+        let query = SearchQuery {
+            filter,
+            ..SearchQuery::everything(SearchDirection::Backward, None)
+        };
+        assert_eq!(
+            command_lines(history.search(query.clone())?),
+            ["make test", "make"]
+        );
+        assert_eq!(history.count(query)?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn saves_to_the_writable_layer_only() -> Result<()> {
+        let mut history = layered();
+        let saved = history.save(HistoryItem::from_command_line("pwd"))?;
+        assert_eq!(history.load(saved.id.unwrap())?, saved);
+        let last = history.search(SearchQuery::everything(SearchDirection::Backward, None))?;
+        assert_eq!(last[2], saved, "saved after the writable layer's entries");
+
+        let read_only = last[0].id.unwrap();
+        assert!(history.delete(read_only).is_err());
+        assert!(history
+            .save(HistoryItem {
+                id: Some(read_only),
+                ..HistoryItem::from_command_line("rm -rf /")
+            })
+            .is_err());
+        assert!(history.load(HistoryItemId::new(-1)).is_err());
+
+        let mut read_only = LayeredHistory::new().with_layer(file_backed(&["ls"]));
+        assert!(read_only
+            .save(HistoryItem::from_command_line("pwd"))
+            .is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "jsonl")]
+    #[test]
+    fn updates_and_deletes_entries_of_the_writable_layer() -> Result<()> {
+        let mut writable = crate::JsonlBackedHistory::new(100);
+        writable.save(HistoryItem::from_command_line("ls"))?;
+        let mut history = LayeredHistory::new()
+            .with_layer(file_backed(&["make"]))
+            .with_writable_layer(Box::new(writable));
+
+        let saved = history.save(HistoryItem::from_command_line("pwd"))?;
+        let id = saved.id.unwrap();
+        history.update(id, &|item| {
+            assert_eq!(item.id, Some(id));
+            HistoryItem {
+                bookmarked: true,
+                ..item
+            }
+        })?;
+        assert!(history.load(id)?.bookmarked);
+
+        history.delete(id)?;
+        assert!(history.load(id).is_err());
+        let all = history.search(SearchQuery::everything(SearchDirection::Backward, None))?;
+        assert_eq!(command_lines(all), ["make", "ls"]);
+        Ok(())
+    }
+}
@@ -1,18 +1,45 @@
 mod base;
 mod cursor;
 mod file_backed;
+mod frecency;
+mod full_text;
+mod interactive_search;
 mod item;
+#[cfg(feature = "jsonl")]
+mod jsonl_backed;
+mod layered;
+mod policy;
+mod predicate;
+mod redaction;
+#[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+mod retention;
+mod shell_history;
 #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
 mod sqlite_backed;
+#[cfg(feature = "jsonl")]
+pub use jsonl_backed::JsonlBackedHistory;
+#[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+pub use retention::HistoryRetention;
 #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
 pub use sqlite_backed::SqliteBackedHistory;
 
 pub use base::{
     CommandLineSearch, History, HistoryNavigationQuery, SearchDirection, SearchFilter, SearchQuery,
+    SearchRanking,
 };
 pub use cursor::HistoryCursor;
+pub(crate) use interactive_search::{HistorySearchContext, InteractiveHistorySearch};
 pub use item::{
     HistoryItem, HistoryItemExtraInfo, HistoryItemId, HistorySessionId, IgnoreAllExtraInfo,
 };
 
 pub use file_backed::{FileBackedHistory, HISTORY_SIZE};
+pub use frecency::FrecencyContext;
+pub use layered::LayeredHistory;
+pub use policy::{HistoryDuplicates, HistoryIgnorePattern, HistoryPolicy};
+pub use predicate::SearchPredicate;
+pub use redaction::{
+    default_secret_detectors, EntropySecretDetector, HistoryRedaction, PatternSecretDetector,
+    RedactionAction, SecretDetector,
+};
+pub use shell_history::ShellHistoryFormat;
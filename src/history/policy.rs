@@ -0,0 +1,270 @@
+use crate::{
+    result::{ReedlineError, ReedlineErrorVariants},
+    Result,
+};
+use regex::Regex;
+
+/// How a [`HistoryPolicy`] treats a command line that is already in the [`History`](crate::History)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HistoryDuplicates {
+    /// Save every command line the history accepts
+    ///
+    /// The [`FileBackedHistory`](crate::FileBackedHistory) never saves a command line identical
+    /// to its most recent entry, so it behaves like [`HistoryDuplicates::IgnoreConsecutive`].
+    #[default]
+    Keep,
+    /// Don't save a command line identical to the most recent entry
+    ///
+    /// Like `ignoredups` in bash's `HISTCONTROL` or zsh's `HIST_IGNORE_DUPS`. The context of the
+    /// skipped run, like its exit status, isn't stored on the earlier entry.
+    IgnoreConsecutive,
+    /// Remove all older copies of the command line before saving it
+    ///
+    /// Like `erasedups` in bash's `HISTCONTROL` or zsh's `HIST_IGNORE_ALL_DUPS`.
+    /// Only histories that support deleting entries can erase the older copies.
+    EraseAll,
+}
+
+#[derive(Debug, Clone)]
+enum Pattern {
+    Glob(String),
+    Regex(Regex),
+}
+
+/// A pattern for command lines that should not be saved to the history
+///
+/// Patterns have to match the whole command line, ignoring surrounding whitespace.
+#[derive(Debug, Clone)]
+pub struct HistoryIgnorePattern(Pattern);
+
+impl HistoryIgnorePattern {
+    /// A shell-style glob like in bash's `HISTIGNORE`
+    ///
+    /// `*` matches any sequence of characters, `?` a single character and `\` escapes the
+    /// following character. E.g. `ls` ignores just `ls`, `ls *` ignores `ls` with arguments.
+    pub fn glob(pattern: impl Into<String>) -> Self {
+        Self(Pattern::Glob(pattern.into()))
+    }
+
+    /// A regular expression in the syntax of the [`regex`] crate
+    ///
+    /// Returns an error if the regular expression is invalid.
+    pub fn regex(pattern: &str) -> Result<Self> {
+        let anchored = format!("^(?:{pattern})$");
+        let regex = Regex::new(&anchored)
+            .map_err(|e| ReedlineError(ReedlineErrorVariants::InvalidRegex(e.to_string())))?;
+        Ok(Self(Pattern::Regex(regex)))
+    }
+
+    /// Check if the pattern matches the command line
+    pub fn matches(&self, command_line: &str) -> bool {
+        let command_line = command_line.trim();
+        match &self.0 {
+            Pattern::Glob(glob) => glob_matches(glob, command_line),
+            Pattern::Regex(regex) => regex.is_match(command_line),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GlobToken {
+    AnySequence,
+    AnyChar,
+    Char(char),
+}
+
+/// Check if the shell-style glob matches the whole text
+pub(crate) fn glob_matches(glob: &str, text: &str) -> bool {
+    let mut tokens = vec![];
+    let mut chars = glob.chars();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            '*' => GlobToken::AnySequence,
+            '?' => GlobToken::AnyChar,
+            '\\' => GlobToken::Char(chars.next().unwrap_or('\\')),
+            c => GlobToken::Char(c),
+        });
+    }
+    let text: Vec<char> = text.chars().collect();
+
+    // Only the last `*` is retried with a longer sequence, earlier ones can keep theirs
+    let (mut token, mut position) = (0, 0);
+    let mut last_any_sequence: Option<(usize, usize)> = None;
+    while position < text.len() {
+        match tokens.get(token) {
+            Some(GlobToken::AnySequence) => {
+                last_any_sequence = Some((token, position));
+                token += 1;
+                continue;
+            }
+            Some(GlobToken::AnyChar) => {
+                token += 1;
+                position += 1;
+                continue;
+            }
+            Some(GlobToken::Char(c)) if *c == text[position] => {
+                token += 1;
+                position += 1;
+                continue;
+            }
+            _ => {}
+        }
+        let Some((any_sequence, start)) = last_any_sequence else {
+            return false;
+        };
+        last_any_sequence = Some((any_sequence, start + 1));
+        token = any_sequence + 1;
+        position = start + 1;
+    }
+    tokens[token..]
+        .iter()
+        .all(|token| *token == GlobToken::AnySequence)
+}
+
+/// Decides which submitted command lines [`Reedline`](crate::Reedline) saves to the history
+///
+/// Applied to every [`History`](crate::History) backend when a line is submitted. The default
+/// policy saves every non-empty line. Navigating the history skips an entry identical to the
+/// one shown before, independent of the policy.
+///
+/// # Example
+/// ```rust
+/// use reedline::{HistoryDuplicates, HistoryIgnorePattern, HistoryPolicy, Reedline};
+///
+/// let policy = HistoryPolicy::default()
+///     .with_duplicates(HistoryDuplicates::EraseAll)
+///     .with_ignore_space(true)
+///     .with_ignore_pattern(HistoryIgnorePattern::glob("ls"))
+///     .with_ignore_pattern(HistoryIgnorePattern::glob("exit"))
+///     .with_min_length(2)
+///     .with_ignore_failed(true);
+/// let line_editor = Reedline::create().with_history_policy(policy);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HistoryPolicy {
+    duplicates: HistoryDuplicates,
+    ignore_space: bool,
+    ignore_patterns: Vec<HistoryIgnorePattern>,
+    min_length: usize,
+    ignore_failed: bool,
+}
+
+impl HistoryPolicy {
+    /// How to treat command lines already in the history
+    #[must_use]
+    pub fn with_duplicates(mut self, duplicates: HistoryDuplicates) -> Self {
+        self.duplicates = duplicates;
+        self
+    }
+
+    /// Don't save command lines starting with a space (bash's `ignorespace`)
+    #[must_use]
+    pub fn with_ignore_space(mut self, ignore_space: bool) -> Self {
+        self.ignore_space = ignore_space;
+        self
+    }
+
+    /// Don't save command lines matching the pattern, can be added multiple times
+    #[must_use]
+    pub fn with_ignore_pattern(mut self, pattern: HistoryIgnorePattern) -> Self {
+        self.ignore_patterns.push(pattern);
+        self
+    }
+
+    /// Don't save command lines with fewer characters, ignoring surrounding whitespace
+    #[must_use]
+    pub fn with_min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length;
+        self
+    }
+
+    /// Don't save commands that failed
+    ///
+    /// The entry is only saved once the exit status is known to be successful or unknown,
+    /// so the exit status has to be reported via
+    /// [`Reedline::update_last_command_context`](crate::Reedline::update_last_command_context)
+    /// before the next line is submitted.
+    #[must_use]
+    pub fn with_ignore_failed(mut self, ignore_failed: bool) -> Self {
+        self.ignore_failed = ignore_failed;
+        self
+    }
+
+    /// How command lines already in the history are treated
+    pub fn duplicates(&self) -> HistoryDuplicates {
+        self.duplicates
+    }
+
+    /// Whether failed commands are dropped from the history
+    pub fn ignores_failed(&self) -> bool {
+        self.ignore_failed
+    }
+
+    /// Check if the command line should not be saved, independent of the existing history
+    pub fn ignores(&self, command_line: &str) -> bool {
+        (self.ignore_space && command_line.starts_with(' '))
+            || command_line.trim().chars().count() < self.min_length
+            || self
+                .ignore_patterns
+                .iter()
+                .any(|pattern| pattern.matches(command_line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("ls", "ls", true)]
+    #[case("ls", " ls ", true)]
+    #[case("ls", "ls -l", false)]
+    #[case("ls *", "ls -l", true)]
+    #[case("ls *", "ls", false)]
+    #[case("?d *", "cd foo", true)]
+    #[case("*exit*", "exit", true)]
+    #[case("echo \\*", "echo *", true)]
+    #[case("echo \\*", "echo a", false)]
+    #[case("a\\", "a\\", true)]
+    #[case("*a*b", "xaxxb", true)]
+    #[case("*a*b", "xaxxbx", false)]
+    #[case("*a?", "aaa", true)]
+    fn glob_patterns(#[case] glob: &str, #[case] command_line: &str, #[case] expected: bool) {
+        assert_eq!(
+            HistoryIgnorePattern::glob(glob).matches(command_line),
+            expected
+        );
+    }
+
+    #[test]
+    fn glob_with_many_stars_fails_fast() {
+        let text = "a".repeat(100);
+        assert!(!HistoryIgnorePattern::glob("*a*a*a*a*a*a*a*a*a*a*b").matches(&text));
+        assert!(HistoryIgnorePattern::glob("*a*a*a*a*a*a*a*a*a*a*").matches(&text));
+    }
+
+    #[test]
+    fn regex_patterns_match_the_whole_line() -> Result<()> {
+        let pattern = HistoryIgnorePattern::regex("exit|cd( .*)?")?;
+        assert!(pattern.matches("exit"));
+        assert!(pattern.matches("cd /tmp"));
+        assert!(!pattern.matches("echo exit"));
+        assert!(HistoryIgnorePattern::regex("(").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn policy_ignores() {
+        let policy = HistoryPolicy::default()
+            .with_ignore_space(true)
+            .with_min_length(3)
+            .with_ignore_pattern(HistoryIgnorePattern::glob("exit"));
+
+        assert!(policy.ignores(" echo secret"));
+        assert!(policy.ignores("ls"));
+        assert!(policy.ignores("exit"));
+        assert!(!policy.ignores("ls -l"));
+        assert!(!HistoryPolicy::default().ignores(" ls"));
+    }
+}
@@ -0,0 +1,253 @@
+#[cfg(any(test, feature = "jsonl"))]
+use super::{policy::glob_matches, HistoryItem};
+use chrono::NaiveTime;
+#[cfg(any(test, feature = "jsonl", feature = "sqlite", feature = "sqlite-dynlib"))]
+use chrono::{Local, Timelike, Utc};
+use std::{ops::RangeInclusive, time::Duration};
+
+/// A condition on the fields of [`HistoryItem`](super::HistoryItem)s for [`SearchFilter::predicate`](super::SearchFilter::predicate)
+///
+/// Predicates can be combined with [`SearchPredicate::and()`], [`SearchPredicate::or()`] and
+/// negated with `!`. An entry without the field a predicate looks at doesn't match it, so
+/// the negated predicate does.
+///
+/// # Example
+/// ```rust
+/// use reedline::{SearchFilter, SearchPredicate};
+/// use std::time::Duration;
+///
+/// // Slow or failed builds in any checkout of the project, except those that were aborted
+/// let predicate = SearchPredicate::MinDuration(Duration::from_secs(60))
+///     .or(!SearchPredicate::exit_code(0))
+///     .and(SearchPredicate::CwdGlob("*/project*".to_string()))
+///     .and(!SearchPredicate::exit_code(130));
+///
+/// let mut filter = SearchFilter::anything(None);
+/// filter.predicate = Some(predicate);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchPredicate {
+    /// The exit status is within the range
+    ExitStatus(RangeInclusive<i64>),
+    /// The command ran at least this long
+    MinDuration(Duration),
+    /// The command ran at most this long
+    MaxDuration(Duration),
+    /// The hostname matches the glob pattern, see
+    /// [`HistoryIgnorePattern::glob()`](crate::HistoryIgnorePattern::glob) for the syntax
+    HostnameGlob(String),
+    /// The working directory matches the glob pattern, see
+    /// [`HistoryIgnorePattern::glob()`](crate::HistoryIgnorePattern::glob) for the syntax
+    CwdGlob(String),
+    /// The command was started at a local time of day in `from..to`
+    ///
+    /// The range wraps around midnight if `from` is later than `to`.
+    TimeOfDay {
+        /// Start of the range, inclusive
+        from: NaiveTime,
+        /// End of the range, exclusive
+        to: NaiveTime,
+    },
+    /// The value at the JSON path of the serialized `more_info` equals the JSON value
+    ///
+    /// The path uses SQLite's syntax, e.g. `$.mode` or `$.tags[0]`. Only supported by the
+    /// `SqliteBackedHistory`.
+    MoreInfo {
+        /// Path to the compared value
+        path: String,
+        /// The expected value as JSON, e.g. `"\"shell\""` or `42`
+        value: String,
+    },
+    /// The predicate doesn't match
+    Not(Box<SearchPredicate>),
+    /// All of the predicates match
+    All(Vec<SearchPredicate>),
+    /// Any of the predicates matches
+    Any(Vec<SearchPredicate>),
+}
+
+impl SearchPredicate {
+    /// The command exited with the specific exit code
+    pub fn exit_code(code: i64) -> Self {
+        SearchPredicate::ExitStatus(code..=code)
+    }
+
+    /// Both predicates match
+    #[must_use]
+    pub fn and(self, other: SearchPredicate) -> Self {
+        match self {
+            SearchPredicate::All(mut all) => {
+                all.push(other);
+                SearchPredicate::All(all)
+            }
+            predicate => SearchPredicate::All(vec![predicate, other]),
+        }
+    }
+
+    /// Any of the predicates matches
+    #[must_use]
+    pub fn or(self, other: SearchPredicate) -> Self {
+        match self {
+            SearchPredicate::Any(mut any) => {
+                any.push(other);
+                SearchPredicate::Any(any)
+            }
+            predicate => SearchPredicate::Any(vec![predicate, other]),
+        }
+    }
+
+    /// Whether the predicate queries `more_info`, which only some histories can do
+    #[cfg(any(test, feature = "jsonl"))]
+    pub(crate) fn uses_more_info(&self) -> bool {
+        match self {
+            SearchPredicate::MoreInfo { .. } => true,
+            SearchPredicate::Not(predicate) => predicate.uses_more_info(),
+            SearchPredicate::All(predicates) | SearchPredicate::Any(predicates) => {
+                predicates.iter().any(SearchPredicate::uses_more_info)
+            }
+            _ => false,
+        }
+    }
+
+    /// Check the predicate against the fields of the item, `more_info` queries never match
+    #[cfg(any(test, feature = "jsonl"))]
+    pub(crate) fn matches(&self, item: &HistoryItem) -> bool {
+        match self {
+            SearchPredicate::ExitStatus(range) => item
+                .exit_status
+                .map_or(false, |status| range.contains(&status)),
+            SearchPredicate::MinDuration(min) => item.duration.map_or(false, |d| d >= *min),
+            SearchPredicate::MaxDuration(max) => item.duration.map_or(false, |d| d <= *max),
+            SearchPredicate::HostnameGlob(glob) => item
+                .hostname
+                .as_ref()
+                .map_or(false, |hostname| glob_matches(glob, hostname)),
+            SearchPredicate::CwdGlob(glob) => item
+                .cwd
+                .as_ref()
+                .map_or(false, |cwd| glob_matches(glob, cwd)),
+            SearchPredicate::TimeOfDay { from, to } => {
+                item.start_timestamp.map_or(false, |timestamp| {
+                    time_of_day_in_range(
+                        local_seconds_from_midnight(timestamp),
+                        from.num_seconds_from_midnight(),
+                        to.num_seconds_from_midnight(),
+                    )
+                })
+            }
+            SearchPredicate::MoreInfo { .. } => false,
+            SearchPredicate::Not(predicate) => !predicate.matches(item),
+            SearchPredicate::All(predicates) => predicates.iter().all(|p| p.matches(item)),
+            SearchPredicate::Any(predicates) => predicates.iter().any(|p| p.matches(item)),
+        }
+    }
+}
+
+impl std::ops::Not for SearchPredicate {
+    type Output = SearchPredicate;
+
+    fn not(self) -> Self::Output {
+        match self {
+            SearchPredicate::Not(predicate) => *predicate,
+            predicate => SearchPredicate::Not(Box::new(predicate)),
+        }
+    }
+}
+
+/// Seconds since midnight of the timestamp in the local time zone
+#[cfg(any(test, feature = "jsonl", feature = "sqlite", feature = "sqlite-dynlib"))]
+pub(crate) fn local_seconds_from_midnight(timestamp: chrono::DateTime<Utc>) -> u32 {
+    timestamp
+        .with_timezone(&Local)
+        .time()
+        .num_seconds_from_midnight()
+}
+
+/// Whether the time of day is in `from..to`, wrapping around midnight if `from > to`
+#[cfg(any(test, feature = "jsonl", feature = "sqlite", feature = "sqlite-dynlib"))]
+pub(crate) fn time_of_day_in_range(time: u32, from: u32, to: u32) -> bool {
+    if from <= to {
+        from <= time && time < to
+    } else {
+        from <= time || time < to
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rstest::rstest;
+
+    fn item() -> HistoryItem {
+        HistoryItem {
+            hostname: Some("build-01".to_string()),
+            cwd: Some("/home/me/project".to_string()),
+            duration: Some(Duration::from_secs(90)),
+            exit_status: Some(2),
+            ..HistoryItem::from_command_line("make")
+        }
+    }
+
+    #[rstest]
+    #[case(SearchPredicate::exit_code(2), true)]
+    #[case(SearchPredicate::ExitStatus(1..=127), true)]
+    #[case(!SearchPredicate::exit_code(2), false)]
+    #[case(SearchPredicate::MinDuration(Duration::from_secs(60)), true)]
+    #[case(SearchPredicate::MaxDuration(Duration::from_secs(60)), false)]
+    #[case(SearchPredicate::HostnameGlob("build-*".to_string()), true)]
+    #[case(SearchPredicate::CwdGlob("/home/*/other".to_string()), false)]
+    #[case(
+        SearchPredicate::exit_code(0).or(SearchPredicate::HostnameGlob("build-??".to_string())),
+        true
+    )]
+    #[case(
+        SearchPredicate::exit_code(2).and(!SearchPredicate::CwdGlob("/home/*".to_string())),
+        false
+    )]
+    #[case(SearchPredicate::MoreInfo { path: "$.a".to_string(), value: "1".to_string() }, false)]
+    fn matches_items(#[case] predicate: SearchPredicate, #[case] expected: bool) {
+        assert_eq!(predicate.matches(&item()), expected);
+    }
+
+    #[test]
+    fn missing_fields_only_match_negated_predicates() {
+        let item = HistoryItem::from_command_line("ls");
+        assert!(!SearchPredicate::exit_code(0).matches(&item));
+        assert!((!SearchPredicate::exit_code(0)).matches(&item));
+    }
+
+    #[test]
+    fn combinators_flatten() {
+        let predicate = SearchPredicate::exit_code(1)
+            .and(SearchPredicate::exit_code(2))
+            .and(SearchPredicate::exit_code(3));
+        assert!(matches!(&predicate, SearchPredicate::All(all) if all.len() == 3));
+        assert!(!predicate.uses_more_info());
+        assert_eq!(!!predicate.clone(), predicate);
+    }
+
+    #[test]
+    fn time_of_day_wraps_around_midnight() {
+        let hour = 60 * 60;
+        assert!(time_of_day_in_range(9 * hour, 8 * hour, 17 * hour));
+        assert!(!time_of_day_in_range(17 * hour, 8 * hour, 17 * hour));
+        assert!(time_of_day_in_range(23 * hour, 22 * hour, 6 * hour));
+        assert!(time_of_day_in_range(hour, 22 * hour, 6 * hour));
+        assert!(!time_of_day_in_range(12 * hour, 22 * hour, 6 * hour));
+
+        let timestamp = Local
+            .with_ymd_and_hms(2024, 1, 1, 23, 30, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let item = HistoryItem {
+            start_timestamp: Some(timestamp),
+            ..HistoryItem::from_command_line("ls")
+        };
+        let night = SearchPredicate::TimeOfDay {
+            from: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            to: NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+        };
+        assert!(night.matches(&item));
+    }
+}
@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+/// Rules deciding which entries of a [`SqliteBackedHistory`](crate::SqliteBackedHistory)
+/// are removed when it is pruned
+///
+/// They are applied when opening the history with
+/// [`SqliteBackedHistory::with_retention()`](crate::SqliteBackedHistory::with_retention) or on
+/// demand with [`SqliteBackedHistory::apply_retention()`](crate::SqliteBackedHistory::apply_retention)
+/// and [`SqliteBackedHistory::compact()`](crate::SqliteBackedHistory::compact).
+///
+/// Every rule is optional, the default retention keeps everything. Entries without a
+/// start timestamp are never removed because of their age.
+///
+/// # Example
+/// ```rust,no_run
+/// use reedline::{HistoryRetention, SqliteBackedHistory};
+/// use std::time::Duration;
+///
+/// const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+///
+/// let retention = HistoryRetention::default()
+///     .with_max_entries(100_000)
+///     .with_max_age(365 * DAY)
+///     .with_max_per_command(50)
+///     .with_max_failed_age(7 * DAY)
+///     .with_keep_bookmarked(true);
+///
+/// let mut history = SqliteBackedHistory::with_file("history.sqlite3".into(), None, None)
+///     .and_then(|history| history.with_retention(&retention))
+///     .unwrap();
+///
+/// // Later, e.g. once a month
+/// let removed = history.compact(&retention).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryRetention {
+    pub(crate) max_entries: Option<usize>,
+    pub(crate) max_age: Option<Duration>,
+    pub(crate) max_per_command: Option<usize>,
+    pub(crate) max_failed_age: Option<Duration>,
+    pub(crate) keep_bookmarked: bool,
+}
+
+impl HistoryRetention {
+    /// Keep only the given number of the most recent entries
+    #[must_use]
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Remove entries started longer ago
+    #[must_use]
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Keep only the given number of the most recent entries of every distinct command line
+    #[must_use]
+    pub fn with_max_per_command(mut self, max_per_command: usize) -> Self {
+        self.max_per_command = Some(max_per_command);
+        self
+    }
+
+    /// Remove entries with a non-zero exit status started longer ago
+    #[must_use]
+    pub fn with_max_failed_age(mut self, max_failed_age: Duration) -> Self {
+        self.max_failed_age = Some(max_failed_age);
+        self
+    }
+
+    /// Never remove bookmarked entries, they don't count towards the limits either
+    #[must_use]
+    pub fn with_keep_bookmarked(mut self, keep_bookmarked: bool) -> Self {
+        self.keep_bookmarked = keep_bookmarked;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{History, HistoryItem, Result, SearchDirection, SearchQuery, SqliteBackedHistory};
+    use chrono::Utc;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn history_with(items: &[(&str, u32, i64)]) -> Result<SqliteBackedHistory> {
+        history_with_bookmarks(items, &[])
+    }
+
+    fn history_with_bookmarks(
+        items: &[(&str, u32, i64)],
+        bookmarked: &[&str],
+    ) -> Result<SqliteBackedHistory> {
+        let mut history = SqliteBackedHistory::in_memory()?;
+        for &(command_line, days_ago, exit_status) in items {
+            history.save(HistoryItem {
+                start_timestamp: Some(
+                    Utc::now() - chrono::Duration::from_std(days_ago * DAY).unwrap(),
+                ),
+                exit_status: Some(exit_status),
+                bookmarked: bookmarked.contains(&command_line),
+                ..HistoryItem::from_command_line(command_line)
+            })?;
+        }
+        Ok(history)
+    }
+
+    fn command_lines(history: &SqliteBackedHistory) -> Result<Vec<String>> {
+        let items = history.search(SearchQuery::everything(SearchDirection::Forward, None))?;
+        Ok(items.into_iter().map(|item| item.command_line).collect())
+    }
+
+    #[test]
+    fn default_retention_keeps_everything() -> Result<()> {
+        let mut history = history_with(&[("old", 1000, 1), ("new", 0, 0)])?;
+        assert_eq!(history.apply_retention(&HistoryRetention::default())?, 0);
+        assert_eq!(command_lines(&history)?, ["old", "new"]);
+        Ok(())
+    }
+
+    #[test]
+    fn removes_old_and_failed_entries() -> Result<()> {
+        let mut history = history_with(&[
+            ("ancient", 400, 0),
+            ("old failure", 10, 1),
+            ("old success", 10, 0),
+            ("recent failure", 1, 1),
+        ])?;
+        let retention = HistoryRetention::default()
+            .with_max_age(365 * DAY)
+            .with_max_failed_age(7 * DAY);
+        assert_eq!(history.apply_retention(&retention)?, 2);
+        assert_eq!(command_lines(&history)?, ["old success", "recent failure"]);
+        Ok(())
+    }
+
+    #[test]
+    fn limits_entries_per_command_and_in_total() -> Result<()> {
+        let mut history = history_with(&[
+            ("ls", 0, 0),
+            ("cd", 0, 0),
+            ("ls", 0, 0),
+            ("ls", 0, 0),
+            ("cd", 0, 0),
+            ("pwd", 0, 0),
+        ])?;
+        let retention = HistoryRetention::default()
+            .with_max_per_command(2)
+            .with_max_entries(4);
+        assert_eq!(history.compact(&retention)?, 2);
+        assert_eq!(command_lines(&history)?, ["ls", "ls", "cd", "pwd"]);
+        Ok(())
+    }
+
+    #[test]
+    fn applies_the_retention_on_open() -> Result<()> {
+        let retention = HistoryRetention::default().with_max_entries(2);
+        let history = history_with(&[("ls", 0, 0), ("cd", 0, 0), ("pwd", 0, 0)])?
+            .with_retention(&retention)?;
+        assert_eq!(command_lines(&history)?, ["cd", "pwd"]);
+        Ok(())
+    }
+
+    #[test]
+    fn keeps_bookmarked_entries() -> Result<()> {
+        let items = [
+            ("ancient", 400, 0),
+            ("failed", 10, 1),
+            ("ls", 0, 0),
+            ("ls", 0, 0),
+            ("pwd", 0, 0),
+        ];
+        let retention = HistoryRetention::default()
+            .with_max_age(365 * DAY)
+            .with_max_failed_age(7 * DAY)
+            .with_max_per_command(1)
+            .with_max_entries(1);
+
+        let mut history = history_with_bookmarks(&items, &["ancient", "failed"])?;
+        assert_eq!(history.apply_retention(&retention)?, 4);
+        assert_eq!(command_lines(&history)?, ["pwd"]);
+
+        let mut history = history_with_bookmarks(&items, &["ancient", "failed"])?;
+        let retention = retention.with_keep_bookmarked(true);
+        assert_eq!(history.apply_retention(&retention)?, 2);
+        assert_eq!(command_lines(&history)?, ["ancient", "failed", "pwd"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,362 @@
+use super::{History, HistoryItem, SearchDirection, SearchQuery};
+use crate::Result;
+use chrono::{DateTime, TimeZone, Utc};
+use std::{path::Path, time::Duration};
+
+/// zsh escapes bytes it uses internally with this marker followed by the byte XOR 32
+const ZSH_META: u8 = 0x83;
+
+/// History file format of another shell that entries can be imported from and exported to
+///
+/// Entries are exchanged as [`HistoryItem`]s, so they can be stored in any [`History`]:
+/// - [`ShellHistoryFormat::Bash`]: one command per line, preceded by a `#<timestamp>` comment
+///   when bash runs with `HISTTIMEFORMAT` set. The lines between two timestamp comments form
+///   one multi-line command.
+/// - [`ShellHistoryFormat::Zsh`]: the `EXTENDED_HISTORY` format `: <timestamp>:<duration>;<command>`
+///   (plain lines are accepted too), continuing multi-line commands with a trailing backslash.
+/// - [`ShellHistoryFormat::Fish`]: the YAML-like `- cmd: <command>` entries with their `when:`
+///   timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellHistoryFormat {
+    /// `~/.bash_history`
+    Bash,
+    /// `~/.zsh_history`
+    Zsh,
+    /// `~/.local/share/fish/fish_history`
+    Fish,
+}
+
+impl ShellHistoryFormat {
+    /// Parse the contents of a history file, oldest entry first
+    pub fn parse(self, contents: &[u8]) -> Vec<HistoryItem> {
+        match self {
+            ShellHistoryFormat::Bash => parse_bash(&String::from_utf8_lossy(contents)),
+            ShellHistoryFormat::Zsh => parse_zsh(&String::from_utf8_lossy(&zsh_unmetafy(contents))),
+            ShellHistoryFormat::Fish => parse_fish(&String::from_utf8_lossy(contents)),
+        }
+    }
+
+    /// Render `items` in the file format
+    pub fn render(self, items: &[HistoryItem]) -> Vec<u8> {
+        match self {
+            ShellHistoryFormat::Bash => render_bash(items).into_bytes(),
+            ShellHistoryFormat::Zsh => zsh_metafy(&render_zsh(items)),
+            ShellHistoryFormat::Fish => render_fish(items).into_bytes(),
+        }
+    }
+
+    /// Save all entries of the history file at `path` to `history`
+    ///
+    /// Returns the number of imported entries.
+    pub fn import(self, path: impl AsRef<Path>, history: &mut dyn History) -> Result<usize> {
+        let items = self.parse(&std::fs::read(path)?);
+        let count = items.len();
+        for item in items {
+            history.save(item)?;
+        }
+        Ok(count)
+    }
+
+    /// Write all entries of `history` to a history file at `path`, replacing its content
+    ///
+    /// Returns the number of exported entries.
+    pub fn export(self, history: &dyn History, path: impl AsRef<Path>) -> Result<usize> {
+        let items = history.search(SearchQuery::everything(SearchDirection::Forward, None))?;
+        std::fs::write(path, self.render(&items))?;
+        Ok(items.len())
+    }
+}
+
+fn item_at(command_line: String, timestamp: Option<i64>) -> HistoryItem {
+    HistoryItem {
+        start_timestamp: timestamp.and_then(from_unix_timestamp),
+        ..HistoryItem::from_command_line(command_line)
+    }
+}
+
+fn from_unix_timestamp(seconds: i64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_opt(seconds, 0).single()
+}
+
+fn parse_bash(contents: &str) -> Vec<HistoryItem> {
+    let mut items: Vec<HistoryItem> = Vec::new();
+    let mut timestamp = None;
+    // Only timestamped entries can span multiple lines
+    let mut continues_entry = false;
+    for line in contents.lines() {
+        if let Some(seconds) = line
+            .strip_prefix('#')
+            .filter(|digits| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|digits| digits.parse().ok())
+        {
+            timestamp = Some(seconds);
+            continues_entry = false;
+            continue;
+        }
+        match items.last_mut() {
+            Some(item) if continues_entry => {
+                item.command_line.push('\n');
+                item.command_line.push_str(line);
+            }
+            _ => {
+                if line.is_empty() {
+                    continue;
+                }
+                continues_entry = timestamp.is_some();
+                items.push(item_at(line.to_string(), timestamp.take()));
+            }
+        }
+    }
+    items
+}
+
+fn render_bash(items: &[HistoryItem]) -> String {
+    let mut contents = String::new();
+    for item in items {
+        if let Some(timestamp) = item.start_timestamp {
+            contents.push_str(&format!("#{}\n", timestamp.timestamp()));
+        }
+        contents.push_str(&item.command_line);
+        contents.push('\n');
+    }
+    contents
+}
+
+fn parse_zsh(contents: &str) -> Vec<HistoryItem> {
+    let mut items = Vec::new();
+    let mut lines = contents.lines();
+    while let Some(line) = lines.next() {
+        let mut command_line = line.to_string();
+        while command_line.ends_with('\\') {
+            let Some(next) = lines.next() else {
+                break;
+            };
+            command_line.pop();
+            command_line.push('\n');
+            command_line.push_str(next);
+        }
+        let item = match parse_zsh_extended(&command_line) {
+            Some((timestamp, duration, command)) => HistoryItem {
+                duration: duration.map(Duration::from_secs),
+                ..item_at(command.to_string(), Some(timestamp))
+            },
+            None if command_line.is_empty() => continue,
+            None => HistoryItem::from_command_line(command_line),
+        };
+        items.push(item);
+    }
+    items
+}
+
+/// Split `: <timestamp>:<duration>;<command>`
+fn parse_zsh_extended(line: &str) -> Option<(i64, Option<u64>, &str)> {
+    let (header, command) = line.strip_prefix(": ")?.split_once(';')?;
+    let (timestamp, duration) = header.split_once(':').unwrap_or((header, ""));
+    Some((
+        timestamp.trim().parse().ok()?,
+        duration.trim().parse().ok(),
+        command,
+    ))
+}
+
+fn render_zsh(items: &[HistoryItem]) -> String {
+    let mut contents = String::new();
+    for item in items {
+        let command_line = item.command_line.replace('\n', "\\\n");
+        match item.start_timestamp {
+            Some(timestamp) => contents.push_str(&format!(
+                ": {}:{};{}\n",
+                timestamp.timestamp(),
+                item.duration.map_or(0, |duration| duration.as_secs()),
+                command_line
+            )),
+            None => {
+                contents.push_str(&command_line);
+                contents.push('\n');
+            }
+        }
+    }
+    contents
+}
+
+fn zsh_unmetafy(contents: &[u8]) -> Vec<u8> {
+    let mut bytes = contents.iter();
+    let mut unmetafied = Vec::with_capacity(contents.len());
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            ZSH_META => unmetafied.extend(bytes.next().map(|byte| byte ^ 32)),
+            byte => unmetafied.push(byte),
+        }
+    }
+    unmetafied
+}
+
+fn zsh_metafy(contents: &str) -> Vec<u8> {
+    let mut metafied = Vec::with_capacity(contents.len());
+    for &byte in contents.as_bytes() {
+        if byte == 0 || (ZSH_META..=0xa2).contains(&byte) {
+            metafied.push(ZSH_META);
+            metafied.push(byte ^ 32);
+        } else {
+            metafied.push(byte);
+        }
+    }
+    metafied
+}
+
+fn parse_fish(contents: &str) -> Vec<HistoryItem> {
+    let mut items: Vec<HistoryItem> = Vec::new();
+    for line in contents.lines() {
+        if let Some(command) = line.strip_prefix("- cmd: ") {
+            items.push(HistoryItem::from_command_line(fish_unescape(command)));
+        } else if let (Some(when), Some(item)) =
+            (line.trim_start().strip_prefix("when: "), items.last_mut())
+        {
+            item.start_timestamp = when.trim().parse().ok().and_then(from_unix_timestamp);
+        }
+    }
+    items
+}
+
+fn render_fish(items: &[HistoryItem]) -> String {
+    let mut contents = String::new();
+    for item in items {
+        contents.push_str("- cmd: ");
+        contents.push_str(&fish_escape(&item.command_line));
+        contents.push('\n');
+        if let Some(timestamp) = item.start_timestamp {
+            contents.push_str(&format!("  when: {}\n", timestamp.timestamp()));
+        }
+    }
+    contents
+}
+
+fn fish_escape(command_line: &str) -> String {
+    command_line.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn fish_unescape(command: &str) -> String {
+    let mut unescaped = String::with_capacity(command.len());
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('\\')) => {
+                chars.next();
+                unescaped.push('\\');
+            }
+            ('\\', Some('n')) => {
+                chars.next();
+                unescaped.push('\n');
+            }
+            (c, _) => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileBackedHistory;
+    use pretty_assertions::assert_eq;
+
+    fn commands(items: &[HistoryItem]) -> Vec<&str> {
+        items
+            .iter()
+            .map(|item| item.command_line.as_str())
+            .collect()
+    }
+
+    fn timestamps(items: &[HistoryItem]) -> Vec<Option<i64>> {
+        items
+            .iter()
+            .map(|item| item.start_timestamp.map(|timestamp| timestamp.timestamp()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_bash_history() {
+        let contents =
+            "ls -l\n#1700000000\nfor i in 1 2; do\n  echo $i\ndone\n#1700000005\ncd /tmp\n";
+        let items = ShellHistoryFormat::Bash.parse(contents.as_bytes());
+
+        assert_eq!(
+            commands(&items),
+            vec!["ls -l", "for i in 1 2; do\n  echo $i\ndone", "cd /tmp"]
+        );
+        assert_eq!(
+            timestamps(&items),
+            vec![None, Some(1_700_000_000), Some(1_700_000_005)]
+        );
+        assert_eq!(
+            String::from_utf8(ShellHistoryFormat::Bash.render(&items)).unwrap(),
+            contents
+        );
+    }
+
+    #[test]
+    fn parses_zsh_extended_history() {
+        let contents = b": 1700000000:3;make test\n: 1700000010:0;echo one\\\ntwo\nplain\n";
+        let items = ShellHistoryFormat::Zsh.parse(contents);
+
+        assert_eq!(
+            commands(&items),
+            vec!["make test", "echo one\ntwo", "plain"]
+        );
+        assert_eq!(
+            timestamps(&items),
+            vec![Some(1_700_000_000), Some(1_700_000_010), None]
+        );
+        assert_eq!(items[0].duration, Some(Duration::from_secs(3)));
+        assert_eq!(ShellHistoryFormat::Zsh.render(&items), contents);
+    }
+
+    #[test]
+    fn zsh_metafication_round_trips() {
+        // 'á' is encoded as 0xc3 0xa1 and 'ă' as 0xc4 0x83, both need escaping
+        let item = HistoryItem::from_command_line("echo áă…");
+        let rendered = ShellHistoryFormat::Zsh.render(&[item]);
+
+        assert!(rendered.contains(&ZSH_META));
+        assert_eq!(
+            commands(&ShellHistoryFormat::Zsh.parse(&rendered)),
+            vec!["echo áă…"]
+        );
+    }
+
+    #[test]
+    fn parses_fish_history() {
+        let contents = "- cmd: echo \\\\n\\nnext\n  when: 1700000000\n  paths:\n    - foo\n- cmd: ls\n  when: 1700000001\n";
+        let items = ShellHistoryFormat::Fish.parse(contents.as_bytes());
+
+        assert_eq!(commands(&items), vec!["echo \\n\nnext", "ls"]);
+        assert_eq!(
+            timestamps(&items),
+            vec![Some(1_700_000_000), Some(1_700_000_001)]
+        );
+        assert_eq!(
+            String::from_utf8(ShellHistoryFormat::Fish.render(&items)).unwrap(),
+            "- cmd: echo \\\\n\\nnext\n  when: 1700000000\n- cmd: ls\n  when: 1700000001\n"
+        );
+    }
+
+    #[test]
+    fn imports_and_exports_through_history() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let bash_history = dir.path().join(".bash_history");
+        let fish_history = dir.path().join("fish_history");
+        std::fs::write(&bash_history, "ls\ncd /tmp\n")?;
+
+        let mut history = FileBackedHistory::new(100)?;
+        assert_eq!(
+            ShellHistoryFormat::Bash.import(&bash_history, &mut history)?,
+            2
+        );
+        assert_eq!(ShellHistoryFormat::Fish.export(&history, &fish_history)?, 2);
+        assert_eq!(
+            std::fs::read_to_string(&fish_history)?,
+            "- cmd: ls\n- cmd: cd /tmp\n"
+        );
+        Ok(())
+    }
+}
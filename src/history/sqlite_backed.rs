@@ -1,26 +1,81 @@
 use super::{
-    base::{CommandLineSearch, SearchDirection, SearchQuery},
-    History, HistoryItem, HistoryItemId, HistorySessionId,
+    base::{
+        search_regex, CommandLineSearch, SearchDirection, SearchPages, SearchQuery, SearchRanking,
+    },
+    frecency::FrecencyStats,
+    full_text::FullTextQuery,
+    policy::glob_matches,
+    predicate::{local_seconds_from_midnight, time_of_day_in_range},
+    retention::HistoryRetention,
+    History, HistoryItem, HistoryItemId, HistorySessionId, SearchPredicate,
 };
 use crate::{
     result::{ReedlineError, ReedlineErrorVariants},
-    Result,
+    FuzzyMatcher, Result,
 };
-use chrono::{TimeZone, Utc};
-use rusqlite::{named_params, params, Connection, ToSql};
-use std::{path::PathBuf, time::Duration};
+use chrono::{TimeZone, Timelike, Utc};
+use regex::Regex;
+use rusqlite::{
+    functions::FunctionFlags, named_params, params, Connection, ToSql, TransactionBehavior,
+};
+use std::{borrow::Cow, path::PathBuf, time::Duration};
 const SQLITE_APPLICATION_ID: i32 = 1151497937;
 
+/// The schema migrations, the one at index `i` upgrades a database from version `i` to `i + 1`
+///
+/// The version is stored in `PRAGMA user_version`. Migrations must never be changed once
+/// released, new columns or indexes are added by appending a migration.
+const MIGRATIONS: &[&str] = &[
+    // 1: the initial schema, databases created before versioning are at version 0 with or
+    // without it
+    "
+    create table if not exists history (
+        id integer primary key autoincrement,
+        command_line text not null,
+        start_timestamp integer,
+        session_id integer,
+        hostname text,
+        cwd text,
+        duration_ms integer,
+        exit_status integer,
+        more_info text
+    ) strict;
+    create index if not exists idx_history_time on history(start_timestamp);
+    create index if not exists idx_history_cwd on history(cwd); -- suboptimal for many hosts
+    create index if not exists idx_history_exit_status on history(exit_status);
+    create index if not exists idx_history_cmd on history(command_line);
+    ",
+    // 2: the session index was never created because its name clashed with idx_history_cmd
+    "
+    create index idx_history_session on history(session_id);
+    ",
+    // 3: tags as a JSON array, notes and bookmarks
+    "
+    alter table history add column tags text;
+    alter table history add column note text;
+    alter table history add column bookmarked integer not null default 0;
+    create index idx_history_bookmarked on history(bookmarked) where bookmarked;
+    ",
+];
+
 /// A history that stores the values to an SQLite database.
 /// In addition to storing the command, the history can store an additional arbitrary HistoryEntryContext,
 /// to add information such as a timestamp, running directory, result...
 ///
 /// ## Required feature:
 /// `sqlite` or `sqlite-dynlib`
+///
+/// Searches with [`CommandLineSearch::FullText`] can use an FTS5 index, see
+/// [`SqliteBackedHistory::enable_full_text_index()`].
+///
+/// Databases of older versions are upgraded when they are opened. Databases upgraded by a
+/// newer version of reedline can't be opened, see
+/// [`ReedlineErrorVariants::HistoryDatabaseTooNew`].
 pub struct SqliteBackedHistory {
     db: rusqlite::Connection,
     session: Option<HistorySessionId>,
     session_timestamp: Option<chrono::DateTime<Utc>>,
+    full_text_index: bool,
 }
 
 fn deserialize_history_item(row: &rusqlite::Row) -> rusqlite::Result<HistoryItem> {
@@ -55,6 +110,21 @@ fn deserialize_history_item(row: &rusqlite::Row) -> rusqlite::Result<HistoryItem
                 })
             })
             .transpose()?,
+        tags: row
+            .get::<&str, Option<String>>("tags")?
+            .map(|tags| {
+                serde_json::from_str(&tags).map_err(|e| {
+                    rusqlite::Error::InvalidColumnType(
+                        0,
+                        format!("could not deserialize tags: {e}"),
+                        rusqlite::types::Type::Text,
+                    )
+                })
+            })
+            .transpose()?
+            .unwrap_or_default(),
+        note: row.get("note")?,
+        bookmarked: row.get("bookmarked")?,
     })
 }
 
@@ -64,8 +134,8 @@ impl History for SqliteBackedHistory {
             .db
             .prepare(
                 "insert into history
-                               (id,  start_timestamp,  command_line,  session_id,  hostname,  cwd,  duration_ms,  exit_status,  more_info)
-                        values (:id, :start_timestamp, :command_line, :session_id, :hostname, :cwd, :duration_ms, :exit_status, :more_info)
+                               (id,  start_timestamp,  command_line,  session_id,  hostname,  cwd,  duration_ms,  exit_status,  more_info,  tags,  note,  bookmarked)
+                        values (:id, :start_timestamp, :command_line, :session_id, :hostname, :cwd, :duration_ms, :exit_status, :more_info, :tags, :note, :bookmarked)
                     on conflict (history.id) do update set
                         start_timestamp = excluded.start_timestamp,
                         command_line = excluded.command_line,
@@ -74,7 +144,10 @@ impl History for SqliteBackedHistory {
                         cwd = excluded.cwd,
                         duration_ms = excluded.duration_ms,
                         exit_status = excluded.exit_status,
-                        more_info = excluded.more_info
+                        more_info = excluded.more_info,
+                        tags = excluded.tags,
+                        note = excluded.note,
+                        bookmarked = excluded.bookmarked
                     returning id",
             )
             .map_err(map_sqlite_err)?
@@ -88,7 +161,10 @@ impl History for SqliteBackedHistory {
                     ":cwd": entry.cwd,
                     ":duration_ms": entry.duration.map(|e| e.as_millis() as i64),
                     ":exit_status": entry.exit_status,
-                    ":more_info": entry.more_info.as_ref().map(|e| serde_json::to_string(e).unwrap())
+                    ":more_info": entry.more_info.as_ref().map(|e| serde_json::to_string(e).unwrap()),
+                    ":tags": (!entry.tags.is_empty()).then(|| serde_json::to_string(&entry.tags).unwrap()),
+                    ":note": entry.note,
+                    ":bookmarked": entry.bookmarked,
                 },
                 |row| row.get(0),
             )
@@ -108,8 +184,10 @@ impl History for SqliteBackedHistory {
     }
 
     fn count(&self, query: SearchQuery) -> Result<i64> {
+        validate_regex(&query)?;
         let (query, params) = self.construct_query(&query, "coalesce(count(*), 0)");
-        let params_borrow: Vec<(&str, &dyn ToSql)> = params.iter().map(|e| (e.0, &*e.1)).collect();
+        let params_borrow: Vec<(&str, &dyn ToSql)> =
+            params.iter().map(|e| (&*e.0, &*e.1)).collect();
         let result: i64 = self
             .db
             .prepare(&query)
@@ -120,8 +198,10 @@ impl History for SqliteBackedHistory {
     }
 
     fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>> {
+        validate_regex(&query)?;
         let (query, params) = self.construct_query(&query, "*");
-        let params_borrow: Vec<(&str, &dyn ToSql)> = params.iter().map(|e| (e.0, &*e.1)).collect();
+        let params_borrow: Vec<(&str, &dyn ToSql)> =
+            params.iter().map(|e| (&*e.0, &*e.1)).collect();
         let results: Vec<HistoryItem> = self
             .db
             .prepare(&query)
@@ -133,6 +213,23 @@ impl History for SqliteBackedHistory {
         Ok(results)
     }
 
+    fn search_iter(
+        &self,
+        query: SearchQuery,
+    ) -> Box<dyn Iterator<Item = Result<HistoryItem>> + '_> {
+        // Results ordered by the rank of the full-text index can't be paged by their id
+        let ranked_by_index = self.full_text_index
+            && matches!(
+                &query.filter.command_line,
+                Some(CommandLineSearch::FullText(text)) if !FullTextQuery::parse(text).is_empty()
+            );
+        if ranked_by_index {
+            Box::new(SearchPages::unpaged(self, query))
+        } else {
+            Box::new(SearchPages::new(self, query))
+        }
+    }
+
     fn update(
         &mut self,
         id: HistoryItemId,
@@ -180,6 +277,14 @@ impl History for SqliteBackedHistory {
         self.session
     }
 }
+/// Report invalid patterns like the other histories instead of as a database error
+fn validate_regex(query: &SearchQuery) -> Result<()> {
+    if let Some(CommandLineSearch::Regex(pattern)) = &query.filter.command_line {
+        search_regex(pattern)?;
+    }
+    Ok(())
+}
+
 fn map_sqlite_err(err: rusqlite::Error) -> ReedlineError {
     // TODO: better error mapping
     ReedlineError(ReedlineErrorVariants::HistoryDatabaseError(format!(
@@ -187,7 +292,114 @@ fn map_sqlite_err(err: rusqlite::Error) -> ReedlineError {
     )))
 }
 
-type BoxedNamedParams<'a> = Vec<(&'static str, Box<dyn ToSql + 'a>)>;
+/// Applies the missing migrations, each in its own transaction
+fn migrate(db: &mut Connection, migrations: &[&str]) -> Result<()> {
+    let user_version = |db: &Connection| -> Result<i32> {
+        db.pragma_query_value(None, "user_version", |r| r.get(0))
+            .map_err(map_sqlite_err)
+    };
+    let supported = migrations.len() as i32;
+    let current = user_version(db)?;
+    if current > supported {
+        return Err(ReedlineError(
+            ReedlineErrorVariants::HistoryDatabaseTooNew { current, supported },
+        ));
+    }
+
+    for (version, migration) in (1..).zip(migrations).skip(current.max(0) as usize) {
+        // `immediate` takes the write lock right away, so concurrently opened histories
+        // can't both apply the same migration
+        let tx = db
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(map_sqlite_err)?;
+        if user_version(&tx)? >= version {
+            continue;
+        }
+        tx.execute_batch(migration)
+            .and_then(|_| tx.pragma_update(None, "user_version", version))
+            .and_then(|_| tx.commit())
+            .map_err(|err| {
+                ReedlineError(ReedlineErrorVariants::HistoryDatabaseMigrationFailed {
+                    version,
+                    message: format!("{err:?}"),
+                })
+            })?;
+    }
+    Ok(())
+}
+
+type BoxedNamedParams<'a> = Vec<(Cow<'static, str>, Box<dyn ToSql + 'a>)>;
+
+/// Adds the parameter with a unique name and returns the name
+fn push_param<'a>(params: &mut BoxedNamedParams<'a>, value: Box<dyn ToSql + 'a>) -> String {
+    let name = format!(":param_{}", params.len());
+    params.push((name.clone().into(), value));
+    name
+}
+
+/// The SQL condition of the predicate
+///
+/// Every leaf evaluates to 0 or 1 and never to NULL, so like in the other histories a
+/// negated predicate matches the entries without the field.
+fn predicate_sql<'a>(predicate: &'a SearchPredicate, params: &mut BoxedNamedParams<'a>) -> String {
+    let millis = |duration: &Duration| duration.as_millis().min(i64::MAX as u128) as i64;
+    match predicate {
+        SearchPredicate::ExitStatus(range) => {
+            let start = push_param(params, Box::new(*range.start()));
+            let end = push_param(params, Box::new(*range.end()));
+            format!("coalesce(exit_status between {start} and {end}, 0)")
+        }
+        SearchPredicate::MinDuration(min) => {
+            let min = push_param(params, Box::new(millis(min)));
+            format!("coalesce(duration_ms >= {min}, 0)")
+        }
+        SearchPredicate::MaxDuration(max) => {
+            let max = push_param(params, Box::new(millis(max)));
+            format!("coalesce(duration_ms <= {max}, 0)")
+        }
+        SearchPredicate::HostnameGlob(glob) => {
+            let glob = push_param(params, Box::new(glob));
+            format!("glob_matches({glob}, hostname)")
+        }
+        SearchPredicate::CwdGlob(glob) => {
+            let glob = push_param(params, Box::new(glob));
+            format!("glob_matches({glob}, cwd)")
+        }
+        SearchPredicate::TimeOfDay { from, to } => {
+            let from = push_param(params, Box::new(from.num_seconds_from_midnight()));
+            let to = push_param(params, Box::new(to.num_seconds_from_midnight()));
+            format!("time_of_day_in_range(start_timestamp, {from}, {to})")
+        }
+        SearchPredicate::MoreInfo { path, value } => {
+            let path = push_param(params, Box::new(path));
+            let value = push_param(params, Box::new(value));
+            // `json_type` is NULL for a missing path but 'null' for a JSON null
+            format!(
+                "coalesce(json_type(more_info, {path}) is not null \
+                 and json_extract(more_info, {path}) is json_extract({value}, '$'), 0)"
+            )
+        }
+        SearchPredicate::Not(predicate) => format!("not ({})", predicate_sql(predicate, params)),
+        SearchPredicate::All(predicates) => join_predicates(predicates, " and ", "1", params),
+        SearchPredicate::Any(predicates) => join_predicates(predicates, " or ", "0", params),
+    }
+}
+
+fn join_predicates<'a>(
+    predicates: &'a [SearchPredicate],
+    operator: &str,
+    empty: &str,
+    params: &mut BoxedNamedParams<'a>,
+) -> String {
+    if predicates.is_empty() {
+        return empty.to_string();
+    }
+    let conditions: Vec<String> = predicates
+        .iter()
+        .map(|predicate| predicate_sql(predicate, params))
+        .collect();
+    format!("({})", conditions.join(operator))
+}
 
 impl SqliteBackedHistory {
     /// Creates a new history with an associated history file.
@@ -218,7 +430,7 @@ impl SqliteBackedHistory {
     }
     /// initialize a new database / migrate an existing one
     fn from_connection(
-        db: Connection,
+        mut db: Connection,
         session: Option<HistorySessionId>,
         session_timestamp: Option<chrono::DateTime<Utc>>,
     ) -> Result<Self> {
@@ -233,47 +445,259 @@ impl SqliteBackedHistory {
             .map_err(map_sqlite_err)?;
         db.pragma_update(None, "application_id", SQLITE_APPLICATION_ID)
             .map_err(map_sqlite_err)?;
-        let db_version: i32 = db
+        db.create_scalar_function(
+            "fuzzy_match",
+            2,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| {
+                let candidate = ctx.get::<String>(0)?;
+                let pattern = ctx.get::<String>(1)?;
+                Ok(FuzzyMatcher::default().is_match(&candidate, &pattern))
+            },
+        )
+        .map_err(map_sqlite_err)?;
+        db.create_scalar_function(
+            "full_text_match",
+            2,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| {
+                let candidate = ctx.get::<String>(0)?;
+                let query = ctx.get::<String>(1)?;
+                Ok(FullTextQuery::parse(&query).matches(&candidate))
+            },
+        )
+        .map_err(map_sqlite_err)?;
+        // `x regexp y` calls `regexp(y, x)`, the compiled pattern is cached for the statement
+        db.create_scalar_function(
+            "regexp",
+            2,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| {
+                type BoxError = Box<dyn std::error::Error + Send + Sync>;
+                let regex =
+                    ctx.get_or_create_aux(0, |pattern| -> std::result::Result<Regex, BoxError> {
+                        Ok(search_regex(pattern.as_str()?)?)
+                    })?;
+                let text = ctx.get::<String>(1)?;
+                Ok(regex.is_match(&text))
+            },
+        )
+        .map_err(map_sqlite_err)?;
+        db.create_scalar_function(
+            "glob_matches",
+            2,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| {
+                let glob = ctx.get::<String>(0)?;
+                let text = ctx.get::<Option<String>>(1)?;
+                Ok(text.map_or(false, |text| glob_matches(&glob, &text)))
+            },
+        )
+        .map_err(map_sqlite_err)?;
+        // Not deterministic, the local time zone may change
+        db.create_scalar_function(
+            "time_of_day_in_range",
+            3,
+            FunctionFlags::SQLITE_UTF8,
+            |ctx| {
+                let timestamp = ctx.get::<Option<i64>>(0)?;
+                let from = ctx.get::<u32>(1)?;
+                let to = ctx.get::<u32>(2)?;
+                Ok(timestamp
+                    .and_then(|timestamp| Utc.timestamp_millis_opt(timestamp).single())
+                    .map_or(false, |timestamp| {
+                        time_of_day_in_range(local_seconds_from_midnight(timestamp), from, to)
+                    }))
+            },
+        )
+        .map_err(map_sqlite_err)?;
+        db.create_scalar_function(
+            "frecency_score",
+            6,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| {
+                let stats = FrecencyStats {
+                    count: ctx.get(0)?,
+                    age: ctx.get(1)?,
+                    cwd_count: ctx.get(2)?,
+                    session_count: ctx.get(3)?,
+                    successes: ctx.get(4)?,
+                    with_status: ctx.get(5)?,
+                };
+                Ok(stats.score())
+            },
+        )
+        .map_err(map_sqlite_err)?;
+        migrate(&mut db, MIGRATIONS)?;
+        let full_text_index = db
             .query_row(
-                "SELECT user_version FROM pragma_user_version",
+                "select count(*) from sqlite_master where type = 'table' and name = 'history_fts'",
                 params![],
-                |r| r.get(0),
+                |r| r.get::<_, i64>(0),
             )
-            .map_err(map_sqlite_err)?;
-        if db_version != 0 {
-            return Err(ReedlineError(ReedlineErrorVariants::HistoryDatabaseError(
-                format!("Unknown database version {db_version}"),
-            )));
-        }
-        db.execute_batch(
-            "
-        create table if not exists history (
-            id integer primary key autoincrement,
-            command_line text not null,
-            start_timestamp integer,
-            session_id integer,
-            hostname text,
-            cwd text,
-            duration_ms integer,
-            exit_status integer,
-            more_info text
-        ) strict;
-        create index if not exists idx_history_time on history(start_timestamp);
-        create index if not exists idx_history_cwd on history(cwd); -- suboptimal for many hosts
-        create index if not exists idx_history_exit_status on history(exit_status);
-        create index if not exists idx_history_cmd on history(command_line);
-        create index if not exists idx_history_cmd on history(session_id);
-        -- todo: better indexes
-        ",
-        )
-        .map_err(map_sqlite_err)?;
+            .map_err(map_sqlite_err)?
+            > 0;
         Ok(SqliteBackedHistory {
             db,
             session,
             session_timestamp,
+            full_text_index,
         })
     }
 
+    /// Creates the FTS5 index used by [`CommandLineSearch::FullText`] searches
+    ///
+    /// The index is built from the existing entries and kept up to date by triggers, so it is
+    /// also used when the database is opened again. Without it full-text searches scan all
+    /// entries and the results aren't ordered by relevance.
+    ///
+    /// Returns an error if SQLite was built without FTS5, which the `sqlite` feature includes.
+    pub fn enable_full_text_index(&mut self) -> Result<()> {
+        if self.full_text_index {
+            return Ok(());
+        }
+        self.db
+            .execute_batch(
+                "
+        begin;
+        create virtual table history_fts using fts5(
+            command_line,
+            content = 'history',
+            content_rowid = 'id',
+            tokenize = 'unicode61 remove_diacritics 0'
+        );
+        create trigger history_fts_insert after insert on history begin
+            insert into history_fts(rowid, command_line) values (new.id, new.command_line);
+        end;
+        create trigger history_fts_delete after delete on history begin
+            insert into history_fts(history_fts, rowid, command_line)
+                values ('delete', old.id, old.command_line);
+        end;
+        create trigger history_fts_update after update of command_line on history begin
+            insert into history_fts(history_fts, rowid, command_line)
+                values ('delete', old.id, old.command_line);
+            insert into history_fts(rowid, command_line) values (new.id, new.command_line);
+        end;
+        insert into history_fts(history_fts) values ('rebuild');
+        commit;
+        ",
+            )
+            .map_err(|err| {
+                let _ = self.db.execute_batch("rollback");
+                map_sqlite_err(err)
+            })?;
+        self.full_text_index = true;
+        Ok(())
+    }
+
+    /// Whether [`CommandLineSearch::FullText`] searches use the FTS5 index
+    pub fn has_full_text_index(&self) -> bool {
+        self.full_text_index
+    }
+
+    /// Applies the retention rules right after opening the history
+    ///
+    /// See [`SqliteBackedHistory::apply_retention()`] to prune it on demand instead.
+    pub fn with_retention(mut self, retention: &HistoryRetention) -> Result<Self> {
+        self.apply_retention(retention)?;
+        Ok(self)
+    }
+
+    /// Removes the entries not kept by the retention rules
+    ///
+    /// The freed space is only returned to the file system by
+    /// [`SqliteBackedHistory::compact()`].
+    ///
+    /// Returns the number of removed entries.
+    pub fn apply_retention(&mut self, retention: &HistoryRetention) -> Result<usize> {
+        let now = Utc::now().timestamp_millis();
+        let cutoff =
+            |age: Duration| now.saturating_sub(age.as_millis().min(i64::MAX as u128) as i64);
+        let limit = |n: usize| n.min(i64::MAX as usize) as i64;
+
+        let keep_bookmarked = retention.keep_bookmarked;
+
+        let tx = self.db.transaction().map_err(map_sqlite_err)?;
+        let mut removed = 0;
+        if let Some(max_failed_age) = retention.max_failed_age {
+            removed += tx
+                .execute(
+                    "delete from history
+                    where exit_status != 0 and start_timestamp < :cutoff
+                        and not (:keep_bookmarked and bookmarked)",
+                    named_params! {
+                        ":cutoff": cutoff(max_failed_age),
+                        ":keep_bookmarked": keep_bookmarked,
+                    },
+                )
+                .map_err(map_sqlite_err)?;
+        }
+        if let Some(max_age) = retention.max_age {
+            removed += tx
+                .execute(
+                    "delete from history
+                    where start_timestamp < :cutoff and not (:keep_bookmarked and bookmarked)",
+                    named_params! {
+                        ":cutoff": cutoff(max_age),
+                        ":keep_bookmarked": keep_bookmarked,
+                    },
+                )
+                .map_err(map_sqlite_err)?;
+        }
+        if let Some(max_per_command) = retention.max_per_command {
+            removed += tx
+                .execute(
+                    "delete from history where id in (
+                        select id from (
+                            select id, row_number() over (
+                                partition by command_line order by id desc
+                            ) as n
+                            from history
+                            where not (:keep_bookmarked and bookmarked)
+                        )
+                        where n > :max
+                    )",
+                    named_params! {
+                        ":max": limit(max_per_command),
+                        ":keep_bookmarked": keep_bookmarked,
+                    },
+                )
+                .map_err(map_sqlite_err)?;
+        }
+        if let Some(max_entries) = retention.max_entries {
+            removed += tx
+                .execute(
+                    "delete from history
+                    where not (:keep_bookmarked and bookmarked) and id not in (
+                        select id from history
+                        where not (:keep_bookmarked and bookmarked)
+                        order by id desc limit :max
+                    )",
+                    named_params! {
+                        ":max": limit(max_entries),
+                        ":keep_bookmarked": keep_bookmarked,
+                    },
+                )
+                .map_err(map_sqlite_err)?;
+        }
+        tx.commit().map_err(map_sqlite_err)?;
+        Ok(removed)
+    }
+
+    /// Applies the retention rules and rebuilds the database file with `VACUUM`
+    ///
+    /// Besides shrinking the file this ensures the removed entries are erased instead of
+    /// being marked as available for reuse.
+    ///
+    /// Returns the number of removed entries.
+    pub fn compact(&mut self, retention: &HistoryRetention) -> Result<usize> {
+        let removed = self.apply_retention(retention)?;
+        self.db
+            .execute("VACUUM", params![])
+            .map_err(map_sqlite_err)?;
+        Ok(removed)
+    }
+
     fn construct_query<'a>(
         &self,
         query: &'a SearchQuery,
@@ -284,23 +708,27 @@ impl SqliteBackedHistory {
             SearchDirection::Forward => (true, "asc"),
             SearchDirection::Backward => (false, "desc"),
         };
-        let mut wheres = Vec::new();
         let mut params: BoxedNamedParams = Vec::new();
+        let predicate = (query.filter.predicate.as_ref())
+            .map(|predicate| predicate_sql(predicate, &mut params));
+        let mut wheres = Vec::new();
+        let mut from = "history";
+        let mut order = format!("id {asc}");
         if let Some(start) = query.start_time {
             wheres.push(if is_asc {
-                "timestamp_start > :start_time"
+                "start_timestamp > :start_time"
             } else {
-                "timestamp_start < :start_time"
+                "start_timestamp < :start_time"
             });
-            params.push((":start_time", Box::new(start.timestamp_millis())));
+            params.push((":start_time".into(), Box::new(start.timestamp_millis())));
         }
         if let Some(end) = query.end_time {
             wheres.push(if is_asc {
-                ":end_time >= timestamp_start"
+                ":end_time >= start_timestamp"
             } else {
-                ":end_time <= timestamp_start"
+                ":end_time <= start_timestamp"
             });
-            params.push((":end_time", Box::new(end.timestamp_millis())));
+            params.push((":end_time".into(), Box::new(end.timestamp_millis())));
         }
         if let Some(start) = query.start_id {
             wheres.push(if is_asc {
@@ -308,7 +736,7 @@ impl SqliteBackedHistory {
             } else {
                 "id < :start_id"
             });
-            params.push((":start_id", Box::new(start.0)));
+            params.push((":start_id".into(), Box::new(start.0)));
         }
         if let Some(end) = query.end_id {
             wheres.push(if is_asc {
@@ -316,11 +744,13 @@ impl SqliteBackedHistory {
             } else {
                 ":end_id <= id"
             });
-            params.push((":end_id", Box::new(end.0)));
+            params.push((":end_id".into(), Box::new(end.0)));
         }
+        // The remaining conditions decide which entries match, the ones above bound the results
+        let range_wheres = wheres.len();
         let limit = match query.limit {
             Some(l) => {
-                params.push((":limit", Box::new(l)));
+                params.push((":limit".into(), Box::new(l)));
                 "limit :limit"
             }
             None => "",
@@ -329,35 +759,59 @@ impl SqliteBackedHistory {
             match command_line {
                 CommandLineSearch::Exact(e) => {
                     wheres.push("command_line == :command_line");
-                    params.push((":command_line", Box::new(e)));
+                    params.push((":command_line".into(), Box::new(e)));
                 }
                 CommandLineSearch::Prefix(prefix) => {
                     wheres.push("instr(command_line, :command_line) == 1");
-                    params.push((":command_line", Box::new(prefix)));
+                    params.push((":command_line".into(), Box::new(prefix)));
                 }
                 CommandLineSearch::Substring(cont) => {
                     wheres.push("instr(command_line, :command_line) >= 1");
-                    params.push((":command_line", Box::new(cont)));
+                    params.push((":command_line".into(), Box::new(cont)));
+                }
+                CommandLineSearch::Fuzzy(pattern) => {
+                    wheres.push("fuzzy_match(command_line, :command_line)");
+                    params.push((":command_line".into(), Box::new(pattern)));
+                }
+                CommandLineSearch::Regex(pattern) => {
+                    wheres.push("command_line regexp :command_line");
+                    params.push((":command_line".into(), Box::new(pattern)));
+                }
+                CommandLineSearch::FullText(text) => {
+                    let full_text = FullTextQuery::parse(text);
+                    if full_text.is_empty() {
+                        // Matches every command line
+                    } else if self.full_text_index {
+                        // The subquery only exposes the id and rank to keep the columns unique
+                        from = "history join (select rowid as fts_id, rank as fts_rank \
+                                from history_fts where history_fts match :command_line) \
+                                on fts_id = history.id";
+                        order = format!("fts_rank, {order}");
+                        params.push((":command_line".into(), Box::new(full_text.to_fts5())));
+                    } else {
+                        wheres.push("full_text_match(command_line, :command_line)");
+                        params.push((":command_line".into(), Box::new(text)));
+                    }
                 }
             };
         }
 
         if let Some(str) = &query.filter.not_command_line {
             wheres.push("command_line != :not_cmd");
-            params.push((":not_cmd", Box::new(str)));
+            params.push((":not_cmd".into(), Box::new(str)));
         }
         if let Some(hostname) = &query.filter.hostname {
             wheres.push("hostname = :hostname");
-            params.push((":hostname", Box::new(hostname)));
+            params.push((":hostname".into(), Box::new(hostname)));
         }
         if let Some(cwd_exact) = &query.filter.cwd_exact {
             wheres.push("cwd = :cwd");
-            params.push((":cwd", Box::new(cwd_exact)));
+            params.push((":cwd".into(), Box::new(cwd_exact)));
         }
         if let Some(cwd_prefix) = &query.filter.cwd_prefix {
             wheres.push("cwd like :cwd_like");
             let cwd_like = format!("{cwd_prefix}%");
-            params.push((":cwd_like", Box::new(cwd_like)));
+            params.push((":cwd_like".into(), Box::new(cwd_like)));
         }
         if let Some(exit_successful) = query.filter.exit_successful {
             if exit_successful {
@@ -366,6 +820,20 @@ impl SqliteBackedHistory {
                 wheres.push("exit_status != 0");
             }
         }
+        if let Some(predicate) = &predicate {
+            wheres.push(predicate);
+        }
+        if let Some(tag) = &query.filter.tag {
+            wheres.push("exists (select 1 from json_each(history.tags) where value = :tag)");
+            params.push((":tag".into(), Box::new(tag)));
+        }
+        if let Some(bookmarked) = query.filter.bookmarked {
+            if bookmarked {
+                wheres.push("bookmarked = 1");
+            } else {
+                wheres.push("bookmarked = 0");
+            }
+        }
         if let (Some(session_id), Some(session_timestamp)) =
             (query.filter.session, self.session_timestamp)
         {
@@ -373,23 +841,399 @@ impl SqliteBackedHistory {
             // - that have the same session_id, or
             // - were executed before our session started
             wheres.push("(session_id = :session_id OR start_timestamp < :session_timestamp)");
-            params.push((":session_id", Box::new(session_id)));
+            params.push((":session_id".into(), Box::new(session_id)));
             params.push((
-                ":session_timestamp",
+                ":session_timestamp".into(),
                 Box::new(session_timestamp.timestamp_millis()),
             ));
         }
-        let mut wheres = wheres.join(" and ");
-        if wheres.is_empty() {
-            wheres = "true".to_string();
+        let join_wheres = |wheres: &[&str]| {
+            if wheres.is_empty() {
+                "true".to_string()
+            } else {
+                wheres.join(" and ")
+            }
+        };
+        // The latest entry of every command line among all matching ones, regardless of the
+        // bounds, so that pages continuing after the last id stay consistent
+        let distinct_wheres = (query.filter.distinct
+            && query.ranking == SearchRanking::Chronological)
+            .then(|| join_wheres(&wheres[range_wheres..]));
+        let mut wheres = join_wheres(&wheres);
+        if let Some(filter_wheres) = distinct_wheres {
+            wheres = format!(
+                "{wheres} and history.id in ( \
+                    SELECT max(history.id) FROM {from} \
+                    WHERE ({filter_wheres}) \
+                    GROUP BY command_line)"
+            );
         }
-        let query = format!(
-            "SELECT {select_expression} \
-             FROM history \
-             WHERE ({wheres}) \
-             ORDER BY id {asc} \
-             {limit}"
-        );
+        let query = match &query.ranking {
+            SearchRanking::Chronological => format!(
+                "SELECT {select_expression} \
+                 FROM {from} \
+                 WHERE ({wheres}) \
+                 ORDER BY {order} \
+                 {limit}"
+            ),
+            SearchRanking::Frecency(context) => {
+                params.push((":rank_cwd".into(), Box::new(&context.cwd)));
+                params.push((":rank_session".into(), Box::new(context.session)));
+                // Aggregate the matching entries per command line and keep the latest of each
+                format!(
+                    "WITH matching AS ( \
+                        SELECT history.id AS id, command_line, cwd, session_id, exit_status, \
+                            row_number() OVER (ORDER BY history.id DESC) - 1 AS age \
+                        FROM {from} \
+                        WHERE ({wheres})), \
+                     ranked AS ( \
+                        SELECT max(id) AS ranked_id, frecency_score( \
+                            count(*), \
+                            min(age), \
+                            sum(coalesce(cwd = :rank_cwd, 0)), \
+                            sum(coalesce(session_id = :rank_session, 0)), \
+                            sum(coalesce(exit_status = 0, 0)), \
+                            count(exit_status)) AS score \
+                        FROM matching \
+                        GROUP BY command_line) \
+                     SELECT {select_expression} \
+                     FROM ranked JOIN history ON history.id = ranked_id \
+                     ORDER BY score DESC, ranked_id DESC \
+                     {limit}"
+                )
+            }
+        };
         (query, params)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// The schema of databases created before they were versioned
+    const FIXTURE_VERSION_0: &str = "
+        create table history (
+            id integer primary key autoincrement,
+            command_line text not null,
+            start_timestamp integer,
+            session_id integer,
+            hostname text,
+            cwd text,
+            duration_ms integer,
+            exit_status integer,
+            more_info text
+        ) strict;
+        create index idx_history_time on history(start_timestamp);
+        create index idx_history_cwd on history(cwd);
+        create index idx_history_exit_status on history(exit_status);
+        create index idx_history_cmd on history(command_line);
+        insert into history (command_line, session_id, cwd, exit_status)
+            values ('ls', 1, '/home', 0), ('cd /tmp', 1, '/home', 0);
+    ";
+
+    /// A database migrated to version 1
+    const FIXTURE_VERSION_1: &str = "
+        create table history (
+            id integer primary key autoincrement,
+            command_line text not null,
+            start_timestamp integer,
+            session_id integer,
+            hostname text,
+            cwd text,
+            duration_ms integer,
+            exit_status integer,
+            more_info text
+        ) strict;
+        insert into history (command_line, session_id) values ('pwd', 2);
+        pragma user_version = 1;
+    ";
+
+    fn open_fixture(fixture: &str) -> Result<SqliteBackedHistory> {
+        let db = Connection::open_in_memory().map_err(map_sqlite_err)?;
+        db.execute_batch(fixture).map_err(map_sqlite_err)?;
+        SqliteBackedHistory::from_connection(db, None, None)
+    }
+
+    fn user_version(history: &SqliteBackedHistory) -> i32 {
+        history
+            .db
+            .pragma_query_value(None, "user_version", |r| r.get(0))
+            .unwrap()
+    }
+
+    fn has_index(history: &SqliteBackedHistory, name: &str) -> bool {
+        history
+            .db
+            .query_row(
+                "select count(*) from sqlite_master where type = 'index' and name = ?",
+                params![name],
+                |r| r.get::<_, i64>(0),
+            )
+            .unwrap()
+            > 0
+    }
+
+    fn command_lines(history: &SqliteBackedHistory) -> Result<Vec<String>> {
+        let items = history.search(SearchQuery::everything(SearchDirection::Forward, None))?;
+        Ok(items.into_iter().map(|item| item.command_line).collect())
+    }
+
+    #[test]
+    fn new_database_has_the_latest_version() -> Result<()> {
+        let history = SqliteBackedHistory::in_memory()?;
+        assert_eq!(user_version(&history), MIGRATIONS.len() as i32);
+        assert!(has_index(&history, "idx_history_session"));
+        Ok(())
+    }
+
+    #[test]
+    fn migrates_unversioned_database() -> Result<()> {
+        let mut history = open_fixture(FIXTURE_VERSION_0)?;
+        assert_eq!(user_version(&history), MIGRATIONS.len() as i32);
+        assert!(has_index(&history, "idx_history_session"));
+        assert_eq!(command_lines(&history)?, ["ls", "cd /tmp"]);
+
+        history.save(HistoryItem::from_command_line("pwd"))?;
+        assert_eq!(command_lines(&history)?, ["ls", "cd /tmp", "pwd"]);
+        Ok(())
+    }
+
+    #[test]
+    fn migrates_from_intermediate_version() -> Result<()> {
+        let history = open_fixture(FIXTURE_VERSION_1)?;
+        assert_eq!(user_version(&history), MIGRATIONS.len() as i32);
+        assert!(has_index(&history, "idx_history_session"));
+        // Migration 1 wasn't applied again, it would have added this index
+        assert!(!has_index(&history, "idx_history_cmd"));
+        assert_eq!(command_lines(&history)?, ["pwd"]);
+        Ok(())
+    }
+
+    #[test]
+    fn reopening_a_file_keeps_the_version() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("history.sqlite3");
+        {
+            let mut history = SqliteBackedHistory::with_file(file.clone(), None, None)?;
+            history.save(HistoryItem::from_command_line("ls"))?;
+        }
+        let history = SqliteBackedHistory::with_file(file, None, None)?;
+        assert_eq!(user_version(&history), MIGRATIONS.len() as i32);
+        assert_eq!(command_lines(&history)?, ["ls"]);
+        Ok(())
+    }
+
+    #[test]
+    fn stores_tags_notes_and_bookmarks() -> Result<()> {
+        let mut history = open_fixture(FIXTURE_VERSION_0)?;
+        let saved = history.save(HistoryItem {
+            tags: vec!["net".to_string(), "debug".to_string()],
+            note: Some("check the connection".to_string()),
+            bookmarked: true,
+            ..HistoryItem::from_command_line("ping localhost")
+        })?;
+        assert_eq!(history.load(saved.id.unwrap())?, saved);
+        // Entries from before the migration have no tags and aren't bookmarked
+        assert_eq!(
+            history.load(HistoryItemId::new(1))?.tags,
+            Vec::<String>::new()
+        );
+
+        let mut query = SearchQuery::everything(SearchDirection::Forward, None);
+        query.filter.tag = Some("debug".to_string());
+        assert_eq!(history.search(query)?, [saved]);
+
+        let mut query = SearchQuery::everything(SearchDirection::Forward, None);
+        query.filter.bookmarked = Some(false);
+        let items = history.search(query)?;
+        let command_lines: Vec<String> = items.into_iter().map(|item| item.command_line).collect();
+        assert_eq!(command_lines, ["ls", "cd /tmp"]);
+        Ok(())
+    }
+
+    fn predicate_history() -> Result<SqliteBackedHistory> {
+        let mut history = SqliteBackedHistory::in_memory()?;
+        let evening = chrono::Local
+            .with_ymd_and_hms(2024, 1, 1, 19, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        for (command_line, hostname, cwd, secs, exit_status) in [
+            ("make", "build-01", "/home/me/project", 90, Some(2)),
+            ("make test", "build-02", "/home/me/project", 5, Some(0)),
+            ("ls", "laptop", "/tmp", 0, Some(0)),
+            ("vim", "laptop", "/home/me", 600, None),
+        ] {
+            history.save(HistoryItem {
+                hostname: Some(hostname.to_string()),
+                cwd: Some(cwd.to_string()),
+                duration: Some(Duration::from_secs(secs)),
+                exit_status,
+                start_timestamp: Some(evening),
+                ..HistoryItem::from_command_line(command_line)
+            })?;
+        }
+        history
+            .db
+            .execute_batch(
+                r#"update history set more_info = '{"mode":"insert","tabs":[1,2]}' where id = 4;
+                update history set more_info = 'null' where id = 3;"#,
+            )
+            .map_err(map_sqlite_err)?;
+        Ok(history)
+    }
+
+    fn search_predicate(
+        history: &SqliteBackedHistory,
+        predicate: SearchPredicate,
+    ) -> Result<Vec<String>> {
+        let mut query = SearchQuery::everything(SearchDirection::Forward, None);
+        query.filter.predicate = Some(predicate);
+        let items = history.search(query)?;
+        Ok(items.into_iter().map(|item| item.command_line).collect())
+    }
+
+    #[test]
+    fn filters_by_predicates() -> Result<()> {
+        let history = predicate_history()?;
+        let more_info = |path: &str, value: &str| SearchPredicate::MoreInfo {
+            path: path.to_string(),
+            value: value.to_string(),
+        };
+        let time = |hour| chrono::NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+
+        for (predicate, expected) in [
+            (SearchPredicate::ExitStatus(1..=255), vec!["make"]),
+            (!SearchPredicate::exit_code(0), vec!["make", "vim"]),
+            (
+                SearchPredicate::MinDuration(Duration::from_secs(60)),
+                vec!["make", "vim"],
+            ),
+            (
+                SearchPredicate::MaxDuration(Duration::from_secs(5)),
+                vec!["make test", "ls"],
+            ),
+            (
+                SearchPredicate::HostnameGlob("build-??".to_string()),
+                vec!["make", "make test"],
+            ),
+            (
+                SearchPredicate::CwdGlob("/home/*".to_string())
+                    .and(!SearchPredicate::CwdGlob("*/project".to_string())),
+                vec!["vim"],
+            ),
+            (
+                SearchPredicate::exit_code(0)
+                    .and(SearchPredicate::HostnameGlob("laptop".to_string()))
+                    .or(SearchPredicate::exit_code(2)),
+                vec!["make", "ls"],
+            ),
+            (
+                SearchPredicate::TimeOfDay {
+                    from: time(18),
+                    to: time(6),
+                },
+                vec!["make", "make test", "ls", "vim"],
+            ),
+            (
+                SearchPredicate::TimeOfDay {
+                    from: time(6),
+                    to: time(18),
+                },
+                vec![],
+            ),
+            (more_info("$.mode", "\"insert\""), vec!["vim"]),
+            (more_info("$.tabs[1]", "2"), vec!["vim"]),
+            (more_info("$.tabs", "[1,2]"), vec!["vim"]),
+            (more_info("$", "null"), vec!["ls"]),
+            (
+                !more_info("$.mode", "\"insert\""),
+                vec!["make", "make test", "ls"],
+            ),
+            (SearchPredicate::Any(vec![]), vec![]),
+        ] {
+            assert_eq!(
+                search_predicate(&history, predicate.clone())?,
+                expected,
+                "{predicate:?}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn filters_by_start_time() -> Result<()> {
+        let mut history = SqliteBackedHistory::in_memory()?;
+        for (command_line, timestamp) in [("first", 100), ("second", 200), ("third", 300)] {
+            history.save(HistoryItem {
+                start_timestamp: Utc.timestamp_opt(timestamp, 0).single(),
+                ..HistoryItem::from_command_line(command_line)
+            })?;
+        }
+        let query = SearchQuery {
+            start_time: Utc.timestamp_opt(100, 0).single(),
+            end_time: Utc.timestamp_opt(200, 0).single(),
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        };
+        let items = history.search(query)?;
+        assert_eq!(items[..], [history.load(HistoryItemId::new(2))?]);
+
+        let query = SearchQuery {
+            start_time: Utc.timestamp_opt(300, 0).single(),
+            end_time: Utc.timestamp_opt(100, 0).single(),
+            ..SearchQuery::everything(SearchDirection::Backward, None)
+        };
+        let items = history.search(query)?;
+        assert_eq!(
+            items[..],
+            [
+                history.load(HistoryItemId::new(2))?,
+                history.load(HistoryItemId::new(1))?
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_newer_database() {
+        let newer = format!("pragma user_version = {};", MIGRATIONS.len() + 1);
+        let result = open_fixture(&newer);
+        assert!(matches!(
+            result,
+            Err(ReedlineError(ReedlineErrorVariants::HistoryDatabaseTooNew {
+                current,
+                supported,
+            })) if current == MIGRATIONS.len() as i32 + 1 && supported == MIGRATIONS.len() as i32
+        ));
+    }
+
+    #[test]
+    fn failed_migration_is_rolled_back() {
+        let mut db = Connection::open_in_memory().unwrap();
+        let migrations = [
+            "create table first (id integer);",
+            "create table second (id integer); create table first (id integer);",
+        ];
+        let result = migrate(&mut db, &migrations);
+        assert!(matches!(
+            result,
+            Err(ReedlineError(
+                ReedlineErrorVariants::HistoryDatabaseMigrationFailed { version: 2, .. }
+            ))
+        ));
+
+        let version: i32 = db
+            .pragma_query_value(None, "user_version", |r| r.get(0))
+            .unwrap();
+        assert_eq!(version, 1);
+        let tables: i64 = db
+            .query_row(
+                "select count(*) from sqlite_master where name = 'second'",
+                params![],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(tables, 0);
+    }
+}
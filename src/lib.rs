@@ -190,7 +190,9 @@
 //! - `bashisms`: Enable support for special text sequences that recall components from the history. e.g. `!!` and `!$`. For use in shells like `bash` or [`nushell`](https://nushell.sh).
 //! - `sqlite`: Provides the `SqliteBackedHistory` to store richer information in the history. Statically links the required sqlite version.
 //! - `sqlite-dynlib`: Alternative to the feature `sqlite`. Will not statically link. Requires `sqlite >= 3.38` to link dynamically!
+//! - `jsonl`: Provides the `JsonlBackedHistory` that stores all `HistoryItem` fields in a JSON-lines file, an alternative to `sqlite` without native dependencies.
 //! - `external_printer`: **Experimental:** Thread-safe `ExternalPrinter` handle to print lines from concurrently running threads.
+//! - `keybindings_config`: Provides the `KeybindingsConfig` to load keybindings from TOML or JSON and to save them.
 //!
 //! ## Are we prompt yet? (Development status)
 //!
@@ -237,30 +239,37 @@
 mod core_editor;
 pub use core_editor::Editor;
 pub use core_editor::LineBuffer;
+pub use core_editor::StructuredEditing;
+pub use core_editor::{KillRing, DEFAULT_KILL_RING_SIZE};
 
 mod enums;
 pub use enums::{
-    EditCommand, MouseButton, ReedlineEvent, ReedlineRawEvent, Signal, TextObject, TextObjectScope,
-    TextObjectType, UndoBehavior,
+    EditCommand, HistorySearchFilter, MouseButton, ReedlineEvent, ReedlineRawEvent, Signal,
+    TextObject, TextObjectScope, TextObjectType, UndoBehavior,
 };
 
 mod painting;
 pub use painting::{Painter, StyledText};
 
 mod engine;
-pub use engine::{MouseClickMode, Reedline};
+pub use engine::{HistorySharing, MouseClickMode, Reedline};
 
 mod result;
 pub use result::{ReedlineError, ReedlineErrorVariants, Result};
 
 mod history;
-#[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
-pub use history::SqliteBackedHistory;
+#[cfg(feature = "jsonl")]
+pub use history::JsonlBackedHistory;
 pub use history::{
-    CommandLineSearch, FileBackedHistory, History, HistoryItem, HistoryItemExtraInfo,
-    HistoryItemId, HistoryNavigationQuery, HistorySessionId, IgnoreAllExtraInfo, SearchDirection,
-    SearchFilter, SearchQuery, HISTORY_SIZE,
+    default_secret_detectors, CommandLineSearch, EntropySecretDetector, FileBackedHistory,
+    FrecencyContext, History, HistoryDuplicates, HistoryIgnorePattern, HistoryItem,
+    HistoryItemExtraInfo, HistoryItemId, HistoryNavigationQuery, HistoryPolicy, HistoryRedaction,
+    HistorySessionId, IgnoreAllExtraInfo, LayeredHistory, PatternSecretDetector, RedactionAction,
+    SearchDirection, SearchFilter, SearchPredicate, SearchQuery, SearchRanking, SecretDetector,
+    ShellHistoryFormat, HISTORY_SIZE,
 };
+#[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+pub use history::{HistoryRetention, SqliteBackedHistory};
 
 mod prompt;
 pub use prompt::{
@@ -270,27 +279,31 @@ pub use prompt::{
 
 mod edit_mode;
 pub use edit_mode::{
-    default_emacs_keybindings, default_vi_insert_keybindings, default_vi_normal_keybindings,
-    CursorConfig, EditMode, Emacs, Keybindings, Vi,
+    default_emacs_keybindings, default_history_search_keybindings, default_vi_insert_keybindings,
+    default_vi_normal_keybindings, CursorConfig, EditMode, Emacs, KeyCombination, KeySequenceMatch,
+    Keybindings, Vi,
 };
+#[cfg(feature = "keybindings_config")]
+pub use edit_mode::{KeybindingMode, KeybindingsConfig};
 
 mod highlighter;
 pub use highlighter::{ExampleHighlighter, Highlighter, SimpleMatchHighlighter};
 
 mod completion;
-pub use completion::{Completer, DefaultCompleter, Span, Suggestion};
+pub use completion::{Completer, DefaultCompleter, FuzzyMatch, FuzzyMatcher, Span, Suggestion};
 
 mod hinter;
 pub use hinter::CwdAwareHinter;
-pub use hinter::{DefaultHinter, Hinter};
+pub use hinter::{DefaultHinter, FrecencyHinter, Hinter};
 
 mod validator;
 pub use validator::{DefaultValidator, ValidationResult, Validator};
 
 mod menu;
 pub use menu::{
-    menu_functions, ColumnarMenu, DescriptionMenu, DescriptionMode, IdeMenu, ListMenu, Menu,
-    MenuBuilder, MenuEvent, MenuSettings, MenuTextStyle, ReedlineMenu, TraversalDirection,
+    menu_functions, ColumnarMenu, DescriptionMenu, DescriptionMode, IdeMenu, ListMenu,
+    MatchAlgorithm, Menu, MenuBuilder, MenuEvent, MenuSettings, MenuTextStyle, ReedlineMenu,
+    TraversalDirection,
 };
 
 mod terminal_extensions;
@@ -299,6 +312,9 @@ pub use terminal_extensions::semantic_prompt::{
     Osc133ClickEventsMarkers, Osc133Markers, Osc633Markers, PromptKind, SemanticPromptMarkers,
 };
 
+mod terminal_backend;
+pub use terminal_backend::{CrosstermBackend, HeadlessBackend, TerminalBackend};
+
 mod utils;
 
 mod external_printer;
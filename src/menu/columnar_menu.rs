@@ -564,7 +564,10 @@ impl Menu for ColumnarMenu {
 
         let (values, base_ranges) = completer.complete_with_base_ranges(&input, pos);
 
-        self.values = values;
+        self.values = self
+            .settings
+            .match_algorithm
+            .apply(editor.get_buffer(), values);
         self.display_widths = self
             .values
             .iter()
@@ -765,7 +768,7 @@ impl Menu for ColumnarMenu {
 mod tests {
     use std::io::BufWriter;
 
-    use crate::{Span, UndoBehavior};
+    use crate::{HeadlessBackend, Span, UndoBehavior};
 
     use super::*;
 
@@ -879,6 +882,10 @@ mod tests {
         terminal_size: (u16, u16),
     ) {
         let mut painter = Painter::new(BufWriter::new(std::io::stderr()));
+        painter.set_backend(Box::new(HeadlessBackend::new(
+            terminal_size.0,
+            terminal_size.1,
+        )));
         painter.handle_resize(terminal_size.0, terminal_size.1);
 
         menu.menu_event(MenuEvent::Activate(false));
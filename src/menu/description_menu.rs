@@ -453,7 +453,11 @@ impl Menu for DescriptionMenu {
             self.input.as_deref(),
             self.settings.only_buffer_difference,
         );
-        self.values = completer.complete(&input, pos);
+        let values = completer.complete(&input, pos);
+        self.values = self
+            .settings
+            .match_algorithm
+            .apply(editor.get_buffer(), values);
 
         self.reset_position();
     }
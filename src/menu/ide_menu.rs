@@ -629,7 +629,10 @@ impl Menu for IdeMenu {
         );
         let (values, base_ranges) = completer.complete_with_base_ranges(&input, pos);
 
-        self.values = values;
+        self.values = self
+            .settings
+            .match_algorithm
+            .apply(editor.get_buffer(), values);
         self.working_details.shortest_base_string = base_ranges
             .iter()
             .map(|range| {
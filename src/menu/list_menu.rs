@@ -378,8 +378,12 @@ impl Menu for ListMenu {
 
             completer.partial_complete(&input, pos, skip, take)
         } else {
+            // Matches are only ranked when all of them are collected at once
             self.query_size = None;
-            completer.complete(&input, pos)
+            let values = completer.complete(&input, pos);
+            self.settings
+                .match_algorithm
+                .apply(editor.get_buffer(), values)
         }
     }
 
@@ -6,7 +6,9 @@ pub mod menu_functions;
 
 use crate::core_editor::Editor;
 use crate::History;
-use crate::{completion::history::HistoryCompleter, painting::Painter, Completer, Suggestion};
+use crate::{
+    completion::history::HistoryCompleter, painting::Painter, Completer, FuzzyMatcher, Suggestion,
+};
 pub use columnar_menu::ColumnarMenu;
 pub use columnar_menu::TraversalDirection;
 pub use description_menu::DescriptionMenu;
@@ -151,6 +153,28 @@ pub trait Menu: Send {
     }
 }
 
+/// How a menu matches the suggestions of its completer against the typed text
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MatchAlgorithm {
+    /// Keep the suggestions and their order as returned by the completer
+    #[default]
+    Completer,
+    /// Keep the suggestions that fuzzy match the text they replace, best match first
+    ///
+    /// See [`FuzzyMatcher`] for the scoring.
+    Fuzzy,
+}
+
+impl MatchAlgorithm {
+    /// Apply the algorithm to the suggestions completing `line`
+    pub(crate) fn apply(self, line: &str, suggestions: Vec<Suggestion>) -> Vec<Suggestion> {
+        match self {
+            MatchAlgorithm::Completer => suggestions,
+            MatchAlgorithm::Fuzzy => FuzzyMatcher::default().rank_suggestions(line, suggestions),
+        }
+    }
+}
+
 /// Struct to store configuration for a menu.
 pub struct MenuSettings {
     /// Menu name
@@ -162,6 +186,8 @@ pub struct MenuSettings {
     /// Calls the completer using only the line buffer difference difference
     /// after the menu was activated
     only_buffer_difference: bool,
+    /// How the suggestions are matched against the typed text
+    match_algorithm: MatchAlgorithm,
 }
 
 impl Default for MenuSettings {
@@ -171,6 +197,7 @@ impl Default for MenuSettings {
             color: MenuTextStyle::default(),
             marker: "| ".to_string(),
             only_buffer_difference: false,
+            match_algorithm: MatchAlgorithm::default(),
         }
     }
 }
@@ -203,6 +230,18 @@ impl MenuSettings {
         self.only_buffer_difference = only_buffer_difference;
         self
     }
+
+    /// MenuSettings builder with match_algorithm
+    #[must_use]
+    pub fn with_match_algorithm(mut self, match_algorithm: MatchAlgorithm) -> Self {
+        self.match_algorithm = match_algorithm;
+        self
+    }
+
+    /// How the suggestions are matched against the typed text
+    pub fn match_algorithm(&self) -> MatchAlgorithm {
+        self.match_algorithm
+    }
 }
 
 /// Common builder for all menus
@@ -270,6 +309,13 @@ pub trait MenuBuilder: Menu + Sized {
         self.settings_mut().only_buffer_difference = only_buffer_difference;
         self
     }
+
+    /// Menu builder with new value for match_algorithm
+    #[must_use]
+    fn with_match_algorithm(mut self, match_algorithm: MatchAlgorithm) -> Self {
+        self.settings_mut().match_algorithm = match_algorithm;
+        self
+    }
 }
 
 /// Allowed menus in Reedline
@@ -316,7 +362,8 @@ impl ReedlineMenu {
                 menu.can_partially_complete(values_updated, editor, completer)
             }
             Self::HistoryMenu(menu) => {
-                let mut history_completer = HistoryCompleter::new(history);
+                let mut history_completer = HistoryCompleter::new(history)
+                    .with_match_algorithm(menu.settings().match_algorithm());
                 menu.can_partially_complete(values_updated, editor, &mut history_completer)
             }
             Self::WithCompleter {
@@ -335,7 +382,8 @@ impl ReedlineMenu {
         match self {
             Self::EngineCompleter(menu) => menu.update_values(editor, completer),
             Self::HistoryMenu(menu) => {
-                let mut history_completer = HistoryCompleter::new(history);
+                let mut history_completer = HistoryCompleter::new(history)
+                    .with_match_algorithm(menu.settings().match_algorithm());
                 menu.update_values(editor, &mut history_completer);
             }
             Self::WithCompleter {
@@ -359,7 +407,8 @@ impl ReedlineMenu {
                 menu.update_working_details(editor, completer, painter);
             }
             Self::HistoryMenu(menu) => {
-                let mut history_completer = HistoryCompleter::new(history);
+                let mut history_completer = HistoryCompleter::new(history)
+                    .with_match_algorithm(menu.settings().match_algorithm());
                 menu.update_working_details(editor, &mut history_completer, painter);
             }
             Self::WithCompleter {
@@ -1,3 +1,4 @@
+use crate::terminal_backend::{CrosstermBackend, TerminalBackend};
 use crate::terminal_extensions::semantic_prompt::{PromptKind, SemanticPromptMarkers};
 use crate::{CursorConfig, PromptEditMode, PromptViMode};
 
@@ -6,12 +7,12 @@ use {
     crate::{
         menu::{Menu, ReedlineMenu},
         painting::PromptLines,
-        Prompt,
+        LineBuffer, Prompt,
     },
     crossterm::{
-        cursor::{self, MoveTo, RestorePosition, SavePosition},
+        cursor::{self, MoveTo, MoveUp, RestorePosition, SavePosition},
         style::{Attribute, Print, ResetColor, SetAttribute, SetForegroundColor},
-        terminal::{self, Clear, ClearType},
+        terminal::{Clear, ClearType},
         QueueableCommand,
     },
     std::io::{Result, Write},
@@ -19,8 +20,6 @@ use {
     unicode_segmentation::UnicodeSegmentation,
     unicode_width::UnicodeWidthStr,
 };
-#[cfg(feature = "external_printer")]
-use {crate::LineBuffer, crossterm::cursor::MoveUp};
 
 // Returns a string that skips N number of lines with the next offset of lines
 // An offset of 0 would return only one line after skipping the required lines
@@ -164,7 +163,7 @@ pub(crate) struct PromptLayout {
 /// Implementation of the output to the terminal
 pub struct Painter {
     // Stdout
-    stdout: W,
+    stdout: Box<dyn TerminalBackend>,
     prompt_start_row: u16,
     // The number of lines that the prompt takes up
     prompt_height: u16,
@@ -182,7 +181,7 @@ pub struct Painter {
 impl Painter {
     pub(crate) fn new(stdout: W) -> Self {
         Painter {
-            stdout,
+            stdout: Box::new(CrosstermBackend::new(stdout)),
             prompt_start_row: 0,
             prompt_height: 0,
             terminal_size: (0, 0),
@@ -195,6 +194,16 @@ impl Painter {
         }
     }
 
+    /// Replace the terminal the painter outputs to
+    pub(crate) fn set_backend(&mut self, backend: Box<dyn TerminalBackend>) {
+        self.stdout = backend;
+    }
+
+    /// The terminal the painter outputs to, also used to read input events
+    pub(crate) fn backend_mut(&mut self) -> &mut dyn TerminalBackend {
+        &mut *self.stdout
+    }
+
     /// Height of the current terminal window
     pub fn screen_height(&self) -> u16 {
         self.terminal_size.1
@@ -348,7 +357,7 @@ impl Painter {
     ) -> Result<()> {
         // Update the terminal size
         self.terminal_size = {
-            let size = terminal::size()?;
+            let size = self.stdout.size()?;
             // if reported size is 0, 0 -
             // use a default size to avoid divide by 0 panics
             if size == (0, 0) {
@@ -357,7 +366,7 @@ impl Painter {
                 size
             }
         };
-        let prompt_selector = select_prompt_row(suspended_state, cursor::position()?);
+        let prompt_selector = select_prompt_row(suspended_state, self.stdout.cursor_position()?);
         self.prompt_start_row = match prompt_selector {
             PromptRowSelector::UseExistingPrompt { start_row } => start_row,
             PromptRowSelector::MakeNewPrompt { new_row } => {
@@ -424,7 +433,7 @@ impl Painter {
         self.large_buffer = required_lines >= screen_height;
 
         // This might not be terribly performant. Testing it out
-        let is_reset = || match cursor::position() {
+        let mut is_reset = || match self.stdout.cursor_position() {
             // when output something without newline, the cursor position is at current line.
             // but the prompt_start_row is next line.
             // in this case we don't want to reset, need to `add 1` to handle for such case.
@@ -894,12 +903,9 @@ impl Painter {
         //
         // I assume this is a bug with the position() call but haven't figured that
         // out yet.
-        #[cfg(not(test))]
-        {
-            if let Ok(position) = cursor::position() {
-                self.prompt_start_row = position.1;
-                self.just_resized = true;
-            }
+        if let Ok(position) = self.stdout.cursor_position() {
+            self.prompt_start_row = position.1;
+            self.just_resized = true;
         }
     }
 
@@ -952,7 +958,6 @@ impl Painter {
     ///
     /// This function doesn't flush the buffer. So buffer should be flushed
     /// afterwards perhaps by repainting the prompt via `repaint_buffer()`.
-    #[cfg(feature = "external_printer")]
     pub(crate) fn print_external_message(
         &mut self,
         messages: Vec<String>,
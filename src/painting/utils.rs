@@ -17,11 +17,12 @@ pub(crate) fn coerce_crlf(input: &str) -> Cow<'_, str> {
                 // optimization for `Cow<str>.is_empty` that would replace the
                 // preallocation
                 owned.push_str(&input[cursor..idx]);
+                owned.push_str("\r\n");
                 result = Cow::Owned(owned);
             } else {
                 result += &input[cursor..idx];
+                result += "\r\n";
             }
-            result += "\r\n";
             // Advance beyond the matched LF char (single byte)
             cursor = idx + 1;
         }
@@ -83,6 +84,7 @@ mod test {
     #[case("😇\nsentence", "😇\r\nsentence")]
     #[case("sentence\n😇", "sentence\r\n😇")]
     #[case("\n", "\r\n")]
+    #[case("\nsentence\nsentence", "\r\nsentence\r\nsentence")]
     #[case("", "")]
     fn test_coerce_crlf(#[case] input: &str, #[case] expected: &str) {
         let result = coerce_crlf(input);
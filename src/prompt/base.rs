@@ -98,6 +98,18 @@ pub trait Prompt: Send {
         &self,
         history_search: PromptHistorySearch,
     ) -> Cow<'_, str>;
+    /// Render the keys typed so far of an incomplete key sequence binding, like `C-x`
+    ///
+    /// Shown in front of the prompt indicator until the sequence is complete.
+    fn render_prompt_pending_keys(&self, pending_keys: &str) -> Cow<'_, str> {
+        Cow::Owned(format!("{pending_keys} "))
+    }
+    /// Render the numeric argument typed for the next command
+    ///
+    /// Shown in front of the prompt indicator until the command is typed.
+    fn render_prompt_numeric_argument(&self, argument: i64) -> Cow<'_, str> {
+        Cow::Owned(format!("(arg: {argument}) "))
+    }
     /// Get the default prompt color
     fn get_prompt_color(&self) -> Color {
         DEFAULT_PROMPT_COLOR
@@ -10,6 +10,28 @@ pub enum ReedlineErrorVariants {
     #[error("error within history database: {0}")]
     HistoryDatabaseError(String),
 
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    /// The history database was created by a newer version of reedline
+    #[error("the history database has schema version {current}, but only versions up to {supported} are supported")]
+    HistoryDatabaseTooNew {
+        /// Schema version of the database
+        current: i32,
+
+        /// Latest schema version known to this version of reedline
+        supported: i32,
+    },
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    /// Upgrading the history database failed, it is left at the previous version
+    #[error("migrating the history database to schema version {version} failed: {message}")]
+    HistoryDatabaseMigrationFailed {
+        /// Schema version the migration upgrades to
+        version: i32,
+
+        /// Error of the database
+        message: String,
+    },
+
     /// Error within history
     #[error("error in Reedline history: {0}")]
     OtherHistoryError(&'static str),
@@ -24,6 +46,15 @@ pub enum ReedlineErrorVariants {
         feature: &'static str,
     },
 
+    #[cfg(feature = "keybindings_config")]
+    /// Error in a keybinding configuration
+    #[error("invalid keybinding configuration: {0}")]
+    KeybindingConfigError(String),
+
+    /// Invalid regular expression
+    #[error("invalid regular expression: {0}")]
+    InvalidRegex(String),
+
     /// I/O error
     #[error("I/O error: {0}")]
     IOError(std::io::Error),
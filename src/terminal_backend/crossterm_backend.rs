@@ -0,0 +1,62 @@
+use {
+    super::TerminalBackend,
+    crossterm::{cursor, event, event::Event, terminal},
+    std::{
+        io::{BufWriter, Result, Stderr, Write},
+        time::Duration,
+    },
+};
+
+/// Backend for the real terminal, painting to stderr and reading events through crossterm
+pub struct CrosstermBackend {
+    stdout: BufWriter<Stderr>,
+}
+
+impl CrosstermBackend {
+    /// Construct a backend painting to the given buffered stderr
+    pub fn new(stdout: BufWriter<Stderr>) -> Self {
+        CrosstermBackend { stdout }
+    }
+}
+
+impl Default for CrosstermBackend {
+    fn default() -> Self {
+        Self::new(BufWriter::new(std::io::stderr()))
+    }
+}
+
+impl Write for CrosstermBackend {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.stdout.write(buf)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.stdout.flush()
+    }
+}
+
+impl TerminalBackend for CrosstermBackend {
+    fn size(&mut self) -> Result<(u16, u16)> {
+        terminal::size()
+    }
+
+    fn cursor_position(&mut self) -> Result<(u16, u16)> {
+        cursor::position()
+    }
+
+    fn enable_raw_mode(&mut self) -> Result<()> {
+        terminal::enable_raw_mode()
+    }
+
+    fn disable_raw_mode(&mut self) -> Result<()> {
+        terminal::disable_raw_mode()
+    }
+
+    fn poll_event(&mut self, timeout: Duration) -> Result<bool> {
+        event::poll(timeout)
+    }
+
+    fn read_event(&mut self) -> Result<Event> {
+        event::read()
+    }
+}
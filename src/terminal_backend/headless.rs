@@ -0,0 +1,172 @@
+use {
+    super::{screen::VirtualScreen, TerminalBackend},
+    crate::ReedlineRawEvent,
+    crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    std::{
+        collections::VecDeque,
+        io::{Error, ErrorKind, Result, Write},
+        sync::{Arc, Mutex, MutexGuard},
+        time::Duration,
+    },
+};
+
+struct HeadlessState {
+    screen: VirtualScreen,
+    events: VecDeque<Event>,
+    raw_mode: bool,
+}
+
+/// Terminal backend that runs without a real terminal
+///
+/// Input events are taken from a scripted queue and the output is rendered into an
+/// in-memory screen grid that can be inspected afterwards. Cloning the backend returns a
+/// handle to the same terminal, so one clone can be passed to
+/// [`crate::Reedline::with_terminal_backend`] while another one is used to feed events and
+/// assert on the screen.
+///
+/// Once all scripted events are consumed, reading the next event fails with
+/// [`ErrorKind::UnexpectedEof`] instead of blocking.
+///
+/// # Example
+/// ```rust
+/// use reedline::{DefaultPrompt, DefaultPromptSegment, HeadlessBackend, KeyCode, KeyModifiers, Reedline, Signal};
+///
+/// let terminal = HeadlessBackend::new(40, 5);
+/// let mut line_editor = Reedline::create().with_terminal_backend(Box::new(terminal.clone()));
+/// let prompt = DefaultPrompt::new(
+///     DefaultPromptSegment::Basic("~".to_string()),
+///     DefaultPromptSegment::Empty,
+/// );
+///
+/// terminal.type_str("hello");
+/// terminal.push_key(KeyCode::Enter, KeyModifiers::NONE);
+///
+/// let signal = line_editor.read_line(&prompt).unwrap();
+/// assert!(matches!(signal, Signal::Success(line) if line == "hello"));
+/// assert_eq!(terminal.screen_lines()[0], "~〉hello");
+/// ```
+#[derive(Clone)]
+pub struct HeadlessBackend {
+    state: Arc<Mutex<HeadlessState>>,
+}
+
+impl HeadlessBackend {
+    /// Create a headless terminal with the given number of columns and rows
+    pub fn new(width: u16, height: u16) -> Self {
+        HeadlessBackend {
+            state: Arc::new(Mutex::new(HeadlessState {
+                screen: VirtualScreen::new(width, height),
+                events: VecDeque::new(),
+                raw_mode: false,
+            })),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, HeadlessState> {
+        // A panic while holding the lock leaves the screen in a usable state
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Queue an input event
+    pub fn push_event(&self, event: ReedlineRawEvent) {
+        self.state().events.push_back(event.into());
+    }
+
+    /// Queue a key press
+    pub fn push_key(&self, code: KeyCode, modifiers: KeyModifiers) {
+        self.state()
+            .events
+            .push_back(Event::Key(KeyEvent::new(code, modifiers)));
+    }
+
+    /// Queue key presses typing out the given text
+    pub fn type_str(&self, text: &str) {
+        let mut state = self.state();
+        for c in text.chars() {
+            state.events.push_back(Event::Key(KeyEvent::new(
+                KeyCode::Char(c),
+                KeyModifiers::NONE,
+            )));
+        }
+    }
+
+    /// Change the size of the terminal and queue the matching resize event
+    pub fn resize(&self, width: u16, height: u16) {
+        let mut state = self.state();
+        state.screen.resize(width, height);
+        state.events.push_back(Event::Resize(width, height));
+    }
+
+    /// Number of queued input events that have not been read yet
+    pub fn pending_events(&self) -> usize {
+        self.state().events.len()
+    }
+
+    /// Text of every row of the screen with trailing whitespace removed
+    ///
+    /// Styling is not preserved.
+    pub fn screen_lines(&self) -> Vec<String> {
+        self.state().screen.lines()
+    }
+
+    /// Current position of the cursor as `(column, row)`
+    pub fn cursor(&self) -> (u16, u16) {
+        self.state().screen.cursor()
+    }
+
+    /// Whether the cursor is currently shown
+    pub fn is_cursor_visible(&self) -> bool {
+        self.state().screen.cursor_visible()
+    }
+
+    /// Whether the terminal is in raw mode
+    pub fn is_raw_mode_enabled(&self) -> bool {
+        self.state().raw_mode
+    }
+}
+
+impl Write for HeadlessBackend {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.state().screen.write(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl TerminalBackend for HeadlessBackend {
+    fn size(&mut self) -> Result<(u16, u16)> {
+        Ok(self.state().screen.size())
+    }
+
+    fn cursor_position(&mut self) -> Result<(u16, u16)> {
+        Ok(self.state().screen.cursor())
+    }
+
+    fn enable_raw_mode(&mut self) -> Result<()> {
+        self.state().raw_mode = true;
+        Ok(())
+    }
+
+    fn disable_raw_mode(&mut self) -> Result<()> {
+        self.state().raw_mode = false;
+        Ok(())
+    }
+
+    fn poll_event(&mut self, _timeout: Duration) -> Result<bool> {
+        Ok(!self.state().events.is_empty())
+    }
+
+    fn read_event(&mut self) -> Result<Event> {
+        self.state().events.pop_front().ok_or_else(|| {
+            Error::new(
+                ErrorKind::UnexpectedEof,
+                "no more scripted events for the headless terminal",
+            )
+        })
+    }
+}
@@ -0,0 +1,41 @@
+mod crossterm_backend;
+mod headless;
+mod screen;
+
+pub use crossterm_backend::CrosstermBackend;
+pub use headless::HeadlessBackend;
+
+use {
+    crossterm::event::Event,
+    std::{
+        io::{Result, Write},
+        time::Duration,
+    },
+};
+
+/// The terminal [`crate::Reedline`] reads its input events from and paints to.
+///
+/// The default [`CrosstermBackend`] talks to the real terminal. Alternative implementations
+/// allow running the line editor without a terminal, e.g. [`HeadlessBackend`] for testing.
+///
+/// All output is written through the [`Write`] implementation and is expected to be
+/// interpreted like a VT100 compatible terminal would.
+pub trait TerminalBackend: Write + Send {
+    /// Size of the terminal as `(columns, rows)`
+    fn size(&mut self) -> Result<(u16, u16)>;
+
+    /// Current position of the cursor as `(column, row)`, both starting at zero
+    fn cursor_position(&mut self) -> Result<(u16, u16)>;
+
+    /// Switch the terminal into raw mode, so input is passed on unprocessed
+    fn enable_raw_mode(&mut self) -> Result<()>;
+
+    /// Restore the regular (cooked) mode of the terminal
+    fn disable_raw_mode(&mut self) -> Result<()>;
+
+    /// Check if an input event is available within `timeout`
+    fn poll_event(&mut self, timeout: Duration) -> Result<bool>;
+
+    /// Read the next input event, blocking until one is available
+    fn read_event(&mut self) -> Result<Event>;
+}
@@ -0,0 +1,408 @@
+use {
+    std::ops::{Bound, RangeBounds},
+    unicode_width::UnicodeWidthChar,
+};
+
+/// Marks the second cell covered by a double width character
+const WIDE_CONTINUATION: char = '\0';
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ParserState {
+    Ground,
+    Escape,
+    Csi(String),
+    // Operating system command (e.g. OSC 133), the flag tracks a pending `ESC` of the `ST`
+    Osc(bool),
+}
+
+/// In-memory grid of terminal cells, updated by interpreting the output reedline emits
+///
+/// Only understands the subset of VT100/xterm control sequences needed to follow the
+/// cursor movements and clears of the painter. Styling is discarded.
+#[derive(Debug, Clone)]
+pub(crate) struct VirtualScreen {
+    width: u16,
+    height: u16,
+    cells: Vec<Vec<char>>,
+    cursor: (u16, u16),
+    saved_cursor: (u16, u16),
+    // Set after printing in the last column, the next printed character wraps
+    pending_wrap: bool,
+    cursor_visible: bool,
+    state: ParserState,
+    // Incomplete UTF-8 sequence left over from the last write
+    partial: Vec<u8>,
+}
+
+impl VirtualScreen {
+    pub fn new(width: u16, height: u16) -> Self {
+        let width = width.max(1);
+        let height = height.max(1);
+        VirtualScreen {
+            width,
+            height,
+            cells: vec![vec![' '; width as usize]; height as usize],
+            cursor: (0, 0),
+            saved_cursor: (0, 0),
+            pending_wrap: false,
+            cursor_visible: true,
+            state: ParserState::Ground,
+            partial: Vec::new(),
+        }
+    }
+
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    pub fn cursor(&self) -> (u16, u16) {
+        self.cursor
+    }
+
+    pub fn cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    /// Text of every row with trailing whitespace removed
+    pub fn lines(&self) -> Vec<String> {
+        self.cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .filter(|c| **c != WIDE_CONTINUATION)
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    /// Change the dimensions, keeping the content anchored at the top left
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.width = width.max(1);
+        self.height = height.max(1);
+        self.cells
+            .resize(self.height as usize, vec![' '; self.width as usize]);
+        for row in &mut self.cells {
+            row.resize(self.width as usize, ' ');
+        }
+        self.cursor = self.clamp(self.cursor);
+        self.saved_cursor = self.clamp(self.saved_cursor);
+        self.pending_wrap = false;
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        self.partial.extend_from_slice(bytes);
+        let mut pending = std::mem::take(&mut self.partial);
+        loop {
+            match std::str::from_utf8(&pending) {
+                Ok(text) => {
+                    text.chars().for_each(|c| self.process(c));
+                    return;
+                }
+                Err(err) => {
+                    let (valid, rest) = pending.split_at(err.valid_up_to());
+                    String::from_utf8_lossy(valid)
+                        .chars()
+                        .for_each(|c| self.process(c));
+                    match err.error_len() {
+                        // Sequence split across writes, wait for the remaining bytes
+                        None => {
+                            self.partial = rest.to_vec();
+                            return;
+                        }
+                        Some(len) => {
+                            self.process(char::REPLACEMENT_CHARACTER);
+                            pending = rest[len..].to_vec();
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn process(&mut self, c: char) {
+        match std::mem::replace(&mut self.state, ParserState::Ground) {
+            ParserState::Ground => match c {
+                '\x1b' => self.state = ParserState::Escape,
+                '\r' => {
+                    self.cursor.0 = 0;
+                    self.pending_wrap = false;
+                }
+                '\n' => {
+                    self.line_feed();
+                    self.pending_wrap = false;
+                }
+                '\x08' => {
+                    self.cursor.0 = self.cursor.0.saturating_sub(1);
+                    self.pending_wrap = false;
+                }
+                '\t' => {
+                    self.cursor.0 = ((self.cursor.0 / 8 + 1) * 8).min(self.width - 1);
+                }
+                c if c.is_control() => {}
+                c => self.print(c),
+            },
+            ParserState::Escape => match c {
+                '[' => self.state = ParserState::Csi(String::new()),
+                ']' | 'P' | '_' | '^' => self.state = ParserState::Osc(false),
+                '7' => self.saved_cursor = self.cursor,
+                '8' => {
+                    self.cursor = self.saved_cursor;
+                    self.pending_wrap = false;
+                }
+                _ => {}
+            },
+            ParserState::Csi(mut sequence) => {
+                if ('\x40'..='\x7e').contains(&c) {
+                    self.execute_csi(&sequence, c);
+                } else {
+                    sequence.push(c);
+                    self.state = ParserState::Csi(sequence);
+                }
+            }
+            ParserState::Osc(pending_escape) => {
+                let terminated = c == '\x07' || (pending_escape && c == '\\');
+                if !terminated {
+                    self.state = ParserState::Osc(c == '\x1b');
+                }
+            }
+        }
+    }
+
+    fn execute_csi(&mut self, sequence: &str, action: char) {
+        // Private modes (`?25l`), cursor styles (`2 q`) and styling (`m`) don't affect the grid
+        if let Some(mode) = sequence.strip_prefix('?') {
+            if mode == "25" {
+                self.cursor_visible = action == 'h';
+            }
+            return;
+        }
+        if sequence.contains(|c: char| !c.is_ascii_digit() && c != ';') {
+            return;
+        }
+
+        let params: Vec<u16> = sequence
+            .split(';')
+            .map(|param| param.parse().unwrap_or(0))
+            .collect();
+        let param = |index: usize| params.get(index).copied().unwrap_or(0);
+        // Movement amounts of zero are treated as one
+        let amount = param(0).max(1);
+        let (column, row) = self.cursor;
+
+        match action {
+            'A' => self.move_to(column, row.saturating_sub(amount)),
+            'B' => self.move_to(column, row.saturating_add(amount)),
+            'C' => self.move_to(column.saturating_add(amount), row),
+            'D' => self.move_to(column.saturating_sub(amount), row),
+            'E' => self.move_to(0, row.saturating_add(amount)),
+            'F' => self.move_to(0, row.saturating_sub(amount)),
+            'G' => self.move_to(amount - 1, row),
+            'd' => self.move_to(column, amount - 1),
+            'H' | 'f' => self.move_to(param(1).max(1) - 1, amount - 1),
+            'J' => match param(0) {
+                0 => {
+                    self.clear_row(row, column as usize..);
+                    for row in row + 1..self.height {
+                        self.clear_row(row, ..);
+                    }
+                }
+                1 => {
+                    for row in 0..row {
+                        self.clear_row(row, ..);
+                    }
+                    self.clear_row(row, ..=column as usize);
+                }
+                _ => {
+                    for row in 0..self.height {
+                        self.clear_row(row, ..);
+                    }
+                }
+            },
+            'K' => match param(0) {
+                0 => self.clear_row(row, column as usize..),
+                1 => self.clear_row(row, ..=column as usize),
+                _ => self.clear_row(row, ..),
+            },
+            'S' => self.scroll_up(amount),
+            'T' => self.scroll_down(amount),
+            _ => {}
+        }
+    }
+
+    fn print(&mut self, c: char) {
+        let (c, char_width) = match c.width().unwrap_or(0) as u16 {
+            0 => return,
+            // A wide character doesn't fit on a single column screen
+            width if width > self.width => (char::REPLACEMENT_CHARACTER, 1),
+            width => (c, width),
+        };
+        if self.pending_wrap || self.cursor.0 + char_width > self.width {
+            self.cursor.0 = 0;
+            self.line_feed();
+        }
+        self.pending_wrap = false;
+
+        let (column, row) = (self.cursor.0 as usize, self.cursor.1 as usize);
+        self.cells[row][column] = c;
+        if char_width == 2 {
+            self.cells[row][column + 1] = WIDE_CONTINUATION;
+        }
+
+        let next_column = self.cursor.0 + char_width;
+        if next_column >= self.width {
+            self.cursor.0 = self.width - 1;
+            self.pending_wrap = true;
+        } else {
+            self.cursor.0 = next_column;
+        }
+    }
+
+    fn line_feed(&mut self) {
+        if self.cursor.1 + 1 >= self.height {
+            self.scroll_up(1);
+        } else {
+            self.cursor.1 += 1;
+        }
+    }
+
+    fn scroll_up(&mut self, lines: u16) {
+        for _ in 0..lines.min(self.height) {
+            self.cells.remove(0);
+            self.cells.push(vec![' '; self.width as usize]);
+        }
+    }
+
+    fn scroll_down(&mut self, lines: u16) {
+        for _ in 0..lines.min(self.height) {
+            self.cells.pop();
+            self.cells.insert(0, vec![' '; self.width as usize]);
+        }
+    }
+
+    fn clear_row(&mut self, row: u16, range: impl RangeBounds<usize>) {
+        let row = &mut self.cells[row as usize];
+        let len = row.len();
+        let start = match range.start_bound() {
+            Bound::Included(start) => *start,
+            Bound::Excluded(start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => end + 1,
+            Bound::Excluded(end) => *end,
+            Bound::Unbounded => len,
+        };
+        for cell in &mut row[start.min(len)..end.min(len)] {
+            *cell = ' ';
+        }
+    }
+
+    fn move_to(&mut self, column: u16, row: u16) {
+        self.cursor = self.clamp((column, row));
+        self.pending_wrap = false;
+    }
+
+    fn clamp(&self, (column, row): (u16, u16)) -> (u16, u16) {
+        (column.min(self.width - 1), row.min(self.height - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::{
+        cursor::{MoveTo, MoveToColumn, MoveUp, RestorePosition, SavePosition},
+        style::{Color, Print, SetForegroundColor},
+        terminal::{Clear, ClearType},
+        QueueableCommand,
+    };
+    use pretty_assertions::assert_eq;
+
+    fn screen_after(width: u16, height: u16, f: impl FnOnce(&mut Vec<u8>)) -> VirtualScreen {
+        let mut output = Vec::new();
+        f(&mut output);
+        let mut screen = VirtualScreen::new(width, height);
+        screen.write(&output);
+        screen
+    }
+
+    #[test]
+    fn prints_text_and_moves_cursor() {
+        let screen = screen_after(10, 3, |out| {
+            out.queue(Print("ab\r\ncd")).unwrap();
+        });
+        assert_eq!(screen.lines(), vec!["ab", "cd", ""]);
+        assert_eq!(screen.cursor(), (2, 1));
+    }
+
+    #[test]
+    fn wraps_and_scrolls_at_the_edges() {
+        let screen = screen_after(3, 2, |out| {
+            out.queue(Print("abcdefgh")).unwrap();
+        });
+        assert_eq!(screen.lines(), vec!["def", "gh"]);
+        assert_eq!(screen.cursor(), (2, 1));
+    }
+
+    #[test]
+    fn delays_wrap_until_next_character() {
+        let screen = screen_after(3, 2, |out| {
+            out.queue(Print("abc\r\nd")).unwrap();
+        });
+        assert_eq!(screen.lines(), vec!["abc", "d"]);
+    }
+
+    #[test]
+    fn understands_cursor_movement_and_clears() {
+        let screen = screen_after(10, 3, |out| {
+            out.queue(Print("hello\r\nworld"))
+                .unwrap()
+                .queue(SavePosition)
+                .unwrap()
+                .queue(MoveUp(1))
+                .unwrap()
+                .queue(MoveToColumn(2))
+                .unwrap()
+                .queue(Clear(ClearType::UntilNewLine))
+                .unwrap()
+                .queue(RestorePosition)
+                .unwrap()
+                .queue(MoveTo(0, 2))
+                .unwrap()
+                .queue(Print("!"))
+                .unwrap();
+        });
+        assert_eq!(screen.lines(), vec!["he", "world", "!"]);
+        assert_eq!(screen.cursor(), (1, 2));
+    }
+
+    #[test]
+    fn ignores_styling_and_osc_sequences() {
+        let screen = screen_after(10, 1, |out| {
+            out.queue(SetForegroundColor(Color::Red))
+                .unwrap()
+                .queue(Print("\x1b]133;A\x1b\\a\x1b]0;title\x07b"))
+                .unwrap();
+        });
+        assert_eq!(screen.lines(), vec!["ab"]);
+    }
+
+    #[test]
+    fn handles_wide_characters_and_split_utf8() {
+        let mut screen = VirtualScreen::new(4, 2);
+        let bytes = "日本".as_bytes();
+        screen.write(&bytes[..2]);
+        screen.write(&bytes[2..]);
+        assert_eq!(screen.lines(), vec!["日本", ""]);
+        assert_eq!(screen.cursor(), (3, 0));
+    }
+
+    #[test]
+    fn replaces_wide_characters_wider_than_the_screen() {
+        let mut screen = VirtualScreen::new(1, 2);
+        screen.write("a日".as_bytes());
+        assert_eq!(screen.lines(), vec!["a", "\u{FFFD}"]);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     default_emacs_keybindings, default_vi_insert_keybindings, default_vi_normal_keybindings,
-    EditCommand, Keybindings, PromptEditMode, ReedlineEvent,
+    edit_mode::key_sequence_to_string, EditCommand, Keybindings, PromptEditMode, ReedlineEvent,
 };
 use crossterm::event::KeyCode;
 use std::fmt::{Display, Formatter};
@@ -142,6 +142,19 @@ fn get_keybinding_strings(
                 format!("{event:?}"),
             )
         })
+        .chain(
+            keybindings
+                .get_sequence_bindings()
+                .into_iter()
+                .map(|(sequence, event)| {
+                    (
+                        mode.to_string(),
+                        "KeySequence".to_string(),
+                        key_sequence_to_string(&sequence),
+                        format!("{event:?}"),
+                    )
+                }),
+        )
         .collect();
 
     data.sort();